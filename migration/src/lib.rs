@@ -5,6 +5,31 @@ mod m20220101_000001_users;
 
 mod m20260220_090748_projects;
 mod m20260220_091028_repos;
+mod m20261019_101500_add_forge_and_releases_to_repos;
+mod m20261019_120000_add_host_to_repos;
+mod m20261019_140000_activity_events;
+mod m20261019_140100_webhook_deliveries;
+mod m20261019_160000_sync_runs;
+mod m20261019_160100_add_sync_status_to_repos;
+mod m20261019_180000_add_forge_id_and_archived_to_repos;
+mod m20261019_200000_unique_repos;
+mod m20261019_220000_project_members;
+mod m20261020_090000_api_keys;
+mod m20261020_110000_user_identities;
+mod m20261020_130000_email_domain_rules;
+mod m20261020_150000_sessions;
+mod m20261020_170000_two_factor;
+mod m20261020_190000_auth_throttles;
+mod m20261020_190100_audit_events;
+mod m20261020_210000_email_changes;
+mod m20261020_230000_data_exports;
+mod m20261021_090000_add_is_admin_to_users;
+mod m20261021_090100_health_configs;
+mod m20261021_110000_add_changes_to_audit_events;
+mod m20261021_130000_add_health_strategy_to_projects;
+mod m20261021_150000_health_distributions;
+mod m20261021_170000_remove_api_key_from_users;
+mod m20261021_190000_add_local_path_to_repos;
 pub struct Migrator;
 
 #[async_trait::async_trait]
@@ -14,6 +39,31 @@ impl MigratorTrait for Migrator {
             Box::new(m20220101_000001_users::Migration),
             Box::new(m20260220_090748_projects::Migration),
             Box::new(m20260220_091028_repos::Migration),
+            Box::new(m20261019_101500_add_forge_and_releases_to_repos::Migration),
+            Box::new(m20261019_120000_add_host_to_repos::Migration),
+            Box::new(m20261019_140000_activity_events::Migration),
+            Box::new(m20261019_140100_webhook_deliveries::Migration),
+            Box::new(m20261019_160000_sync_runs::Migration),
+            Box::new(m20261019_160100_add_sync_status_to_repos::Migration),
+            Box::new(m20261019_180000_add_forge_id_and_archived_to_repos::Migration),
+            Box::new(m20261019_200000_unique_repos::Migration),
+            Box::new(m20261019_220000_project_members::Migration),
+            Box::new(m20261020_090000_api_keys::Migration),
+            Box::new(m20261020_110000_user_identities::Migration),
+            Box::new(m20261020_130000_email_domain_rules::Migration),
+            Box::new(m20261020_150000_sessions::Migration),
+            Box::new(m20261020_170000_two_factor::Migration),
+            Box::new(m20261020_190000_auth_throttles::Migration),
+            Box::new(m20261020_190100_audit_events::Migration),
+            Box::new(m20261020_210000_email_changes::Migration),
+            Box::new(m20261020_230000_data_exports::Migration),
+            Box::new(m20261021_090000_add_is_admin_to_users::Migration),
+            Box::new(m20261021_090100_health_configs::Migration),
+            Box::new(m20261021_110000_add_changes_to_audit_events::Migration),
+            Box::new(m20261021_130000_add_health_strategy_to_projects::Migration),
+            Box::new(m20261021_150000_health_distributions::Migration),
+            Box::new(m20261021_170000_remove_api_key_from_users::Migration),
+            Box::new(m20261021_190000_add_local_path_to_repos::Migration),
             // inject-above (do not remove this comment)
         ]
     }
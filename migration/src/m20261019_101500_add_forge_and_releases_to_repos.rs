@@ -0,0 +1,24 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(
+            m,
+            "repos",
+            "forge",
+            ColType::StringWithDefault("github".to_string()),
+        )
+        .await?;
+        add_column(m, "repos", "releases", ColType::IntegerWithDefault(0)).await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        remove_column(m, "repos", "releases").await?;
+        remove_column(m, "repos", "forge").await
+    }
+}
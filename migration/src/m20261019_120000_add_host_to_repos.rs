@@ -0,0 +1,26 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(
+            m,
+            "repos",
+            "host",
+            ColType::StringWithDefault("github.com".to_string()),
+        )
+        .await?;
+        m.get_connection()
+            .execute_unprepared("UPDATE repos SET host = 'gitlab.com' WHERE forge = 'gitlab'")
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        remove_column(m, "repos", "host").await
+    }
+}
@@ -0,0 +1,30 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "activity_events",
+            &[
+                ("id", ColType::PkAuto),
+                ("kind", ColType::String),
+                ("action", ColType::StringNull),
+                ("actor", ColType::StringNull),
+                ("title", ColType::TextNull),
+                ("url", ColType::StringNull),
+                ("occurred_at", ColType::TimestampWithTimeZone),
+            ],
+            &[("repo", "")],
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "activity_events").await
+    }
+}
@@ -0,0 +1,33 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "sync_runs",
+            &[
+                ("id", ColType::PkAuto),
+                ("forge", ColType::String),
+                ("host", ColType::String),
+                ("owner", ColType::String),
+                ("name", ColType::String),
+                ("started_at", ColType::TimestampWithTimeZone),
+                ("finished_at", ColType::TimestampWithTimeZone),
+                ("api_calls", ColType::IntegerWithDefault(0)),
+                ("outcome", ColType::String),
+                ("error", ColType::TextNull),
+            ],
+            &[("repo?", "")],
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "sync_runs").await
+    }
+}
@@ -0,0 +1,24 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(
+            m,
+            "repos",
+            "sync_status",
+            ColType::StringWithDefault("ok".to_string()),
+        )
+        .await?;
+        add_column(m, "repos", "failed_syncs", ColType::IntegerWithDefault(0)).await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        remove_column(m, "repos", "failed_syncs").await?;
+        remove_column(m, "repos", "sync_status").await
+    }
+}
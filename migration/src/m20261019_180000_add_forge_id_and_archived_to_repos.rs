@@ -0,0 +1,18 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(m, "repos", "forge_id", ColType::BigIntegerNull).await?;
+        add_column(m, "repos", "archived", ColType::BooleanWithDefault(false)).await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        remove_column(m, "repos", "archived").await?;
+        remove_column(m, "repos", "forge_id").await
+    }
+}
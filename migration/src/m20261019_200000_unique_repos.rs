@@ -0,0 +1,65 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const INDEX: &str = "idx-repos-forge-host-owner-name";
+
+/// The row of each repo that is kept: the one fetched last among the rows
+/// with the same owner and name ignoring case, like forges match them.
+const KEEPER: &str = "SELECT k.id FROM repos k
+    WHERE k.forge = r.forge AND k.host = r.host
+        AND lower(k.owner) = lower(r.owner) AND lower(k.name) = lower(r.name)
+    ORDER BY k.last_fetch DESC, k.id DESC LIMIT 1";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        // Duplicates are merged the way `repos::Entity::merge_duplicates`
+        // does before the index can exist. That runs on the final schema, so
+        // the `merge_duplicate_repos` task can not run in the middle of the
+        // migrations; this does the part the index needs and leaves the
+        // projects the merged rows leave empty to the task.
+        let db = m.get_connection();
+        for table in ["activity_events", "sync_runs"] {
+            db.execute_unprepared(&format!(
+                "UPDATE {table} SET repo_id = (
+                    SELECT ({KEEPER}) FROM repos r WHERE r.id = {table}.repo_id
+                ) WHERE repo_id IS NOT NULL"
+            ))
+            .await?;
+        }
+        // keep the stable id when only a merged row had it
+        db.execute_unprepared(&format!(
+            "UPDATE repos SET forge_id = (
+                SELECT r.forge_id FROM repos r
+                WHERE r.forge_id IS NOT NULL AND ({KEEPER}) = repos.id
+                ORDER BY r.last_fetch DESC LIMIT 1
+            ) WHERE forge_id IS NULL"
+        ))
+        .await?;
+        db.execute_unprepared(&format!(
+            "DELETE FROM repos WHERE id <> (
+                SELECT ({KEEPER}) FROM repos r WHERE r.id = repos.id
+            )"
+        ))
+        .await?;
+
+        db.execute_unprepared(&format!(
+            r#"CREATE UNIQUE INDEX "{INDEX}"
+                ON repos (forge, host, lower(owner), lower(name))"#
+        ))
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        m.drop_index(
+            Index::drop()
+                .name(INDEX)
+                .table(Alias::new("repos"))
+                .to_owned(),
+        )
+        .await
+    }
+}
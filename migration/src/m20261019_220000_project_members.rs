@@ -0,0 +1,35 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const INDEX: &str = "idx-project_members-project-user";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "project_members",
+            &[("id", ColType::PkAuto), ("role", ColType::String)],
+            &[("project", ""), ("user", "")],
+        )
+        .await?;
+
+        m.create_index(
+            Index::create()
+                .name(INDEX)
+                .table(Alias::new("project_members"))
+                .col(Alias::new("project_id"))
+                .col(Alias::new("user_id"))
+                .unique()
+                .to_owned(),
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "project_members").await
+    }
+}
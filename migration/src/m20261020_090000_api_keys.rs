@@ -0,0 +1,42 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "api_keys",
+            &[
+                ("id", ColType::PkAuto),
+                ("name", ColType::String),
+                ("key_hash", ColType::StringUniq),
+                ("prefix", ColType::String),
+                ("scopes", ColType::String),
+                ("last_used_at", ColType::TimestampWithTimeZoneNull),
+                ("revoked_at", ColType::TimestampWithTimeZoneNull),
+            ],
+            &[("user", "")],
+        )
+        .await?;
+
+        // The key every user already has keeps working, with every scope,
+        // until it is revoked.
+        m.get_connection()
+            .execute_unprepared(
+                "INSERT INTO api_keys (name, key_hash, prefix, scopes, user_id)
+                SELECT 'default', encode(sha256(convert_to(api_key, 'UTF8')), 'hex'),
+                    left(api_key, 11), 'projects repos members', id
+                FROM users",
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "api_keys").await
+    }
+}
@@ -0,0 +1,49 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "user_identities",
+            &[
+                ("id", ColType::PkAuto),
+                ("provider", ColType::String),
+                ("provider_user_id", ColType::String),
+                ("login", ColType::String),
+            ],
+            &[("user", "")],
+        )
+        .await?;
+
+        // An account on a provider belongs to one user, and a user links at
+        // most one account per provider.
+        for (name, col) in [
+            (
+                "idx-user_identities-provider-provider_user_id",
+                "provider_user_id",
+            ),
+            ("idx-user_identities-provider-user", "user_id"),
+        ] {
+            m.create_index(
+                Index::create()
+                    .name(name)
+                    .table(Alias::new("user_identities"))
+                    .col(Alias::new("provider"))
+                    .col(Alias::new(col))
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "user_identities").await
+    }
+}
@@ -0,0 +1,39 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "sessions",
+            &[
+                ("id", ColType::PkAuto),
+                ("refresh_hash", ColType::StringUniq),
+                ("previous_hash", ColType::StringNull),
+                ("user_agent", ColType::StringNull),
+                ("expires_at", ColType::TimestampWithTimeZone),
+                ("last_used_at", ColType::TimestampWithTimeZoneNull),
+                ("revoked_at", ColType::TimestampWithTimeZoneNull),
+            ],
+            &[("user", "")],
+        )
+        .await?;
+
+        m.create_index(
+            Index::create()
+                .name("idx-sessions-previous_hash")
+                .table(Alias::new("sessions"))
+                .col(Alias::new("previous_hash"))
+                .to_owned(),
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "sessions").await
+    }
+}
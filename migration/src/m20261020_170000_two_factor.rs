@@ -0,0 +1,49 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "totp_credentials",
+            &[
+                ("id", ColType::PkAuto),
+                ("secret", ColType::String),
+                ("confirmed_at", ColType::TimestampWithTimeZoneNull),
+                ("last_used_step", ColType::BigIntegerNull),
+            ],
+            &[("user", "")],
+        )
+        .await?;
+        m.create_index(
+            Index::create()
+                .name("idx-totp_credentials-user")
+                .table(Alias::new("totp_credentials"))
+                .col(Alias::new("user_id"))
+                .unique()
+                .to_owned(),
+        )
+        .await?;
+
+        create_table(
+            m,
+            "recovery_codes",
+            &[
+                ("id", ColType::PkAuto),
+                ("code_hash", ColType::String),
+                ("used_at", ColType::TimestampWithTimeZoneNull),
+            ],
+            &[("user", "")],
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "recovery_codes").await?;
+        drop_table(m, "totp_credentials").await
+    }
+}
@@ -0,0 +1,29 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "auth_throttles",
+            &[
+                ("id", ColType::PkAuto),
+                ("key", ColType::StringUniq),
+                ("attempts", ColType::Integer),
+                ("window_started_at", ColType::TimestampWithTimeZone),
+                ("lockouts", ColType::Integer),
+                ("locked_until", ColType::TimestampWithTimeZoneNull),
+            ],
+            &[],
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "auth_throttles").await
+    }
+}
@@ -0,0 +1,39 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        // no foreign key to users, events outlive the accounts they mention
+        create_table(
+            m,
+            "audit_events",
+            &[
+                ("id", ColType::PkAuto),
+                ("action", ColType::String),
+                ("actor_id", ColType::IntegerNull),
+                ("target", ColType::StringNull),
+                ("ip", ColType::StringNull),
+                ("details", ColType::JsonBinaryNull),
+            ],
+            &[],
+        )
+        .await?;
+
+        m.create_index(
+            Index::create()
+                .name("idx-audit_events-action")
+                .table(Alias::new("audit_events"))
+                .col(Alias::new("action"))
+                .to_owned(),
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "audit_events").await
+    }
+}
@@ -0,0 +1,27 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "email_changes",
+            &[
+                ("id", ColType::PkAuto),
+                ("new_email", ColType::String),
+                ("token", ColType::StringUniq),
+                ("expires_at", ColType::TimestampWithTimeZone),
+            ],
+            &[("user", "")],
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "email_changes").await
+    }
+}
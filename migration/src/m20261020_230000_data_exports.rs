@@ -0,0 +1,28 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "data_exports",
+            &[
+                ("id", ColType::PkAuto),
+                ("token", ColType::StringUniq),
+                ("archive", ColType::JsonBinaryNull),
+                ("completed_at", ColType::TimestampWithTimeZoneNull),
+                ("expires_at", ColType::TimestampWithTimeZoneNull),
+            ],
+            &[("user", "")],
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "data_exports").await
+    }
+}
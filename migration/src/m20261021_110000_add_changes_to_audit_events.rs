@@ -0,0 +1,40 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(m, "audit_events", "changes", ColType::JsonBinaryNull).await?;
+        add_column(m, "audit_events", "request_id", ColType::StringNull).await?;
+
+        // the viewer filters by who did something and to what
+        for col in ["actor_id", "target"] {
+            m.create_index(
+                Index::create()
+                    .name(format!("idx-audit_events-{col}"))
+                    .table(Alias::new("audit_events"))
+                    .col(Alias::new(col))
+                    .to_owned(),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        for col in ["actor_id", "target"] {
+            m.drop_index(
+                Index::drop()
+                    .name(format!("idx-audit_events-{col}"))
+                    .table(Alias::new("audit_events"))
+                    .to_owned(),
+            )
+            .await?;
+        }
+        remove_column(m, "audit_events", "request_id").await?;
+        remove_column(m, "audit_events", "changes").await
+    }
+}
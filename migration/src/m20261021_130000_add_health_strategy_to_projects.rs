@@ -0,0 +1,26 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(
+            m,
+            "projects",
+            "health_strategy",
+            ColType::StringWithDefault("average".to_string()),
+        )
+        .await?;
+        add_column(m, "projects", "health_strategy_used", ColType::StringNull).await?;
+        add_column(m, "projects", "primary_repo_id", ColType::IntegerNull).await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        remove_column(m, "projects", "primary_repo_id").await?;
+        remove_column(m, "projects", "health_strategy_used").await?;
+        remove_column(m, "projects", "health_strategy").await
+    }
+}
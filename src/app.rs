@@ -1,4 +1,5 @@
 use async_trait::async_trait;
+use axum::Router as AxumRouter;
 use loco_rs::{
     app::{AppContext, Hooks, Initializer},
     bgworker::{BackgroundWorker, Queue},
@@ -15,7 +16,13 @@ use std::path::Path;
 
 #[allow(unused_imports)]
 use crate::{
-    controllers, initializers, models::_entities::users, tasks, workers::downloader::DownloadWorker,
+    controllers, initializers,
+    models::_entities::users,
+    tasks,
+    workers::{
+        data_export::DataExportWorker, downloader::DownloadWorker, fetch_repo::FetchRepoWorker,
+        import_repos::ImportReposWorker, sync_repo::SyncRepoWorker,
+    },
 };
 
 pub struct App;
@@ -52,19 +59,45 @@ impl Hooks for App {
         )])
     }
 
+    async fn after_routes(router: AxumRouter, _ctx: &AppContext) -> Result<AxumRouter> {
+        Ok(router.layer(axum::middleware::from_fn(
+            controllers::browser::csrf_form_field,
+        )))
+    }
+
     fn routes(_ctx: &AppContext) -> AppRoutes {
         AppRoutes::with_default_routes() // controller routes below
             .add_route(controllers::repo::routes())
             .add_route(controllers::project::routes())
             .add_route(controllers::auth::routes())
+            .add_route(controllers::oauth::routes())
+            .add_route(controllers::two_factor::routes())
+            .add_route(controllers::account::routes())
+            .add_route(controllers::webhooks::routes())
+            .add_route(controllers::admin::routes())
+            .add_route(controllers::api::admin::routes())
+            .add_route(controllers::api::api_keys::routes())
+            .add_route(controllers::api::me::routes())
+            .add_route(controllers::api::projects::routes())
+            .add_route(controllers::api::repos::routes())
     }
     async fn connect_workers(ctx: &AppContext, queue: &Queue) -> Result<()> {
+        queue.register(DataExportWorker::build(ctx)).await?;
         queue.register(DownloadWorker::build(ctx)).await?;
+        queue.register(FetchRepoWorker::build(ctx)).await?;
+        queue.register(ImportReposWorker::build(ctx)).await?;
+        queue.register(SyncRepoWorker::build(ctx)).await?;
         Ok(())
     }
 
-    #[allow(unused_variables)]
     fn register_tasks(tasks: &mut Tasks) {
+        tasks.register(tasks::fetch_repo::FetchRepo);
+        tasks.register(tasks::import_repos::ImportRepos);
+        tasks.register(tasks::merge_duplicate_repos::MergeDuplicateRepos);
+        tasks.register(tasks::email_domains::EmailDomains);
+        tasks.register(tasks::data_export::DataExport);
+        tasks.register(tasks::admin::Admin);
+        tasks.register(tasks::recalculate_health::RecalculateHealth);
         // tasks-inject (do not remove)
     }
     async fn truncate(ctx: &AppContext) -> Result<()> {
@@ -0,0 +1,225 @@
+//! The signed in user managing their own account: profile, email address,
+//! password, exporting their data and deleting it.
+use axum::http::{header, StatusCode};
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    controllers::{audit::Audit, current_user::SignedIn},
+    mailers::auth::AuthMailer,
+    models::{
+        audit_events::{self, NewEvent},
+        data_exports, email_changes, project_members, sessions,
+        users::{self, Validator},
+    },
+    views::auth::CurrentResponse,
+    workers::data_export::{DataExportWorker, DataExportWorkerArgs},
+};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ProfileParams {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EmailParams {
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PasswordParams {
+    pub current_password: String,
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DeleteParams {
+    pub password: String,
+}
+
+fn validate(user: &users::Model, name: &str, email: &str) -> Result<()> {
+    Validate::validate(&Validator {
+        name: name.to_string(),
+        email: email.to_string(),
+    })
+    .map_err(|err| {
+        tracing::debug!(pid = user.pid.to_string(), error = %err, "invalid account change");
+        Error::BadRequest(err.to_string())
+    })
+}
+
+/// Change the name of the user.
+#[debug_handler]
+async fn update_profile(
+    auth: SignedIn,
+    State(ctx): State<AppContext>,
+    Json(params): Json<ProfileParams>,
+) -> Result<Response> {
+    let name = params.name.trim();
+    validate(&auth.user, name, &auth.user.email)?;
+    let mut user = auth.user.into_active_model();
+    user.name = Set(name.to_string());
+    let user = user.update(&ctx.db).await?;
+    format::json(CurrentResponse::new(&user))
+}
+
+/// Start changing the email address of the user. The address only changes
+/// once the link mailed to the new one is followed.
+#[debug_handler]
+async fn change_email(
+    auth: SignedIn,
+    State(ctx): State<AppContext>,
+    Json(params): Json<EmailParams>,
+) -> Result<Response> {
+    let email = params.email.trim();
+    validate(&auth.user, &auth.user.name, email)?;
+    if email.eq_ignore_ascii_case(&auth.user.email) {
+        return bad_request("that is already your email address");
+    }
+    if users::Model::find_by_email(&ctx.db, email).await.is_ok() {
+        return bad_request("that email address is taken");
+    }
+    let change = email_changes::Entity::start(&ctx.db, auth.user.id, email).await?;
+    AuthMailer::send_email_change(&ctx, &auth.user, &change).await?;
+    format::json(())
+}
+
+/// Confirm a new email address with the token mailed to it.
+#[debug_handler]
+async fn confirm_email(
+    State(ctx): State<AppContext>,
+    audit: Audit,
+    Path(token): Path<String>,
+) -> Result<Response> {
+    let Some(change) = email_changes::Entity::find_pending(&ctx.db, &token).await? else {
+        return unauthorized("invalid token");
+    };
+    let old_email = users::Entity::find_by_id(change.user_id)
+        .one(&ctx.db)
+        .await?
+        .map(|user| user.email);
+    let user = match change.complete(&ctx.db).await {
+        Ok(user) => user,
+        Err(ModelError::EntityAlreadyExists) => {
+            return bad_request("that email address is taken");
+        }
+        Err(err) => return Err(err.into()),
+    };
+    NewEvent {
+        actor_id: Some(user.id),
+        target: Some(user.pid.to_string()),
+        details: Some(json!({ "from": old_email, "to": user.email })),
+        ..audit.event(audit_events::EMAIL_CHANGED)
+    }
+    .record(&ctx.db)
+    .await?;
+    format::json(CurrentResponse::new(&user))
+}
+
+/// Change the password, which takes the current one. Other sessions end.
+#[debug_handler]
+async fn change_password(
+    auth: SignedIn,
+    State(ctx): State<AppContext>,
+    audit: Audit,
+    Json(params): Json<PasswordParams>,
+) -> Result<Response> {
+    if !auth.user.verify_password(&params.current_password) {
+        return unauthorized("wrong password");
+    }
+    let keep = Some(auth.session.id);
+    let user = auth
+        .user
+        .into_active_model()
+        .reset_password(&ctx.db, &params.password)
+        .await?;
+    sessions::Entity::revoke_others(&ctx.db, user.id, keep).await?;
+    NewEvent {
+        actor_id: Some(user.id),
+        target: Some(user.pid.to_string()),
+        ..audit.event(audit_events::PASSWORD_CHANGED)
+    }
+    .record(&ctx.db)
+    .await?;
+    format::json(())
+}
+
+/// Ask for an export of everything stored about the user. It is assembled
+/// in the background and the link to it mailed to them.
+#[debug_handler]
+async fn request_export(auth: SignedIn, State(ctx): State<AppContext>) -> Result<Response> {
+    let export = data_exports::Entity::request(&ctx.db, auth.user.id).await?;
+    DataExportWorker::perform_later(
+        &ctx,
+        DataExportWorkerArgs {
+            export_id: export.id,
+        },
+    )
+    .await?;
+    format::render()
+        .status(StatusCode::ACCEPTED)
+        .json(json!({ "status": "queued" }))
+}
+
+/// Download a finished export, with the token from the mailed link.
+#[debug_handler]
+async fn download_export(
+    State(ctx): State<AppContext>,
+    Path(token): Path<String>,
+) -> Result<Response> {
+    let Some(export) = data_exports::Entity::find_ready(&ctx.db, &token).await? else {
+        return not_found();
+    };
+    format::render()
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!(
+                "attachment; filename=\"gooncityhub-export-{}.json\"",
+                export.id
+            ),
+        )
+        .json(export.archive)
+}
+
+/// Delete the account, which takes the password. Projects keep their repos
+/// and history, only the memberships of the user go.
+#[debug_handler]
+async fn delete_account(
+    auth: SignedIn,
+    State(ctx): State<AppContext>,
+    audit: Audit,
+    Json(params): Json<DeleteParams>,
+) -> Result<Response> {
+    if !auth.user.verify_password(&params.password) {
+        return unauthorized("wrong password");
+    }
+    let orphaned = project_members::Entity::left_without_owner(&ctx.db, auth.user.id).await?;
+    if !orphaned.is_empty() {
+        return bad_request(format!(
+            "make someone else an owner of projects {orphaned:?} first"
+        ));
+    }
+    let (id, pid) = (auth.user.id, auth.user.pid);
+    auth.user.delete(&ctx.db).await?;
+    NewEvent {
+        actor_id: Some(id),
+        target: Some(pid.to_string()),
+        ..audit.event(audit_events::ACCOUNT_DELETED)
+    }
+    .record(&ctx.db)
+    .await?;
+    format::json(())
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("/api/account")
+        .add("/", patch(update_profile))
+        .add("/", delete(delete_account))
+        .add("/email", post(change_email))
+        .add("/email/{token}", get(confirm_email))
+        .add("/password", post(change_password))
+        .add("/export", post(request_export))
+        .add("/export/{token}", get(download_export))
+}
@@ -0,0 +1,359 @@
+//! The admin console, for users with the admin role: managing users,
+//! syncing repos again and changing how health is scored.
+//!
+//! The actions here are shared with the JSON API in [`super::api::admin`],
+//! and each one is written to the audit log.
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::unused_async)]
+use axum::response::Redirect;
+use axum_extra::extract::Form;
+use loco_rs::prelude::*;
+use sea_orm::{sea_query::Order, QueryOrder, QuerySelect};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::{
+    api::{invalid, page_query, parsed},
+    audit::Audit,
+    browser::CsrfToken,
+    current_user::Admin,
+};
+use crate::{
+    health::{self, Bucket, Caps, Normalization, Weights},
+    models::{
+        _entities::repos,
+        audit_events::{self, Audited, NewEvent},
+        health_configs, projects, sessions,
+        sync_runs::{self, SyncStatus},
+        users,
+    },
+    views,
+    workers::sync_repo::{SyncRepoWorker, SyncRepoWorkerArgs},
+};
+
+/// How many failed runs the failures page shows.
+const RECENT_FAILURES: u64 = 100;
+/// How many users the users page shows.
+const USERS_SHOWN: u64 = 100;
+
+/// Grant or take the admin role. Admins can not take it from themselves,
+/// so there is always one left.
+pub(crate) async fn set_admin(
+    ctx: &AppContext,
+    admin: &users::Model,
+    pid: &str,
+    is_admin: bool,
+    audit: &Audit,
+) -> Result<users::Model> {
+    let user = users::Model::find_by_pid(&ctx.db, pid).await?;
+    if user.id == admin.id && !is_admin {
+        return Err(invalid("you can not take the admin role from yourself"));
+    }
+    let user = user
+        .into_active_model()
+        .set_admin(&ctx.db, is_admin)
+        .await?;
+    NewEvent {
+        actor_id: Some(admin.id),
+        target: Some(user.pid.to_string()),
+        ..audit.event(if is_admin {
+            audit_events::ADMIN_GRANTED
+        } else {
+            audit_events::ADMIN_REVOKED
+        })
+    }
+    .record(&ctx.db)
+    .await?;
+    Ok(user)
+}
+
+/// Sign a user out everywhere, returning how many sessions ended.
+pub(crate) async fn revoke_sessions(
+    ctx: &AppContext,
+    admin: &users::Model,
+    pid: &str,
+    audit: &Audit,
+) -> Result<u64> {
+    let user = users::Model::find_by_pid(&ctx.db, pid).await?;
+    let revoked = sessions::Entity::revoke_all(&ctx.db, user.id).await?;
+    NewEvent {
+        actor_id: Some(admin.id),
+        target: Some(user.pid.to_string()),
+        details: Some(json!({ "revoked": revoked })),
+        ..audit.event(audit_events::SESSIONS_REVOKED)
+    }
+    .record(&ctx.db)
+    .await?;
+    Ok(revoked)
+}
+
+/// Queue a repo to be fetched from its forge again.
+pub(crate) async fn resync_repo(
+    ctx: &AppContext,
+    admin: &users::Model,
+    repo_id: i32,
+    audit: &Audit,
+) -> Result<repos::Model> {
+    let repo = repos::Entity::find_by_id(repo_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    SyncRepoWorker::perform_later(ctx, SyncRepoWorkerArgs { repo_id: repo.id }).await?;
+    NewEvent {
+        actor_id: Some(admin.id),
+        target: Some(repo.audit_target()),
+        ..audit.event(audit_events::REPO_RESYNC)
+    }
+    .record(&ctx.db)
+    .await?;
+    Ok(repo)
+}
+
+/// Score repos with `config` from now on, and rescore every project.
+pub(crate) async fn update_health_config(
+    ctx: &AppContext,
+    admin: &users::Model,
+    config: &health::Config,
+    audit: &Audit,
+) -> Result<()> {
+    config.validate().map_err(|err| invalid(&err))?;
+    let before = health_configs::Entity::current(&ctx.db).await?;
+    let event = NewEvent {
+        actor_id: Some(admin.id),
+        target: Some("health_config".to_string()),
+        changes: audit_events::changes(Some(&before), Some(config)),
+        ..NewEvent::new(audit_events::HEALTH_CONFIG_CHANGED)
+    };
+    let config = config.clone();
+    audit
+        .transaction(&ctx.db, |txn| {
+            Box::pin(async move {
+                health_configs::Entity::replace(txn, &config).await?;
+                Ok(((), event))
+            })
+        })
+        .await?;
+    // each project records its own health change
+    projects::Entity::recalculate_all_health(&ctx.db, projects::RECALCULATE_CONCURRENCY).await?;
+    Ok(())
+}
+
+/// The health config as the edit form sends it, one field per number.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HealthForm {
+    pub activity: f64,
+    pub community: f64,
+    pub adoption: f64,
+    pub maintenance: f64,
+    pub commits_last_30d: f64,
+    pub contributors: f64,
+    pub stars: f64,
+    pub prs: f64,
+    pub issues: f64,
+    #[serde(default)]
+    pub normalization: Normalization,
+    #[serde(default)]
+    pub bucket: Bucket,
+}
+
+impl From<HealthForm> for health::Config {
+    fn from(form: HealthForm) -> Self {
+        Self {
+            weights: Weights {
+                activity: form.activity,
+                community: form.community,
+                adoption: form.adoption,
+                maintenance: form.maintenance,
+            },
+            caps: Caps {
+                commits_last_30d: form.commits_last_30d,
+                contributors: form.contributors,
+                stars: form.stars,
+                prs: form.prs,
+                issues: form.issues,
+            },
+            normalization: form.normalization,
+            bucket: form.bucket,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SetAdminForm {
+    pub is_admin: bool,
+}
+
+/// Which audit events to show, as the viewer's form sends them.
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditParams {
+    #[serde(flatten)]
+    pub pagination: query::PaginationQuery,
+    /// An action, or its first part like `auth`.
+    pub action: Option<String>,
+    #[serde(default, deserialize_with = "parsed")]
+    pub actor_id: Option<i32>,
+    pub target: Option<String>,
+    pub request_id: Option<String>,
+    /// RFC 3339, like `2026-10-21T00:00:00Z`.
+    #[serde(default, deserialize_with = "parsed")]
+    pub since: Option<DateTimeWithTimeZone>,
+    #[serde(default, deserialize_with = "parsed")]
+    pub until: Option<DateTimeWithTimeZone>,
+}
+
+impl AuditParams {
+    #[must_use]
+    pub fn filter(&self) -> audit_events::Filter {
+        audit_events::Filter {
+            action: self.action.clone(),
+            actor_id: self.actor_id,
+            target: self.target.clone(),
+            request_id: self.request_id.clone(),
+            since: self.since,
+            until: self.until,
+        }
+    }
+}
+
+/// Audit events matching `params`, newest first.
+pub(crate) async fn audit_events_page(
+    ctx: &AppContext,
+    params: &AuditParams,
+) -> Result<(
+    query::PaginationQuery,
+    query::PageResponse<audit_events::Model>,
+)> {
+    let pagination = page_query(&params.pagination);
+    let select = audit_events::Entity::find().order_by_desc(audit_events::Column::Id);
+    let res = query::paginate(
+        &ctx.db,
+        select,
+        Some(params.filter().condition()),
+        &pagination,
+    )
+    .await?;
+    Ok((pagination, res))
+}
+
+/// Recent failed fetches, and the repos marked stale or not found because
+/// of them.
+#[debug_handler]
+pub async fn sync_failures(
+    _admin: Admin,
+    CsrfToken(csrf_token): CsrfToken,
+    ViewEngine(v): ViewEngine<TeraView>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let runs = sync_runs::Entity::recent_failures(&ctx.db, RECENT_FAILURES).await?;
+    let marked = repos::Entity::find()
+        .filter(repos::Column::SyncStatus.ne(SyncStatus::Ok.as_str()))
+        .order_by(repos::Column::UpdatedAt, Order::Desc)
+        .all(&ctx.db)
+        .await?;
+    views::admin::sync_failures(&v, &runs, &marked, csrf_token.as_deref())
+}
+
+#[debug_handler]
+pub async fn sync_repo(
+    Path(id): Path<i32>,
+    Admin(admin): Admin,
+    audit: Audit,
+    State(ctx): State<AppContext>,
+) -> Result<Redirect> {
+    resync_repo(&ctx, &admin, id, &audit).await?;
+    Ok(Redirect::to("/admin/sync_failures"))
+}
+
+/// The newest users.
+#[debug_handler]
+pub async fn list_users(
+    _admin: Admin,
+    CsrfToken(csrf_token): CsrfToken,
+    ViewEngine(v): ViewEngine<TeraView>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let items = users::Entity::find()
+        .order_by(users::users::Column::Id, Order::Desc)
+        .limit(USERS_SHOWN)
+        .all(&ctx.db)
+        .await?;
+    views::admin::users(&v, &items, csrf_token.as_deref())
+}
+
+#[debug_handler]
+pub async fn update_admin(
+    Path(pid): Path<String>,
+    Admin(admin): Admin,
+    audit: Audit,
+    State(ctx): State<AppContext>,
+    Form(params): Form<SetAdminForm>,
+) -> Result<Redirect> {
+    set_admin(&ctx, &admin, &pid, params.is_admin, &audit).await?;
+    Ok(Redirect::to("/admin/users"))
+}
+
+#[debug_handler]
+pub async fn sign_out_user(
+    Path(pid): Path<String>,
+    Admin(admin): Admin,
+    audit: Audit,
+    State(ctx): State<AppContext>,
+) -> Result<Redirect> {
+    revoke_sessions(&ctx, &admin, &pid, &audit).await?;
+    Ok(Redirect::to("/admin/users"))
+}
+
+#[debug_handler]
+pub async fn edit_health(
+    _admin: Admin,
+    CsrfToken(csrf_token): CsrfToken,
+    ViewEngine(v): ViewEngine<TeraView>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let config = health_configs::Entity::current(&ctx.db).await?;
+    views::admin::health(&v, &config, None, csrf_token.as_deref())
+}
+
+/// Save the health config, or show the form again with why it was
+/// rejected.
+#[debug_handler]
+pub async fn update_health(
+    Admin(admin): Admin,
+    audit: Audit,
+    CsrfToken(csrf_token): CsrfToken,
+    ViewEngine(v): ViewEngine<TeraView>,
+    State(ctx): State<AppContext>,
+    Form(params): Form<HealthForm>,
+) -> Result<Response> {
+    let config = health::Config::from(params);
+    if let Err(err) = config.validate() {
+        return views::admin::health(&v, &config, Some(&err), csrf_token.as_deref());
+    }
+    update_health_config(&ctx, &admin, &config, &audit).await?;
+    Ok(Redirect::to("/admin/health").into_response())
+}
+
+/// The audit log, filtered by the query string.
+#[debug_handler]
+pub async fn audit_log(
+    _admin: Admin,
+    ViewEngine(v): ViewEngine<TeraView>,
+    State(ctx): State<AppContext>,
+    Query(params): Query<AuditParams>,
+) -> Result<Response> {
+    let (pagination, res) = audit_events_page(&ctx, &params).await?;
+    views::admin::audit_log(&v, &params.filter(), &pagination, &res)
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("admin/")
+        .add("sync_failures", get(sync_failures))
+        .add("repos/{id}/sync", post(sync_repo))
+        .add("users", get(list_users))
+        .add("users/{pid}/admin", post(update_admin))
+        .add("users/{pid}/sessions/revoke", post(sign_out_user))
+        .add("health", get(edit_health))
+        .add("health", post(update_health))
+        .add("audit", get(audit_log))
+}
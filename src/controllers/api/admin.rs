@@ -0,0 +1,148 @@
+#![allow(clippy::missing_errors_doc)]
+//! The admin console as JSON, for users with the admin role. The actions
+//! are the ones of [`crate::controllers::admin`].
+use axum::{
+    extract::rejection::{JsonRejection, QueryRejection},
+    http::StatusCode,
+    Json,
+};
+use loco_rs::prelude::*;
+use sea_orm::QueryOrder;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::{bad_json, bad_query, parsed};
+use crate::{
+    controllers::{admin, audit::Audit, current_user::Admin},
+    health,
+    models::{health_configs, users},
+    views::admin::UserResponse,
+};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct UsersParams {
+    #[serde(flatten)]
+    pub pagination: query::PaginationQuery,
+    #[serde(default, deserialize_with = "parsed")]
+    pub is_admin: Option<bool>,
+    /// Part of the email address.
+    pub q: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateUserParams {
+    pub is_admin: bool,
+}
+
+/// Users, newest first.
+#[debug_handler]
+pub async fn list_users(
+    _admin: Admin,
+    State(ctx): State<AppContext>,
+    params: std::result::Result<Query<UsersParams>, QueryRejection>,
+) -> Result<Response> {
+    let Query(params) = params.map_err(bad_query)?;
+    let mut condition = query::condition();
+    if let Some(is_admin) = params.is_admin {
+        condition = condition.eq(users::users::Column::IsAdmin, is_admin);
+    }
+    if let Some(q) = params.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        condition = condition.contains(users::users::Column::Email, q);
+    }
+
+    let pagination = super::page_query(&params.pagination);
+    let select = users::Entity::find().order_by_desc(users::users::Column::Id);
+    let res = query::paginate(&ctx.db, select, Some(condition.build()), &pagination).await?;
+    super::page(
+        &pagination,
+        query::PageResponse {
+            page: res.page.iter().map(UserResponse::new).collect(),
+            total_pages: res.total_pages,
+            total_items: res.total_items,
+        },
+    )
+}
+
+#[debug_handler]
+pub async fn update_user(
+    Path(pid): Path<String>,
+    Admin(admin): Admin,
+    audit: Audit,
+    State(ctx): State<AppContext>,
+    params: std::result::Result<Json<UpdateUserParams>, JsonRejection>,
+) -> Result<Response> {
+    let Json(params) = params.map_err(bad_json)?;
+    let user = admin::set_admin(&ctx, &admin, &pid, params.is_admin, &audit).await?;
+    format::json(UserResponse::new(&user))
+}
+
+/// Sign the user out everywhere.
+#[debug_handler]
+pub async fn revoke_sessions(
+    Path(pid): Path<String>,
+    Admin(admin): Admin,
+    audit: Audit,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let revoked = admin::revoke_sessions(&ctx, &admin, &pid, &audit).await?;
+    format::json(json!({ "revoked": revoked }))
+}
+
+/// Queue the repo to be fetched again, answering `202 Accepted`.
+#[debug_handler]
+pub async fn sync_repo(
+    Path(id): Path<i32>,
+    Admin(admin): Admin,
+    audit: Audit,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let repo = admin::resync_repo(&ctx, &admin, id, &audit).await?;
+    format::render()
+        .status(StatusCode::ACCEPTED)
+        .json(json!({ "status": "queued", "repo_id": repo.id }))
+}
+
+#[debug_handler]
+pub async fn show_health(_admin: Admin, State(ctx): State<AppContext>) -> Result<Response> {
+    format::json(health_configs::Entity::current(&ctx.db).await?)
+}
+
+/// The audit log, newest first. Filters by `action`, or all actions
+/// starting with it when it has no dot, `actor_id`, `target`, `request_id`,
+/// `since` and `until`.
+#[debug_handler]
+pub async fn audit_events(
+    _admin: Admin,
+    State(ctx): State<AppContext>,
+    params: std::result::Result<Query<admin::AuditParams>, QueryRejection>,
+) -> Result<Response> {
+    let Query(params) = params.map_err(bad_query)?;
+    let (pagination, res) = admin::audit_events_page(&ctx, &params).await?;
+    super::page(&pagination, res)
+}
+
+/// Replace the health config. Every project is scored again.
+#[debug_handler]
+pub async fn update_health(
+    Admin(admin): Admin,
+    audit: Audit,
+    State(ctx): State<AppContext>,
+    params: std::result::Result<Json<health::Config>, JsonRejection>,
+) -> Result<Response> {
+    let Json(config) = params.map_err(bad_json)?;
+    admin::update_health_config(&ctx, &admin, &config, &audit).await?;
+    format::json(config)
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix(&format!("{}/admin/", super::PREFIX))
+        .add("users", get(list_users))
+        .add("users/{pid}", patch(update_user))
+        .add("users/{pid}/sessions", delete(revoke_sessions))
+        .add("repos/{id}/sync", post(sync_repo))
+        .add("health", get(show_health))
+        .add("health", put(update_health))
+        .add("audit_events", get(audit_events))
+}
@@ -0,0 +1,167 @@
+#![allow(clippy::missing_errors_doc)]
+//! API keys of the signed in user. Keys are managed with a JWT only, so a
+//! leaked key can not mint or rotate keys.
+use axum::{extract::rejection::JsonRejection, Json};
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{bad_json, invalid};
+use crate::{
+    controllers::{audit::Audit, current_user::SignedIn},
+    models::{
+        api_keys::{self, Issued, Scope},
+        audit_events::{self, NewEvent},
+        users,
+    },
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CreateParams {
+    pub name: String,
+    /// Every scope when not given.
+    pub scopes: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ApiKeyResponse {
+    pub id: i32,
+    pub name: String,
+    /// The start of the key, to tell keys apart.
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    pub created_at: DateTimeWithTimeZone,
+    pub last_used_at: Option<DateTimeWithTimeZone>,
+    pub revoked_at: Option<DateTimeWithTimeZone>,
+    /// The key itself, only when it was just created or rotated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+}
+
+impl ApiKeyResponse {
+    #[must_use]
+    pub fn new(key: &api_keys::Model) -> Self {
+        Self {
+            id: key.id,
+            name: key.name.clone(),
+            prefix: key.prefix.clone(),
+            scopes: key
+                .scopes()
+                .iter()
+                .map(|s| s.as_str().to_string())
+                .collect(),
+            created_at: key.created_at,
+            last_used_at: key.last_used_at,
+            revoked_at: key.revoked_at,
+            key: None,
+        }
+    }
+
+    #[must_use]
+    pub fn issued(issued: Issued) -> Self {
+        Self {
+            key: Some(issued.secret),
+            ..Self::new(&issued.key)
+        }
+    }
+}
+
+async fn load_key(ctx: &AppContext, user: &users::Model, id: i32) -> Result<api_keys::Model> {
+    api_keys::Entity::find_by_id(id)
+        .filter(api_keys::Column::UserId.eq(user.id))
+        .one(&ctx.db)
+        .await?
+        .ok_or(Error::NotFound)
+}
+
+async fn record(
+    ctx: &AppContext,
+    audit: &Audit,
+    action: &str,
+    key: &api_keys::Model,
+) -> Result<()> {
+    NewEvent {
+        actor_id: Some(key.user_id),
+        target: Some(format!("api_key:{}", key.id)),
+        details: Some(serde_json::json!({ "name": key.name })),
+        ..audit.event(action)
+    }
+    .record(&ctx.db)
+    .await?;
+    Ok(())
+}
+
+#[debug_handler]
+pub async fn list(auth: SignedIn, State(ctx): State<AppContext>) -> Result<Response> {
+    let keys = api_keys::Entity::for_user(&ctx.db, auth.user.id).await?;
+    format::json(keys.iter().map(ApiKeyResponse::new).collect::<Vec<_>>())
+}
+
+#[debug_handler]
+pub async fn add(
+    auth: SignedIn,
+    audit: Audit,
+    State(ctx): State<AppContext>,
+    params: std::result::Result<Json<CreateParams>, JsonRejection>,
+) -> Result<Response> {
+    let Json(params) = params.map_err(bad_json)?;
+    let name = params.name.trim();
+    if name.is_empty() {
+        return Err(invalid("name must not be empty"));
+    }
+    let scopes = match &params.scopes {
+        None => Scope::ALL.to_vec(),
+        Some(scopes) if scopes.is_empty() => {
+            return Err(invalid("give the key at least one scope"))
+        }
+        Some(scopes) => scopes
+            .iter()
+            .map(|s| s.parse())
+            .collect::<std::result::Result<Vec<Scope>, String>>()
+            .map_err(|err| invalid(&err))?,
+    };
+
+    let issued = api_keys::Entity::issue(&ctx.db, auth.user.id, name, &scopes).await?;
+    record(&ctx, &audit, audit_events::API_KEY_CREATED, &issued.key).await?;
+    super::created(&ApiKeyResponse::issued(issued))
+}
+
+#[debug_handler]
+pub async fn rotate(
+    Path(id): Path<i32>,
+    auth: SignedIn,
+    audit: Audit,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let key = load_key(&ctx, &auth.user, id).await?;
+    if key.revoked_at.is_some() {
+        return Err(invalid("a revoked key can not be rotated"));
+    }
+    let issued = key.rotate(&ctx.db).await?;
+    record(&ctx, &audit, audit_events::API_KEY_ROTATED, &issued.key).await?;
+    format::json(ApiKeyResponse::issued(issued))
+}
+
+#[debug_handler]
+pub async fn revoke(
+    Path(id): Path<i32>,
+    auth: SignedIn,
+    audit: Audit,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let key = load_key(&ctx, &auth.user, id).await?;
+    if key.revoked_at.is_none() {
+        let key = key.revoke(&ctx.db).await?;
+        record(&ctx, &audit, audit_events::API_KEY_REVOKED, &key).await?;
+    }
+    super::no_content()
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix(&format!("{}/api_keys/", super::PREFIX))
+        .add("/", get(list))
+        .add("/", post(add))
+        .add("{id}/rotate", post(rotate))
+        .add("{id}", delete(revoke))
+}
@@ -0,0 +1,45 @@
+#![allow(clippy::missing_errors_doc)]
+//! What the signed in user has linked and done.
+use axum::extract::rejection::QueryRejection;
+use loco_rs::prelude::*;
+use serde::Deserialize;
+
+use super::bad_query;
+use crate::{
+    controllers::current_user::CurrentUser,
+    models::{activity_events, user_identities},
+};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ContributionsParams {
+    #[serde(flatten)]
+    pub pagination: query::PaginationQuery,
+}
+
+/// Accounts on other sites linked to the user.
+#[debug_handler]
+pub async fn identities(current: CurrentUser, State(ctx): State<AppContext>) -> Result<Response> {
+    format::json(user_identities::Entity::for_user(&ctx.db, current.user.id).await?)
+}
+
+/// Activity on tracked repos by the user's linked accounts, newest first.
+#[debug_handler]
+pub async fn contributions(
+    current: CurrentUser,
+    State(ctx): State<AppContext>,
+    params: std::result::Result<Query<ContributionsParams>, QueryRejection>,
+) -> Result<Response> {
+    let Query(params) = params.map_err(bad_query)?;
+    let identities = user_identities::Entity::for_user(&ctx.db, current.user.id).await?;
+    let pagination = super::page_query(&params.pagination);
+    let select = activity_events::Entity::by_identities(&identities);
+    let res = query::paginate(&ctx.db, select, None, &pagination).await?;
+    super::page(&pagination, res)
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix(&format!("{}/me/", super::PREFIX))
+        .add("identities", get(identities))
+        .add("contributions", get(contributions))
+}
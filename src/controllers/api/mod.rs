@@ -0,0 +1,130 @@
+//! Versioned JSON API under `/api/v1`.
+//!
+//! Errors use the same body as the rest of the app,
+//! `{"error": "<code>", "description": "<what went wrong>"}`.
+use std::{fmt::Display, str::FromStr};
+
+use axum::{
+    extract::rejection::{JsonRejection, QueryRejection},
+    http::StatusCode,
+};
+use loco_rs::{
+    controller::{
+        views::pagination::{Pager, PagerMeta},
+        ErrorDetail,
+    },
+    prelude::*,
+};
+use sea_orm::sea_query::Order;
+use serde::{Deserialize, Deserializer};
+
+pub mod admin;
+pub mod api_keys;
+pub mod me;
+pub mod projects;
+pub mod repos;
+
+pub const PREFIX: &str = "/api/v1";
+
+/// Largest page a client can ask for.
+const MAX_PAGE_SIZE: u64 = 100;
+
+pub(crate) fn error(status: StatusCode, code: &str, description: &str) -> Error {
+    Error::CustomError(status, ErrorDetail::new(code, description))
+}
+
+/// The request was understood, but its values can not be used.
+pub(crate) fn invalid(description: &str) -> Error {
+    error(
+        StatusCode::UNPROCESSABLE_ENTITY,
+        "invalid_params",
+        description,
+    )
+}
+
+pub(crate) fn bad_json(rejection: JsonRejection) -> Error {
+    error(
+        StatusCode::BAD_REQUEST,
+        "bad_request",
+        &rejection.body_text(),
+    )
+}
+
+pub(crate) fn bad_query(rejection: QueryRejection) -> Error {
+    error(
+        StatusCode::BAD_REQUEST,
+        "bad_request",
+        &rejection.body_text(),
+    )
+}
+
+/// Query string values arrive as strings, also inside a flattened struct.
+pub(crate) fn parsed<'de, D, T>(deserializer: D) -> std::result::Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+    match value.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(value) => value.parse().map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+/// `sort=field` sorts ascending, `sort=-field` descending, by one of
+/// `columns`. Newest first when not given.
+pub(crate) fn sort_order<C: Copy>(
+    sort: Option<&str>,
+    default: C,
+    columns: &[(&str, C)],
+) -> Result<(C, Order)> {
+    let Some(sort) = sort.map(str::trim).filter(|s| !s.is_empty()) else {
+        return Ok((default, Order::Desc));
+    };
+    let (field, order) = sort
+        .strip_prefix('-')
+        .map_or((sort, Order::Asc), |field| (field, Order::Desc));
+    columns
+        .iter()
+        .find(|(name, _)| *name == field)
+        .map(|(_, column)| (*column, order))
+        .ok_or_else(|| {
+            let names: Vec<_> = columns.iter().map(|(name, _)| *name).collect();
+            invalid(&format!(
+                "can not sort by `{field}`, use one of {}",
+                names.join(", ")
+            ))
+        })
+}
+
+/// Clamp a requested page into what the API serves.
+pub(crate) fn page_query(pagination: &query::PaginationQuery) -> query::PaginationQuery {
+    query::PaginationQuery {
+        page: pagination.page.max(1),
+        page_size: pagination.page_size.clamp(1, MAX_PAGE_SIZE),
+    }
+}
+
+pub(crate) fn page<T: serde::Serialize>(
+    pagination: &query::PaginationQuery,
+    res: query::PageResponse<T>,
+) -> Result<Response> {
+    format::json(Pager::new(
+        res.page,
+        PagerMeta {
+            page: pagination.page,
+            page_size: pagination.page_size,
+            total_pages: res.total_pages,
+            total_items: res.total_items,
+        },
+    ))
+}
+
+pub(crate) fn created<T: serde::Serialize>(item: &T) -> Result<Response> {
+    format::render().status(StatusCode::CREATED).json(item)
+}
+
+pub(crate) fn no_content() -> Result<Response> {
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
@@ -0,0 +1,409 @@
+#![allow(clippy::missing_errors_doc)]
+use axum::{
+    extract::rejection::{JsonRejection, QueryRejection},
+    Json,
+};
+use chrono::Utc;
+use loco_rs::prelude::*;
+use sea_orm::QueryOrder;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::{bad_json, bad_query, invalid, parsed};
+use crate::{
+    controllers::{audit::Audit, current_user::CurrentUser},
+    health::Strategy,
+    models::{
+        _entities::projects::{ActiveModel, Column, Entity, Model},
+        api_keys::Scope,
+        audit_events::{self, Audited, NewEvent},
+        project_members::{self, Role},
+        users,
+    },
+};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ListParams {
+    #[serde(flatten)]
+    pub pagination: query::PaginationQuery,
+    /// `name`, `owner`, `health`, `last_fetch` or `created_at`, `-` first
+    /// for descending.
+    pub sort: Option<String>,
+    pub owner: Option<String>,
+    /// Part of the name.
+    pub q: Option<String>,
+    #[serde(default, deserialize_with = "parsed")]
+    pub min_health: Option<f32>,
+}
+
+/// A new project. Health is derived from its repos and can not be set,
+/// only how they are combined.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CreateParams {
+    pub name: String,
+    pub owner: String,
+    pub description: Option<String>,
+    /// A [`Strategy`], `average` when left out.
+    pub health_strategy: Option<String>,
+}
+
+/// Fields to change, an empty `description` removes it.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateParams {
+    pub name: Option<String>,
+    pub owner: Option<String>,
+    pub description: Option<String>,
+    pub health_strategy: Option<String>,
+    /// The repo the `primary` strategy scores, one of the project's.
+    pub primary_repo_id: Option<i32>,
+}
+
+/// Add a member or change their role.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MemberParams {
+    pub email: String,
+    pub role: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MemberResponse {
+    pub pid: String,
+    pub name: String,
+    pub role: String,
+}
+
+impl MemberResponse {
+    #[must_use]
+    pub fn new(user: &users::Model, member: &project_members::Model) -> Self {
+        Self {
+            pid: user.pid.to_string(),
+            name: user.name.clone(),
+            role: member.role.clone(),
+        }
+    }
+}
+
+fn required(field: &str, value: &str) -> Result<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(invalid(&format!("{field} must not be empty")));
+    }
+    Ok(value.to_string())
+}
+
+fn strategy(value: &str) -> Result<String> {
+    let strategy: Strategy = value.trim().parse().map_err(|err: String| invalid(&err))?;
+    Ok(strategy.as_str().to_string())
+}
+
+impl UpdateParams {
+    /// Whether the change needs the health to be combined again.
+    const fn rescores(&self) -> bool {
+        self.health_strategy.is_some() || self.primary_repo_id.is_some()
+    }
+
+    fn update(&self, item: &mut ActiveModel) -> Result<()> {
+        if let Some(name) = &self.name {
+            item.name = Set(required("name", name)?);
+        }
+        if let Some(owner) = &self.owner {
+            item.owner = Set(required("owner", owner)?);
+        }
+        if let Some(description) = &self.description {
+            let description = description.trim();
+            item.description = Set((!description.is_empty()).then(|| description.to_string()));
+        }
+        if let Some(health_strategy) = &self.health_strategy {
+            item.health_strategy = Set(strategy(health_strategy)?);
+        }
+        if let Some(primary_repo_id) = self.primary_repo_id {
+            item.primary_repo_id = Set(Some(primary_repo_id));
+        }
+        Ok(())
+    }
+}
+
+async fn load_item(ctx: &AppContext, id: i32) -> Result<Model> {
+    let item = Entity::find_by_id(id).one(&ctx.db).await?;
+    item.ok_or_else(|| Error::NotFound)
+}
+
+#[debug_handler]
+pub async fn list(
+    State(ctx): State<AppContext>,
+    params: std::result::Result<Query<ListParams>, QueryRejection>,
+) -> Result<Response> {
+    let Query(params) = params.map_err(bad_query)?;
+    let (column, order) = super::sort_order(
+        params.sort.as_deref(),
+        Column::Id,
+        &[
+            ("id", Column::Id),
+            ("name", Column::Name),
+            ("owner", Column::Owner),
+            ("health", Column::Health),
+            ("last_fetch", Column::LastFetch),
+            ("created_at", Column::CreatedAt),
+        ],
+    )?;
+
+    let mut condition = query::condition();
+    if let Some(owner) = &params.owner {
+        condition = condition.eq(Column::Owner, owner.as_str());
+    }
+    if let Some(q) = params.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        condition = condition.contains(Column::Name, q);
+    }
+    if let Some(min_health) = params.min_health {
+        condition = condition.gte(Column::Health, min_health);
+    }
+
+    let pagination = super::page_query(&params.pagination);
+    let select = Entity::find()
+        .order_by(column, order)
+        .order_by_asc(Column::Id);
+    let res = query::paginate(&ctx.db, select, Some(condition.build()), &pagination).await?;
+    super::page(&pagination, res)
+}
+
+#[debug_handler]
+pub async fn show(Path(id): Path<i32>, State(ctx): State<AppContext>) -> Result<Response> {
+    format::json(load_item(&ctx, id).await?)
+}
+
+#[debug_handler]
+pub async fn add(
+    current: CurrentUser,
+    audit: Audit,
+    State(ctx): State<AppContext>,
+    params: std::result::Result<Json<CreateParams>, JsonRejection>,
+) -> Result<Response> {
+    current.require(Scope::Projects)?;
+    let Json(params) = params.map_err(bad_json)?;
+    let item = ActiveModel {
+        name: Set(required("name", &params.name)?),
+        owner: Set(required("owner", &params.owner)?),
+        description: Set(params
+            .description
+            .map(|d| d.trim().to_string())
+            .filter(|d| !d.is_empty())),
+        health: Set(100.),
+        health_strategy: Set(strategy(
+            params
+                .health_strategy
+                .as_deref()
+                .unwrap_or(Strategy::default().as_str()),
+        )?),
+        last_fetch: Set(Utc::now().naive_utc()),
+        ..Default::default()
+    };
+    let actor_id = current.user.id;
+    let item = audit
+        .transaction(&ctx.db, |txn| {
+            Box::pin(async move {
+                let item = item.insert(txn).await?;
+                project_members::Entity::grant(txn, item.id, actor_id, Role::Owner).await?;
+                let event =
+                    NewEvent::change(audit_events::PROJECT_CREATED, actor_id, None, Some(&item));
+                Ok((item, event))
+            })
+        })
+        .await?;
+    super::created(&item)
+}
+
+#[debug_handler]
+pub async fn update(
+    Path(id): Path<i32>,
+    current: CurrentUser,
+    audit: Audit,
+    State(ctx): State<AppContext>,
+    params: std::result::Result<Json<UpdateParams>, JsonRejection>,
+) -> Result<Response> {
+    current.require(Scope::Projects)?;
+    let Json(params) = params.map_err(bad_json)?;
+    let before = load_item(&ctx, id).await?;
+    current.authorize(&ctx, before.id, Role::Maintainer).await?;
+    if let Some(repo_id) = params.primary_repo_id {
+        if !before.has_repo(&ctx.db, repo_id).await? {
+            return Err(invalid(&format!("repo {repo_id} is not in this project")));
+        }
+    }
+    let mut item = before.clone().into_active_model();
+    params.update(&mut item)?;
+    let rescores = params.rescores();
+    let actor_id = current.user.id;
+    let item = audit
+        .transaction(&ctx.db, |txn| {
+            Box::pin(async move {
+                let mut item = item.update(txn).await?;
+                if rescores {
+                    item.recalculate_health(txn).await?;
+                    item = Entity::find_by_id(id)
+                        .one(txn)
+                        .await?
+                        .ok_or(Error::NotFound)?;
+                }
+                let event = NewEvent::change(
+                    audit_events::PROJECT_UPDATED,
+                    actor_id,
+                    Some(&before),
+                    Some(&item),
+                );
+                Ok((item, event))
+            })
+        })
+        .await?;
+    format::json(item)
+}
+
+#[debug_handler]
+pub async fn remove(
+    Path(id): Path<i32>,
+    current: CurrentUser,
+    audit: Audit,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    current.require(Scope::Projects)?;
+    let item = load_item(&ctx, id).await?;
+    current.authorize(&ctx, item.id, Role::Owner).await?;
+    let actor_id = current.user.id;
+    audit
+        .transaction(&ctx.db, |txn| {
+            Box::pin(async move {
+                item.clone().delete(txn).await?;
+                let event =
+                    NewEvent::change(audit_events::PROJECT_DELETED, actor_id, Some(&item), None);
+                Ok(((), event))
+            })
+        })
+        .await?;
+    super::no_content()
+}
+
+#[debug_handler]
+pub async fn members(
+    Path(id): Path<i32>,
+    current: CurrentUser,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    current.require(Scope::Members)?;
+    let item = load_item(&ctx, id).await?;
+    current.authorize(&ctx, item.id, Role::Viewer).await?;
+    let members = project_members::Entity::find()
+        .filter(project_members::Column::ProjectId.eq(item.id))
+        .find_also_related(users::Entity)
+        .order_by_asc(project_members::Column::Id)
+        .all(&ctx.db)
+        .await?;
+    format::json(
+        members
+            .iter()
+            .filter_map(|(member, user)| user.as_ref().map(|u| MemberResponse::new(u, member)))
+            .collect::<Vec<_>>(),
+    )
+}
+
+#[debug_handler]
+pub async fn put_member(
+    Path(id): Path<i32>,
+    current: CurrentUser,
+    audit: Audit,
+    State(ctx): State<AppContext>,
+    params: std::result::Result<Json<MemberParams>, JsonRejection>,
+) -> Result<Response> {
+    current.require(Scope::Members)?;
+    let Json(params) = params.map_err(bad_json)?;
+    let item = load_item(&ctx, id).await?;
+    current.authorize(&ctx, item.id, Role::Owner).await?;
+    let role: Role = params.role.parse().map_err(|err: String| invalid(&err))?;
+    let user = users::Model::find_by_email(&ctx.db, params.email.trim())
+        .await
+        .map_err(|_| invalid(&format!("no user with email {}", params.email.trim())))?;
+    if user.id == current.user.id && role != Role::Owner {
+        return Err(invalid("owners can not demote themselves"));
+    }
+
+    let event = NewEvent {
+        actor_id: Some(current.user.id),
+        target: Some(item.audit_target()),
+        details: Some(json!({ "user": user.pid, "role": role.as_str() })),
+        ..NewEvent::new(audit_events::MEMBER_GRANTED)
+    };
+    let (project_id, user_id) = (item.id, user.id);
+    audit
+        .transaction(&ctx.db, |txn| {
+            Box::pin(async move {
+                project_members::Entity::grant(txn, project_id, user_id, role).await?;
+                Ok(((), event))
+            })
+        })
+        .await?;
+    let member = project_members::Entity::find()
+        .filter(project_members::Column::ProjectId.eq(item.id))
+        .filter(project_members::Column::UserId.eq(user.id))
+        .one(&ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    format::json(MemberResponse::new(&user, &member))
+}
+
+#[debug_handler]
+pub async fn remove_member(
+    Path((id, pid)): Path<(i32, String)>,
+    current: CurrentUser,
+    audit: Audit,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    current.require(Scope::Members)?;
+    let item = load_item(&ctx, id).await?;
+    current.authorize(&ctx, item.id, Role::Owner).await?;
+    let user = users::Model::find_by_pid(&ctx.db, &pid)
+        .await
+        .map_err(|_| Error::NotFound)?;
+    if user.id == current.user.id {
+        return Err(invalid("owners can not remove themselves"));
+    }
+    if project_members::Entity::role_of(&ctx.db, item.id, user.id)
+        .await?
+        .is_none()
+    {
+        return super::no_content();
+    }
+    let event = NewEvent {
+        actor_id: Some(current.user.id),
+        target: Some(item.audit_target()),
+        details: Some(json!({ "user": user.pid })),
+        ..NewEvent::new(audit_events::MEMBER_REMOVED)
+    };
+    let (project_id, user_id) = (item.id, user.id);
+    audit
+        .transaction(&ctx.db, |txn| {
+            Box::pin(async move {
+                project_members::Entity::delete_many()
+                    .filter(project_members::Column::ProjectId.eq(project_id))
+                    .filter(project_members::Column::UserId.eq(user_id))
+                    .exec(txn)
+                    .await?;
+                Ok(((), event))
+            })
+        })
+        .await?;
+    super::no_content()
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix(&format!("{}/projects/", super::PREFIX))
+        .add("/", get(list))
+        .add("/", post(add))
+        .add("{id}", get(show))
+        .add("{id}", patch(update))
+        .add("{id}", delete(remove))
+        .add("{id}/members", get(members))
+        .add("{id}/members", put(put_member))
+        .add("{id}/members/{pid}", delete(remove_member))
+}
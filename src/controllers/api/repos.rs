@@ -0,0 +1,278 @@
+#![allow(clippy::missing_errors_doc)]
+use axum::{
+    extract::rejection::{JsonRejection, QueryRejection},
+    http::StatusCode,
+    Json,
+};
+use loco_rs::prelude::*;
+use sea_orm::QueryOrder;
+use serde::{Deserialize, Serialize};
+
+use super::{bad_json, bad_query, invalid, parsed};
+use crate::{
+    controllers::{audit::Audit, current_user::CurrentUser},
+    forges::RepoRef,
+    models::{
+        _entities::repos::{Column, Entity, Model},
+        api_keys::Scope,
+        audit_events::{self, NewEvent},
+        project_members::Role,
+        projects,
+    },
+    settings::Settings,
+    workers::fetch_repo::{FetchRepoWorker, FetchRepoWorkerArgs},
+};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ListParams {
+    #[serde(flatten)]
+    pub pagination: query::PaginationQuery,
+    /// A stat column, `name`, `owner` or `last_fetch`, `-` first for
+    /// descending.
+    pub sort: Option<String>,
+    #[serde(default, deserialize_with = "parsed")]
+    pub project_id: Option<i32>,
+    pub forge: Option<String>,
+    pub host: Option<String>,
+    pub owner: Option<String>,
+    pub sync_status: Option<String>,
+    #[serde(default, deserialize_with = "parsed")]
+    pub archived: Option<bool>,
+    /// Part of the name.
+    pub q: Option<String>,
+}
+
+/// A repo to import, as a URL or `owner/name`, like the import form.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CreateParams {
+    pub repo: String,
+    pub project_id: Option<i32>,
+}
+
+/// Stats come from the forge, a repo can only be moved to another project.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateParams {
+    pub project_id: i32,
+}
+
+/// A repo queued to be fetched.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct QueuedResponse {
+    pub status: String,
+    pub forge: String,
+    pub host: String,
+    pub owner: String,
+    pub name: String,
+    pub project_id: Option<i32>,
+}
+
+async fn load_item(ctx: &AppContext, id: i32) -> Result<Model> {
+    let item = Entity::find_by_id(id).one(&ctx.db).await?;
+    item.ok_or_else(|| Error::NotFound)
+}
+
+async fn check_project(ctx: &AppContext, project_id: i32) -> Result<projects::Model> {
+    projects::Entity::find_by_id(project_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| invalid(&format!("project {project_id} does not exist")))
+}
+
+#[debug_handler]
+pub async fn list(
+    State(ctx): State<AppContext>,
+    params: std::result::Result<Query<ListParams>, QueryRejection>,
+) -> Result<Response> {
+    let Query(params) = params.map_err(bad_query)?;
+    let (column, order) = super::sort_order(
+        params.sort.as_deref(),
+        Column::Id,
+        &[
+            ("id", Column::Id),
+            ("name", Column::Name),
+            ("owner", Column::Owner),
+            ("stars", Column::Stars),
+            ("forks", Column::Forks),
+            ("issues", Column::Issues),
+            ("prs", Column::Prs),
+            ("contributors", Column::Contributors),
+            ("commits_last_30d", Column::CommitsLast30d),
+            ("watchers", Column::Watchers),
+            ("releases", Column::Releases),
+            ("last_fetch", Column::LastFetch),
+        ],
+    )?;
+
+    let mut condition = query::condition();
+    if let Some(project_id) = params.project_id {
+        condition = condition.eq(Column::ProjectId, project_id);
+    }
+    for (column, value) in [
+        (Column::Forge, &params.forge),
+        (Column::Host, &params.host),
+        (Column::Owner, &params.owner),
+        (Column::SyncStatus, &params.sync_status),
+    ] {
+        if let Some(value) = value {
+            condition = condition.eq(column, value.as_str());
+        }
+    }
+    if let Some(archived) = params.archived {
+        condition = condition.eq(Column::Archived, archived);
+    }
+    if let Some(q) = params.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        condition = condition.contains(Column::Name, q);
+    }
+
+    let pagination = super::page_query(&params.pagination);
+    let select = Entity::find()
+        .order_by(column, order)
+        .order_by_asc(Column::Id);
+    let res = query::paginate(&ctx.db, select, Some(condition.build()), &pagination).await?;
+    super::page(&pagination, res)
+}
+
+#[debug_handler]
+pub async fn show(Path(id): Path<i32>, State(ctx): State<AppContext>) -> Result<Response> {
+    format::json(load_item(&ctx, id).await?)
+}
+
+/// Queue the repo to be fetched from its forge, answering `202 Accepted`.
+#[debug_handler]
+pub async fn add(
+    current: CurrentUser,
+    audit: Audit,
+    State(ctx): State<AppContext>,
+    params: std::result::Result<Json<CreateParams>, JsonRejection>,
+) -> Result<Response> {
+    current.require(Scope::Repos)?;
+    let Json(params) = params.map_err(bad_json)?;
+    let settings = Settings::from_config(&ctx.config)?;
+    let repo =
+        RepoRef::parse(&params.repo, &settings.forges).map_err(|err| invalid(&err.to_string()))?;
+    if let Some(project_id) = params.project_id {
+        check_project(&ctx, project_id).await?;
+        current
+            .authorize(&ctx, project_id, Role::Maintainer)
+            .await?;
+        // moving a tracked repo also changes the project it is in now
+        let tracked =
+            Entity::find_by_ref(&ctx.db, repo.forge, &repo.host, &repo.owner, &repo.name).await?;
+        if let Some(tracked) = tracked.filter(|tracked| tracked.project_id != project_id) {
+            current
+                .authorize(&ctx, tracked.project_id, Role::Maintainer)
+                .await?;
+        }
+    }
+
+    let queued = QueuedResponse {
+        status: "queued".to_string(),
+        forge: repo.forge.to_string(),
+        host: repo.host.clone(),
+        owner: repo.owner.clone(),
+        name: repo.name.clone(),
+        project_id: params.project_id,
+    };
+    NewEvent {
+        actor_id: Some(current.user.id),
+        details: Some(serde_json::to_value(&queued)?),
+        ..audit.event(audit_events::REPO_QUEUED)
+    }
+    .record(&ctx.db)
+    .await?;
+    FetchRepoWorker::perform_later(
+        &ctx,
+        FetchRepoWorkerArgs {
+            forge: repo.forge,
+            host: repo.host,
+            owner: repo.owner,
+            name: repo.name,
+            project_id: params.project_id,
+            user_id: Some(current.user.id),
+        },
+    )
+    .await?;
+    format::render().status(StatusCode::ACCEPTED).json(queued)
+}
+
+#[debug_handler]
+pub async fn update(
+    Path(id): Path<i32>,
+    current: CurrentUser,
+    audit: Audit,
+    State(ctx): State<AppContext>,
+    params: std::result::Result<Json<UpdateParams>, JsonRejection>,
+) -> Result<Response> {
+    current.require(Scope::Repos)?;
+    let Json(params) = params.map_err(bad_json)?;
+    let item = load_item(&ctx, id).await?;
+    current
+        .authorize(&ctx, item.project_id, Role::Maintainer)
+        .await?;
+    check_project(&ctx, params.project_id).await?;
+    current
+        .authorize(&ctx, params.project_id, Role::Maintainer)
+        .await?;
+
+    let before = item.clone();
+    let mut item = item.into_active_model();
+    item.project_id = Set(params.project_id);
+    let actor_id = current.user.id;
+    let item = audit
+        .transaction(&ctx.db, |txn| {
+            Box::pin(async move {
+                let item = item.update(txn).await?;
+                for project_id in [before.project_id, item.project_id] {
+                    projects::Entity::recalculate_health_of(txn, project_id).await?;
+                }
+                let event = NewEvent::change(
+                    audit_events::REPO_UPDATED,
+                    actor_id,
+                    Some(&before),
+                    Some(&item),
+                );
+                Ok((item, event))
+            })
+        })
+        .await?;
+    format::json(item)
+}
+
+#[debug_handler]
+pub async fn remove(
+    Path(id): Path<i32>,
+    current: CurrentUser,
+    audit: Audit,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    current.require(Scope::Repos)?;
+    let item = load_item(&ctx, id).await?;
+    current
+        .authorize(&ctx, item.project_id, Role::Maintainer)
+        .await?;
+    let actor_id = current.user.id;
+    audit
+        .transaction(&ctx.db, |txn| {
+            Box::pin(async move {
+                item.clone().delete(txn).await?;
+                projects::Entity::recalculate_health_of(txn, item.project_id).await?;
+                let event =
+                    NewEvent::change(audit_events::REPO_DELETED, actor_id, Some(&item), None);
+                Ok(((), event))
+            })
+        })
+        .await?;
+    super::no_content()
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix(&format!("{}/repos/", super::PREFIX))
+        .add("/", get(list))
+        .add("/", post(add))
+        .add("{id}", get(show))
+        .add("{id}", patch(update))
+        .add("{id}", delete(remove))
+}
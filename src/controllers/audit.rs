@@ -0,0 +1,92 @@
+//! Where a request came from, for the audit log, see
+//! [`crate::models::audit_events`].
+use std::{convert::Infallible, future::Future, pin::Pin};
+
+use axum::{
+    extract::FromRequestParts,
+    http::{header, request::Parts},
+};
+use loco_rs::{
+    controller::middleware::{remote_ip::RemoteIP, request_id::LocoRequestId},
+    prelude::*,
+};
+use sea_orm::DatabaseTransaction;
+
+use crate::models::audit_events::NewEvent;
+
+/// The client and request an event is recorded for.
+#[derive(Debug, Clone, Default)]
+pub struct Audit {
+    /// `None` when the `remote_ip` middleware is off.
+    pub ip: Option<String>,
+    /// `None` when the `request_id` middleware is off.
+    pub request_id: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl<S> FromRequestParts<S> for Audit
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Infallible> {
+        let ip = match RemoteIP::from_request_parts(parts, state).await {
+            Ok(RemoteIP::Forwarded(ip) | RemoteIP::Socket(ip)) => Some(ip.to_string()),
+            Ok(RemoteIP::None) | Err(_) => None,
+        };
+        Ok(Self {
+            ip,
+            request_id: parts
+                .extensions
+                .get::<LocoRequestId>()
+                .map(|id| id.get().to_string()),
+            user_agent: parts
+                .headers
+                .get(header::USER_AGENT)
+                .and_then(|agent| agent.to_str().ok())
+                .map(str::to_string),
+        })
+    }
+}
+
+impl Audit {
+    /// An event for `action` in this request.
+    #[must_use]
+    pub fn event(&self, action: &str) -> NewEvent {
+        NewEvent {
+            ip: self.ip.clone(),
+            request_id: self.request_id.clone(),
+            ..NewEvent::new(action)
+        }
+    }
+
+    /// Make a change and record the event `change` returns along with its
+    /// result, in this request and in one transaction, so neither is stored
+    /// without the other. See [`NewEvent::change`] for edits of a row.
+    ///
+    /// # Errors
+    ///
+    /// What `change` fails with, or DB Error.
+    pub async fn transaction<R, F>(&self, db: &DatabaseConnection, change: F) -> Result<R>
+    where
+        R: Send,
+        F: for<'c> FnOnce(
+                &'c DatabaseTransaction,
+            )
+                -> Pin<Box<dyn Future<Output = Result<(R, NewEvent)>> + Send + 'c>>
+            + Send,
+    {
+        let txn = db.begin().await?;
+        let (result, event) = change(&txn).await?;
+        NewEvent {
+            ip: self.ip.clone(),
+            request_id: self.request_id.clone(),
+            ..event
+        }
+        .record(&txn)
+        .await?;
+        txn.commit().await?;
+        Ok(result)
+    }
+}
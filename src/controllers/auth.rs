@@ -1,23 +1,18 @@
 use crate::{
+    controllers::{audit::Audit, browser, current_user::SignedIn, throttle::Attempt, two_factor},
     mailers::auth::AuthMailer,
     models::{
         _entities::users,
+        audit_events::{self, NewEvent},
+        email_domain_rules, sessions, totp_credentials,
         users::{LoginParams, RegisterParams},
     },
-    views::auth::{CurrentResponse, LoginResponse},
+    settings::Settings,
+    views::auth::{CurrentResponse, LoginResponse, SessionResponse},
 };
 use loco_rs::prelude::*;
-use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::sync::OnceLock;
-
-pub static EMAIL_DOMAIN_RE: OnceLock<Regex> = OnceLock::new();
-
-fn get_allow_email_domain_re() -> &'static Regex {
-    EMAIL_DOMAIN_RE.get_or_init(|| {
-        Regex::new(r"@example\.com$|@gmail\.com$").expect("Failed to compile regex")
-    })
-}
+use serde_json::json;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ForgotParams {
@@ -40,11 +35,86 @@ pub struct ResendVerificationParams {
     pub email: String,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RefreshParams {
+    pub refresh_token: String,
+}
+
+/// Answer a successful first step of signing in as `user`. That is a
+/// session, unless the user has two-factor authentication on, in which case
+/// it is a challenge to answer at `/api/auth/2fa/verify`.
+///
+/// # Errors
+///
+/// When the session could not be saved or the token generated.
+pub(crate) async fn signed_in(
+    ctx: &AppContext,
+    user: &users::Model,
+    audit: &Audit,
+) -> Result<Response> {
+    if totp_credentials::Entity::enabled_for(&ctx.db, user.id)
+        .await?
+        .is_some()
+    {
+        return two_factor::challenge(ctx, user);
+    }
+    start_session(ctx, user, audit).await
+}
+
+/// Answer a sign in of `user` from the client of `audit`, with a
+/// short-lived token and the refresh token of a new session.
+///
+/// # Errors
+///
+/// When the session could not be saved or the token generated.
+pub(crate) async fn start_session(
+    ctx: &AppContext,
+    user: &users::Model,
+    audit: &Audit,
+) -> Result<Response> {
+    let settings = Settings::from_config(&ctx.config)?;
+    let started = sessions::Entity::start(
+        &ctx.db,
+        &settings.sessions,
+        user.id,
+        audit.user_agent.as_deref(),
+    )
+    .await?;
+    NewEvent {
+        actor_id: Some(user.id),
+        target: Some(user.pid.to_string()),
+        details: Some(json!({ "session_id": started.session.id })),
+        ..audit.event(audit_events::AUTH_SIGNED_IN)
+    }
+    .record(&ctx.db)
+    .await?;
+    respond_with_session(ctx, user, &started)
+}
+
+fn respond_with_session(
+    ctx: &AppContext,
+    user: &users::Model,
+    started: &sessions::Started,
+) -> Result<Response> {
+    let jwt_secret = ctx.config.get_jwt_config()?;
+    let token = user
+        .generate_session_jwt(
+            &jwt_secret.secret,
+            jwt_secret.expiration,
+            started.session.id,
+        )
+        .or_else(|_| unauthorized("unauthorized!"))?;
+    format::render()
+        .cookies(&[browser::session_cookie(ctx, &started.session)?])?
+        .json(LoginResponse::new(user, &token, &started.refresh_token))
+}
+
 /// Register function creates a new user with the given parameters and sends a
 /// welcome email to the user
 #[debug_handler]
 async fn register(
     State(ctx): State<AppContext>,
+    audit: Audit,
     Json(params): Json<RegisterParams>,
 ) -> Result<Response> {
     let res = users::Model::create_with_password(&ctx.db, &params).await;
@@ -65,6 +135,13 @@ async fn register(
         .into_active_model()
         .set_email_verification_sent(&ctx.db)
         .await?;
+    NewEvent {
+        actor_id: Some(user.id),
+        target: Some(user.pid.to_string()),
+        ..audit.event(audit_events::AUTH_REGISTERED)
+    }
+    .record(&ctx.db)
+    .await?;
 
     AuthMailer::send_welcome(&ctx, &user).await?;
 
@@ -74,7 +151,11 @@ async fn register(
 /// Verify register user. if the user not verified his email, he can't login to
 /// the system.
 #[debug_handler]
-async fn verify(State(ctx): State<AppContext>, Path(token): Path<String>) -> Result<Response> {
+async fn verify(
+    State(ctx): State<AppContext>,
+    audit: Audit,
+    Path(token): Path<String>,
+) -> Result<Response> {
     let Ok(user) = users::Model::find_by_verification_token(&ctx.db, &token).await else {
         return unauthorized("invalid token");
     };
@@ -85,6 +166,13 @@ async fn verify(State(ctx): State<AppContext>, Path(token): Path<String>) -> Res
         let active_model = user.into_active_model();
         let user = active_model.verified(&ctx.db).await?;
         tracing::info!(pid = user.pid.to_string(), "user verified");
+        NewEvent {
+            actor_id: Some(user.id),
+            target: Some(user.pid.to_string()),
+            ..audit.event(audit_events::AUTH_VERIFIED)
+        }
+        .record(&ctx.db)
+        .await?;
     }
 
     format::json(())
@@ -97,8 +185,16 @@ async fn verify(State(ctx): State<AppContext>, Path(token): Path<String>) -> Res
 #[debug_handler]
 async fn forgot(
     State(ctx): State<AppContext>,
+    audit: Audit,
     Json(params): Json<ForgotParams>,
 ) -> Result<Response> {
+    let attempt = Attempt::new(&ctx, "forgot", &audit, &params.email)?;
+    if let Some(locked_out) = attempt.locked_out(&ctx).await? {
+        return Ok(locked_out);
+    }
+    // every request sends an email, so they all count
+    attempt.count(&ctx).await?;
+
     let Ok(user) = users::Model::find_by_email(&ctx.db, &params.email).await else {
         // we don't want to expose our users email. if the email is invalid we still
         // returning success to the caller
@@ -117,7 +213,11 @@ async fn forgot(
 
 /// reset user password by the given parameters
 #[debug_handler]
-async fn reset(State(ctx): State<AppContext>, Json(params): Json<ResetParams>) -> Result<Response> {
+async fn reset(
+    State(ctx): State<AppContext>,
+    audit: Audit,
+    Json(params): Json<ResetParams>,
+) -> Result<Response> {
     let Ok(user) = users::Model::find_by_reset_token(&ctx.db, &params.token).await else {
         // we don't want to expose our users email. if the email is invalid we still
         // returning success to the caller
@@ -125,43 +225,165 @@ async fn reset(State(ctx): State<AppContext>, Json(params): Json<ResetParams>) -
 
         return format::json(());
     };
-    user.into_active_model()
+    let user = user
+        .into_active_model()
         .reset_password(&ctx.db, &params.password)
         .await?;
+    // whoever knew the old password may have signed in with it
+    let revoked = sessions::Entity::revoke_all(&ctx.db, user.id).await?;
+    NewEvent {
+        actor_id: Some(user.id),
+        target: Some(user.pid.to_string()),
+        details: Some(json!({ "sessions_revoked": revoked })),
+        ..audit.event(audit_events::AUTH_PASSWORD_RESET)
+    }
+    .record(&ctx.db)
+    .await?;
 
     format::json(())
 }
 
-/// Creates a user login and returns a token
+/// Write down a failed sign in, by `user_id` when the email address is
+/// theirs.
+async fn sign_in_failed(
+    ctx: &AppContext,
+    audit: &Audit,
+    user_id: Option<i32>,
+    email: &str,
+) -> Result<()> {
+    NewEvent {
+        actor_id: user_id,
+        target: Some(email.trim().to_lowercase()),
+        details: Some(json!({
+            "reason": if user_id.is_some() { "wrong_password" } else { "unknown_email" },
+        })),
+        ..audit.event(audit_events::AUTH_SIGN_IN_FAILED)
+    }
+    .record(&ctx.db)
+    .await?;
+    Ok(())
+}
+
+/// Creates a user login and returns a token, or a challenge for the second
+/// factor when the user has one
 #[debug_handler]
-async fn login(State(ctx): State<AppContext>, Json(params): Json<LoginParams>) -> Result<Response> {
+async fn login(
+    State(ctx): State<AppContext>,
+    audit: Audit,
+    Json(params): Json<LoginParams>,
+) -> Result<Response> {
+    let attempt = Attempt::new(&ctx, "login", &audit, &params.email)?;
+    if let Some(locked_out) = attempt.locked_out(&ctx).await? {
+        return Ok(locked_out);
+    }
+
     let Ok(user) = users::Model::find_by_email(&ctx.db, &params.email).await else {
         tracing::debug!(
             email = params.email,
             "login attempt with non-existent email"
         );
+        sign_in_failed(&ctx, &audit, None, &params.email).await?;
+        attempt.count(&ctx).await?;
         return unauthorized("Invalid credentials!");
     };
 
     let valid = user.verify_password(&params.password);
 
     if !valid {
+        sign_in_failed(&ctx, &audit, Some(user.id), &params.email).await?;
+        attempt.count(&ctx).await?;
         return unauthorized("unauthorized!");
     }
+    attempt.succeeded(&ctx).await?;
 
-    let jwt_secret = ctx.config.get_jwt_config()?;
+    signed_in(&ctx, &user, &audit).await
+}
 
-    let token = user
-        .generate_jwt(&jwt_secret.secret, jwt_secret.expiration)
-        .or_else(|_| unauthorized("unauthorized!"))?;
+/// Trade a refresh token for a new token and refresh token. The old refresh
+/// token stops working.
+#[debug_handler]
+async fn refresh(
+    State(ctx): State<AppContext>,
+    Json(params): Json<RefreshParams>,
+) -> Result<Response> {
+    let settings = Settings::from_config(&ctx.config)?;
+    let Some(started) =
+        sessions::Entity::refresh(&ctx.db, &settings.sessions, &params.refresh_token).await?
+    else {
+        return unauthorized("invalid refresh token");
+    };
+    let Some(user) = users::Entity::find_by_id(started.session.user_id)
+        .one(&ctx.db)
+        .await?
+    else {
+        return unauthorized("invalid refresh token");
+    };
+    respond_with_session(&ctx, &user, &started)
+}
 
-    format::json(LoginResponse::new(&user, &token))
+/// The sessions of the current user that can still be refreshed.
+#[debug_handler]
+async fn list_sessions(auth: SignedIn, State(ctx): State<AppContext>) -> Result<Response> {
+    let current = Some(auth.session.id);
+    let sessions = sessions::Entity::active_for_user(&ctx.db, auth.user.id).await?;
+    format::json(
+        sessions
+            .iter()
+            .map(|session| SessionResponse::new(session, current))
+            .collect::<Vec<_>>(),
+    )
 }
 
+/// Sign out one session of the current user.
 #[debug_handler]
-async fn current(auth: auth::JWT, State(ctx): State<AppContext>) -> Result<Response> {
-    let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
-    format::json(CurrentResponse::new(&user))
+async fn revoke_session(
+    auth: SignedIn,
+    audit: Audit,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let Some(session) = sessions::Entity::find_by_id(id)
+        .filter(sessions::Column::UserId.eq(auth.user.id))
+        .filter(sessions::Column::RevokedAt.is_null())
+        .one(&ctx.db)
+        .await?
+    else {
+        return not_found();
+    };
+    let session = session.revoke(&ctx.db).await?;
+    NewEvent {
+        actor_id: Some(auth.user.id),
+        target: Some(auth.user.pid.to_string()),
+        details: Some(json!({ "session_id": session.id })),
+        ..audit.event(audit_events::AUTH_SIGNED_OUT)
+    }
+    .record(&ctx.db)
+    .await?;
+    format::json(())
+}
+
+/// Sign out every session of the current user, this one included.
+#[debug_handler]
+async fn revoke_sessions(
+    auth: SignedIn,
+    audit: Audit,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let revoked = sessions::Entity::revoke_all(&ctx.db, auth.user.id).await?;
+    NewEvent {
+        actor_id: Some(auth.user.id),
+        target: Some(auth.user.pid.to_string()),
+        details: Some(json!({ "revoked": revoked })),
+        ..audit.event(audit_events::AUTH_SIGNED_OUT)
+    }
+    .record(&ctx.db)
+    .await?;
+    format::json(())
+}
+
+#[debug_handler]
+async fn current(auth: SignedIn, State(_ctx): State<AppContext>) -> Result<Response> {
+    format::json(CurrentResponse::new(&auth.user))
 }
 
 /// Magic link authentication provides a secure and passwordless way to log in to the application.
@@ -169,26 +391,38 @@ async fn current(auth: auth::JWT, State(ctx): State<AppContext>) -> Result<Respo
 /// # Flow
 /// 1. **Request a Magic Link**:
 ///    A registered user sends a POST request to `/magic-link` with their email.
+///    The email's domain must be allowed, see [`crate::email_domains`], or the
+///    request is rejected with a 400 that says so.
 ///    If the email exists, a short-lived, one-time-use token is generated and sent to the user's email.
 ///    For security and to avoid exposing whether an email exists, the response always returns 200, even if the email is invalid.
 ///
 /// 2. **Click the Magic Link**:
 ///    The user clicks the link (/magic-link/{token}), which validates the token and its expiration.
-///    If valid, the server generates a JWT and responds with a [`LoginResponse`].
+///    If valid, the server generates a JWT and responds with a [`LoginResponse`],
+///    or with a two-factor challenge when the user has two-factor authentication on.
 ///    If invalid or expired, an unauthorized response is returned.
 ///
 /// This flow enhances security by avoiding traditional passwords and providing a seamless login experience.
 async fn magic_link(
     State(ctx): State<AppContext>,
+    audit: Audit,
     Json(params): Json<MagicLinkParams>,
 ) -> Result<Response> {
-    let email_regex = get_allow_email_domain_re();
-    if !email_regex.is_match(&params.email) {
+    let attempt = Attempt::new(&ctx, "magic_link", &audit, &params.email)?;
+    if let Some(locked_out) = attempt.locked_out(&ctx).await? {
+        return Ok(locked_out);
+    }
+    attempt.count(&ctx).await?;
+
+    let settings = Settings::from_config(&ctx.config)?;
+    let rules = email_domain_rules::Entity::effective(&ctx.db, &settings.magic_link).await?;
+    if let Err(rejected) = rules.check(&params.email) {
         tracing::debug!(
             email = params.email,
-            "The provided email is invalid or does not match the allowed domains"
+            reason = rejected.to_string(),
+            "magic link email domain rejected"
         );
-        return bad_request("invalid request");
+        return bad_request(rejected.to_string());
     }
 
     let Ok(user) = users::Model::find_by_email(&ctx.db, &params.email).await else {
@@ -208,6 +442,7 @@ async fn magic_link(
 async fn magic_link_verify(
     Path(token): Path<String>,
     State(ctx): State<AppContext>,
+    audit: Audit,
 ) -> Result<Response> {
     let Ok(user) = users::Model::find_by_magic_token(&ctx.db, &token).await else {
         // we don't want to expose our users email. if the email is invalid we still
@@ -217,20 +452,21 @@ async fn magic_link_verify(
 
     let user = user.into_active_model().clear_magic_link(&ctx.db).await?;
 
-    let jwt_secret = ctx.config.get_jwt_config()?;
-
-    let token = user
-        .generate_jwt(&jwt_secret.secret, jwt_secret.expiration)
-        .or_else(|_| unauthorized("unauthorized!"))?;
-
-    format::json(LoginResponse::new(&user, &token))
+    signed_in(&ctx, &user, &audit).await
 }
 
 #[debug_handler]
 async fn resend_verification_email(
     State(ctx): State<AppContext>,
+    audit: Audit,
     Json(params): Json<ResendVerificationParams>,
 ) -> Result<Response> {
+    let attempt = Attempt::new(&ctx, "resend_verification", &audit, &params.email)?;
+    if let Some(locked_out) = attempt.locked_out(&ctx).await? {
+        return Ok(locked_out);
+    }
+    attempt.count(&ctx).await?;
+
     let Ok(user) = users::Model::find_by_email(&ctx.db, &params.email).await else {
         tracing::info!(
             email = params.email,
@@ -264,6 +500,10 @@ pub fn routes() -> Routes {
         .add("/register", post(register))
         .add("/verify/{token}", get(verify))
         .add("/login", post(login))
+        .add("/refresh", post(refresh))
+        .add("/sessions", get(list_sessions))
+        .add("/sessions", delete(revoke_sessions))
+        .add("/sessions/{id}", delete(revoke_session))
         .add("/forgot", post(forgot))
         .add("/reset", post(reset))
         .add("/current", get(current))
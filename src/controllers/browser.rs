@@ -0,0 +1,179 @@
+//! Signing in from a browser, which can not send an `Authorization` header
+//! with a form.
+//!
+//! Every sign in also sets a session cookie, see [`session_cookie`], that
+//! [`super::current_user::CurrentUser`] takes in place of the header. Because
+//! a browser sends it along with requests other sites make too, changes made
+//! with it need a CSRF token of the same session: the `csrf_token` field of a
+//! form, or the `X-CSRF-Token` header of a script. Pages with forms get the
+//! token with [`CsrfToken`].
+use axum::{
+    body::{to_bytes, Body},
+    extract::{FromRef, FromRequestParts, Request},
+    http::{header, request::Parts, HeaderValue},
+    middleware::Next,
+};
+use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
+use chrono::{DateTime, Utc};
+use loco_rs::prelude::*;
+
+use crate::{
+    models::{sessions, users},
+    signed,
+};
+
+/// Holds the signed id of the session a browser signed in with.
+pub const SESSION_COOKIE: &str = "session";
+/// The header scripts send the CSRF token in.
+pub const CSRF_HEADER: &str = "x-csrf-token";
+/// The form field forms send the CSRF token in.
+pub const CSRF_FIELD: &str = "csrf_token";
+
+/// What session cookies and CSRF tokens are signed for, see [`signed`].
+const SESSION_PURPOSE: &str = "browser-session";
+const CSRF_PURPOSE: &str = "csrf";
+/// How long a page with a form may be open before the form is sent.
+const CSRF_TTL_SECS: i64 = 24 * 60 * 60;
+/// Forms are small, a bigger one is rejected.
+const FORM_LIMIT: usize = 64 * 1024;
+
+/// The cookie that keeps a browser signed in with `session`, until the
+/// session expires or is revoked.
+///
+/// # Errors
+///
+/// When the JWT config, whose secret signs the cookie, is missing.
+pub fn session_cookie(ctx: &AppContext, session: &sessions::Model) -> Result<Cookie<'static>> {
+    let secret = &ctx.config.get_jwt_config()?.secret;
+    let now = Utc::now();
+    let ttl = (session.expires_at.with_timezone(&Utc) - now).num_seconds();
+    let value = signed::sign(
+        secret,
+        SESSION_PURPOSE,
+        &[&session.id.to_string(), &session.user_id.to_string()],
+        now,
+        ttl,
+    );
+    Ok(Cookie::build((SESSION_COOKIE, value))
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .max_age(::cookie::time::Duration::seconds(ttl))
+        .build())
+}
+
+/// The session id and user id signed into the session cookie of a request.
+fn signed_session(secret: &str, parts: &Parts, now: DateTime<Utc>) -> Option<(i32, i32)> {
+    let jar = CookieJar::from_headers(&parts.headers);
+    let fields = signed::verify(
+        secret,
+        SESSION_PURPOSE,
+        jar.get(SESSION_COOKIE)?.value(),
+        now,
+    )?;
+    let [session_id, user_id] = fields.as_slice() else {
+        return None;
+    };
+    Some((session_id.parse().ok()?, user_id.parse().ok()?))
+}
+
+fn csrf_token(secret: &str, session_id: i32, now: DateTime<Utc>) -> String {
+    signed::sign(
+        secret,
+        CSRF_PURPOSE,
+        &[&session_id.to_string()],
+        now,
+        CSRF_TTL_SECS,
+    )
+}
+
+/// The user signed in with the session cookie of the request, `None` when
+/// it has none. Changes need the CSRF token of the session, requests that
+/// only read do not.
+///
+/// # Errors
+///
+/// `401 Unauthorized` when the session has ended, `403 Forbidden` when the
+/// CSRF token is missing or wrong, or on DB errors.
+pub(crate) async fn signed_in(ctx: &AppContext, parts: &Parts) -> Result<Option<users::Model>> {
+    let secret = &ctx.config.get_jwt_config()?.secret;
+    let now = Utc::now();
+    let Some((session_id, user_id)) = signed_session(secret, parts, now) else {
+        return Ok(None);
+    };
+    if !parts.method.is_safe() {
+        let token = parts
+            .headers
+            .get(CSRF_HEADER)
+            .and_then(|token| token.to_str().ok())
+            .and_then(|token| signed::verify(secret, CSRF_PURPOSE, token, now));
+        if token != Some(vec![session_id.to_string()]) {
+            return Err(super::current_user::forbidden(
+                "missing or invalid csrf token, reload the page and try again",
+            ));
+        }
+    }
+    sessions::Entity::live(&ctx.db, session_id, user_id)
+        .await?
+        .ok_or_else(|| Error::Unauthorized("session has ended".to_string()))?;
+    let user = users::Entity::find_by_id(user_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| Error::Unauthorized("not found".to_string()))?;
+    Ok(Some(user))
+}
+
+/// The CSRF token for the forms of a page, `None` when the browser is not
+/// signed in with a session cookie.
+pub struct CsrfToken(pub Option<String>);
+
+impl<S> FromRequestParts<S> for CsrfToken
+where
+    AppContext: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self> {
+        let ctx = AppContext::from_ref(state);
+        let secret = &ctx.config.get_jwt_config()?.secret;
+        let now = Utc::now();
+        Ok(Self(signed_session(secret, parts, now).map(
+            |(session_id, _)| csrf_token(secret, session_id, now),
+        )))
+    }
+}
+
+/// Move the CSRF token of a submitted form into the [`CSRF_HEADER`], where
+/// [`signed_in`] checks it, and out of the form, whose fields are checked
+/// like any other input.
+pub async fn csrf_form_field(request: Request, next: Next) -> Response {
+    let is_form = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/x-www-form-urlencoded"));
+    if request.method().is_safe() || !is_form || request.headers().contains_key(CSRF_HEADER) {
+        return next.run(request).await;
+    }
+
+    let (mut parts, body) = request.into_parts();
+    let Ok(bytes) = to_bytes(body, FORM_LIMIT).await else {
+        return Error::BadRequest("form is too large".to_string()).into_response();
+    };
+    let (tokens, fields): (Vec<_>, Vec<_>) =
+        form_urlencoded::parse(&bytes).partition(|(key, _)| key == CSRF_FIELD);
+    let Some((_, token)) = tokens.first() else {
+        return next
+            .run(Request::from_parts(parts, Body::from(bytes)))
+            .await;
+    };
+    if let Ok(token) = HeaderValue::from_str(token) {
+        parts.headers.insert(CSRF_HEADER, token);
+    }
+    let body = form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(fields)
+        .finish();
+    parts.headers.remove(header::CONTENT_LENGTH);
+    next.run(Request::from_parts(parts, Body::from(body))).await
+}
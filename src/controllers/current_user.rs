@@ -0,0 +1,172 @@
+//! Who is making a request, for the routes that change data.
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::{header, request::Parts, StatusCode},
+};
+use loco_rs::{
+    controller::{extractor::auth, ErrorDetail},
+    prelude::*,
+};
+
+use super::browser;
+use crate::models::{
+    api_keys::{self, Scope},
+    project_members::{self, Role},
+    sessions,
+    users::{self, SESSION_CLAIM},
+};
+
+/// A user signed in with a JWT `Authorization: Bearer <token>` of a session
+/// that is still live.
+///
+/// Access tokens are checked against their session, so signing out, or a
+/// password reset, ends them right away instead of when they expire.
+pub struct SignedIn {
+    pub user: users::Model,
+    /// The session the token was issued for.
+    pub session: sessions::Model,
+}
+
+impl<S> FromRequestParts<S> for SignedIn
+where
+    AppContext: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self> {
+        let jwt = auth::JWTWithUser::<users::Model>::from_request_parts(parts, state).await?;
+        let session_id = jwt
+            .claims
+            .claims
+            .get(SESSION_CLAIM)
+            .and_then(serde_json::Value::as_i64)
+            .and_then(|id| i32::try_from(id).ok())
+            .ok_or_else(|| Error::Unauthorized("token has no session".to_string()))?;
+        let ctx = AppContext::from_ref(state);
+        let session = sessions::Entity::live(&ctx.db, session_id, jwt.user.id)
+            .await?
+            .ok_or_else(|| Error::Unauthorized("session has ended".to_string()))?;
+        Ok(Self {
+            user: jwt.user,
+            session,
+        })
+    }
+}
+
+/// The signed in user, from a JWT or an API key sent as
+/// `Authorization: Bearer <token>`, or else from the session cookie of a
+/// browser, see [`super::browser`].
+///
+/// Rejects the request with `401 Unauthorized` when none is valid.
+pub struct CurrentUser {
+    pub user: users::Model,
+    /// The key the request was made with, `None` for a JWT.
+    pub key: Option<api_keys::Model>,
+}
+
+impl<S> FromRequestParts<S> for CurrentUser
+where
+    AppContext: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self> {
+        let ctx = AppContext::from_ref(state);
+        if !parts.headers.contains_key(header::AUTHORIZATION) {
+            if let Some(user) = browser::signed_in(&ctx, parts).await? {
+                return Ok(Self { user, key: None });
+            }
+        }
+        let secret = auth::extract_token_from_header(&parts.headers)?;
+        // JWTs are dot separated, API keys never have a dot
+        if secret.contains('.') {
+            let signed_in = SignedIn::from_request_parts(parts, state).await?;
+            return Ok(Self {
+                user: signed_in.user,
+                key: None,
+            });
+        }
+
+        let key = api_keys::Entity::authenticate(&ctx.db, &secret)
+            .await?
+            .ok_or_else(|| Error::Unauthorized("unknown or revoked api key".to_string()))?;
+        let user = users::Entity::find_by_id(key.user_id)
+            .one(&ctx.db)
+            .await?
+            .ok_or_else(|| Error::Unauthorized("not found".to_string()))?;
+        Ok(Self {
+            user,
+            key: Some(key),
+        })
+    }
+}
+
+/// A signed in user with [`users::Model::is_admin`] set.
+///
+/// Admin actions are not open to API keys, they need a session.
+pub struct Admin(pub users::Model);
+
+impl<S> FromRequestParts<S> for Admin
+where
+    AppContext: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self> {
+        let current = CurrentUser::from_request_parts(parts, state).await?;
+        if current.key.is_some() {
+            return Err(forbidden("admin actions can not be made with an api key"));
+        }
+        if !current.user.is_admin {
+            return Err(forbidden("you need to be an admin"));
+        }
+        Ok(Self(current.user))
+    }
+}
+
+pub(crate) fn forbidden(description: &str) -> Error {
+    Error::CustomError(
+        StatusCode::FORBIDDEN,
+        ErrorDetail::new("forbidden", description),
+    )
+}
+
+impl CurrentUser {
+    /// Check that an API key was given `scope`, a JWT may do anything.
+    ///
+    /// # Errors
+    ///
+    /// `403 Forbidden` when the key lacks the scope.
+    pub fn require(&self, scope: Scope) -> Result<()> {
+        match &self.key {
+            Some(key) if !key.allows(scope) => Err(forbidden(&format!(
+                "api key `{}` lacks the `{}` scope",
+                key.name,
+                scope.as_str()
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that the user has at least role `min` in `project_id`.
+    ///
+    /// # Errors
+    ///
+    /// `403 Forbidden` when they do not, or on DB errors.
+    pub async fn authorize(&self, ctx: &AppContext, project_id: i32, min: Role) -> Result<Role> {
+        match project_members::Entity::role_of(&ctx.db, project_id, self.user.id).await? {
+            Some(role) if role >= min => Ok(role),
+            _ => Err(forbidden(&format!(
+                "you need to be {} of project {project_id}",
+                match min {
+                    Role::Viewer => "a viewer",
+                    Role::Maintainer => "a maintainer",
+                    Role::Owner => "the owner",
+                }
+            ))),
+        }
+    }
+}
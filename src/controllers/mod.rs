@@ -1,4 +1,14 @@
+pub mod account;
+pub mod admin;
+pub mod api;
+pub mod audit;
 pub mod auth;
+pub mod browser;
+pub mod current_user;
+pub mod oauth;
 
 pub mod project;
 pub mod repo;
+pub mod throttle;
+pub mod two_factor;
+pub mod webhooks;
@@ -0,0 +1,185 @@
+//! Sign in with GitHub, and linking a GitHub account to a signed in user.
+//!
+//! `GET /api/auth/github` sends the browser to GitHub, which sends it back to
+//! `/api/auth/github/callback`. That answers like the other logins, with a
+//! [`crate::views::auth::LoginResponse`]. To link an account instead, a signed in user asks
+//! `POST /api/auth/github/link` for the URL to send the browser to.
+//!
+//! Both set a short-lived cookie with the nonce signed into the state, the
+//! callback only completes in the browser that has it.
+use axum::http::StatusCode;
+use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
+use chrono::Utc;
+use loco_rs::{controller::ErrorDetail, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    controllers::{audit::Audit, auth::signed_in, current_user::SignedIn},
+    forges::Forge,
+    models::{
+        audit_events::{self, NewEvent},
+        user_identities, users,
+    },
+    oauth::{self, github::GitHubOAuth, Purpose},
+    settings::Settings,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct CallbackParams {
+    pub code: String,
+    pub state: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LinkResponse {
+    pub url: String,
+}
+
+fn oauth_error(err: oauth::Error) -> Error {
+    match err {
+        oauth::Error::NotConfigured(_) => Error::NotFound,
+        oauth::Error::Refused(..) | oauth::Error::NoVerifiedEmail(_) => {
+            Error::Unauthorized(err.to_string())
+        }
+        oauth::Error::Http(_) => {
+            tracing::warn!(err = err.to_string(), "github sign in failed");
+            Error::CustomError(
+                StatusCode::BAD_GATEWAY,
+                ErrorDetail::new("bad_gateway", "could not reach github"),
+            )
+        }
+        oauth::Error::InvalidUrl(..) => Error::Message(err.to_string()),
+    }
+}
+
+fn client(ctx: &AppContext) -> Result<GitHubOAuth> {
+    let settings = Settings::from_config(&ctx.config)?;
+    GitHubOAuth::new(&settings.oauth.github).map_err(oauth_error)
+}
+
+/// Keeps the nonce of a sign in in the browser that started it.
+const STATE_COOKIE: &str = "oauth_state";
+const STATE_COOKIE_PATH: &str = "/api/auth/github";
+
+fn state_cookie(nonce: String) -> Cookie<'static> {
+    Cookie::build((STATE_COOKIE, nonce))
+        .path(STATE_COOKIE_PATH)
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .max_age(::cookie::time::Duration::seconds(oauth::STATE_TTL_SECS))
+        .build()
+}
+
+/// The URL to send the browser to, and the cookie to send along.
+fn authorize_url(ctx: &AppContext, purpose: &Purpose) -> Result<(String, Cookie<'static>)> {
+    let secret = ctx.config.get_jwt_config()?.secret.clone();
+    let nonce = Purpose::nonce();
+    let url = client(ctx)?
+        .authorize_url(&purpose.sign(&secret, &nonce, Utc::now()))
+        .map_err(oauth_error)?;
+    Ok((url.to_string(), state_cookie(nonce)))
+}
+
+/// Send the browser to GitHub to sign in.
+#[debug_handler]
+async fn sign_in(State(ctx): State<AppContext>) -> Result<Response> {
+    let (url, cookie) = authorize_url(&ctx, &Purpose::SignIn)?;
+    format::render().cookies(&[cookie])?.redirect(&url)
+}
+
+/// Where to send the browser to link a GitHub account to the signed in user.
+#[debug_handler]
+async fn link(auth: SignedIn, State(ctx): State<AppContext>) -> Result<Response> {
+    let (url, cookie) = authorize_url(&ctx, &Purpose::Link(auth.user.pid.to_string()))?;
+    format::render()
+        .cookies(&[cookie])?
+        .json(LinkResponse { url })
+}
+
+/// Forget the GitHub account linked to the signed in user.
+#[debug_handler]
+async fn unlink(auth: SignedIn, audit: Audit, State(ctx): State<AppContext>) -> Result<Response> {
+    if !user_identities::Entity::unlink(&ctx.db, auth.user.id, Forge::Github).await? {
+        return not_found();
+    }
+    NewEvent {
+        actor_id: Some(auth.user.id),
+        target: Some(auth.user.pid.to_string()),
+        details: Some(serde_json::json!({ "provider": Forge::Github })),
+        ..audit.event(audit_events::IDENTITY_UNLINKED)
+    }
+    .record(&ctx.db)
+    .await?;
+    format::json(())
+}
+
+/// GitHub sends the browser back here with a code for the account.
+#[debug_handler]
+async fn callback(
+    State(ctx): State<AppContext>,
+    audit: Audit,
+    jar: CookieJar,
+    Query(params): Query<CallbackParams>,
+) -> Result<Response> {
+    let jwt = ctx.config.get_jwt_config()?;
+    let nonce = jar.get(STATE_COOKIE).map(Cookie::value).unwrap_or_default();
+    let Some(purpose) = Purpose::verify(&jwt.secret, &params.state, nonce, Utc::now()) else {
+        return unauthorized("invalid or expired state");
+    };
+    // the state is used up
+    let jar = jar.remove(Cookie::build(STATE_COOKIE).path(STATE_COOKIE_PATH));
+    Ok((jar, completed(&ctx, &audit, purpose, &params.code).await?).into_response())
+}
+
+/// Sign in or link with the account `code` is for.
+async fn completed(
+    ctx: &AppContext,
+    audit: &Audit,
+    purpose: Purpose,
+    code: &str,
+) -> Result<Response> {
+    let account = client(ctx)?.account(code).await.map_err(oauth_error)?;
+
+    match purpose {
+        Purpose::SignIn => {
+            let Some(user) =
+                user_identities::Entity::sign_in(&ctx.db, Forge::Github, &account).await?
+            else {
+                return Err(oauth_error(oauth::Error::NoVerifiedEmail("GitHub")));
+            };
+            signed_in(ctx, &user, audit).await
+        }
+        Purpose::Link(pid) => {
+            let user = users::Model::find_by_pid(&ctx.db, &pid).await?;
+            match user_identities::Entity::link(&ctx.db, user.id, Forge::Github, &account).await {
+                Ok(identity) => {
+                    NewEvent {
+                        actor_id: Some(user.id),
+                        target: Some(user.pid.to_string()),
+                        details: Some(serde_json::json!({
+                            "provider": identity.provider,
+                            "login": identity.login,
+                        })),
+                        ..audit.event(audit_events::IDENTITY_LINKED)
+                    }
+                    .record(&ctx.db)
+                    .await?;
+                    format::json(identity)
+                }
+                Err(ModelError::EntityAlreadyExists) => {
+                    bad_request("the github account is linked to another user")
+                }
+                Err(err) => Err(err.into()),
+            }
+        }
+    }
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("/api/auth/github")
+        .add("/", get(sign_in))
+        .add("/link", post(link))
+        .add("/link", delete(unlink))
+        .add("/callback", get(callback))
+}
@@ -3,31 +3,89 @@
 #![allow(clippy::unused_async)]
 use axum::response::Redirect;
 use axum_extra::extract::Form;
+use chrono::Utc;
 use loco_rs::prelude::*;
 use sea_orm::{sea_query::Order, QueryOrder};
 use serde::{Deserialize, Serialize};
 
+use super::{
+    api::invalid, audit::Audit, browser::CsrfToken, current_user::CurrentUser, repo::empty_as_none,
+};
 use crate::{
-    models::_entities::projects::{ActiveModel, Column, Entity, Model},
+    forges::{listing, ImportFilter},
+    health::Strategy,
+    models::{
+        _entities::projects::{ActiveModel, Column, Entity, Model},
+        api_keys::Scope,
+        audit_events::{self, NewEvent},
+        project_members::{self, Role},
+    },
     views,
+    workers::import_repos::{ImportReposWorker, ImportReposWorkerArgs},
 };
 
+/// The create and edit forms. Health and the last fetch are derived from
+/// the project's repos, so they are rejected like any other unknown field.
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Params {
     pub name: String,
     pub owner: String,
     pub description: Option<String>,
-    pub health: f32,
-    pub last_fetch: DateTime,
+    /// A [`Strategy`], unchanged when left out.
+    pub health_strategy: Option<String>,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub primary_repo_id: Option<i32>,
+}
+
+/// Import the repos of a GitHub account, as submitted by the import form.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ImportParams {
+    pub account: String,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub project_id: Option<i32>,
+    /// Checkboxes, present when ticked.
+    pub include_archived: Option<String>,
+    pub include_forks: Option<String>,
+    pub topic: Option<String>,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub min_stars: Option<i32>,
+}
+
+impl ImportParams {
+    fn filter(&self) -> ImportFilter {
+        ImportFilter {
+            include_archived: self.include_archived.is_some(),
+            include_forks: self.include_forks.is_some(),
+            topic: self
+                .topic
+                .as_deref()
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(str::to_string),
+            min_stars: self.min_stars.unwrap_or_default(),
+        }
+    }
 }
 
 impl Params {
-    fn update(&self, item: &mut ActiveModel) {
+    fn update(&self, item: &mut ActiveModel) -> Result<()> {
         item.name = Set(self.name.clone());
         item.owner = Set(self.owner.clone());
-        item.description = Set(self.description.clone());
-        item.health = Set(self.health);
-        item.last_fetch = Set(self.last_fetch);
+        item.description = Set(self
+            .description
+            .as_deref()
+            .map(str::trim)
+            .filter(|d| !d.is_empty())
+            .map(str::to_string));
+        if let Some(health_strategy) = &self.health_strategy {
+            let strategy: Strategy = health_strategy
+                .parse()
+                .map_err(|err: String| invalid(&err))?;
+            item.health_strategy = Set(strategy.as_str().to_string());
+        }
+        item.primary_repo_id = Set(self.primary_repo_id);
+        Ok(())
     }
 }
 
@@ -50,33 +108,63 @@ pub async fn list(
 
 #[debug_handler]
 pub async fn new(
+    CsrfToken(csrf_token): CsrfToken,
     ViewEngine(v): ViewEngine<TeraView>,
     State(_ctx): State<AppContext>,
 ) -> Result<Response> {
-    views::project::create(&v)
+    views::project::create(&v, csrf_token.as_deref())
 }
 
 #[debug_handler]
 pub async fn update(
     Path(id): Path<i32>,
+    current: CurrentUser,
+    audit: Audit,
     State(ctx): State<AppContext>,
     Form(params): Form<Params>,
 ) -> Result<Redirect> {
-    let item = load_item(&ctx, id).await?;
-    let mut item = item.into_active_model();
-    params.update(&mut item);
-    item.update(&ctx.db).await?;
+    current.require(Scope::Projects)?;
+    let before = load_item(&ctx, id).await?;
+    current.authorize(&ctx, before.id, Role::Maintainer).await?;
+    if let Some(repo_id) = params.primary_repo_id {
+        if !before.has_repo(&ctx.db, repo_id).await? {
+            return Err(invalid(&format!("repo {repo_id} is not in this project")));
+        }
+    }
+    let mut item = before.clone().into_active_model();
+    params.update(&mut item)?;
+    let actor_id = current.user.id;
+    audit
+        .transaction(&ctx.db, |txn| {
+            Box::pin(async move {
+                let item = item.update(txn).await?;
+                item.recalculate_health(txn).await?;
+                let item = Entity::find_by_id(id)
+                    .one(txn)
+                    .await?
+                    .ok_or(Error::NotFound)?;
+                let event = NewEvent::change(
+                    audit_events::PROJECT_UPDATED,
+                    actor_id,
+                    Some(&before),
+                    Some(&item),
+                );
+                Ok(((), event))
+            })
+        })
+        .await?;
     Ok(Redirect::to("../projects"))
 }
 
 #[debug_handler]
 pub async fn edit(
     Path(id): Path<i32>,
+    CsrfToken(csrf_token): CsrfToken,
     ViewEngine(v): ViewEngine<TeraView>,
     State(ctx): State<AppContext>,
 ) -> Result<Response> {
     let item = load_item(&ctx, id).await?;
-    views::project::edit(&v, &item)
+    views::project::edit(&v, &item, csrf_token.as_deref())
 }
 
 #[debug_handler]
@@ -90,18 +178,106 @@ pub async fn show(
 }
 
 #[debug_handler]
-pub async fn add(State(ctx): State<AppContext>, Form(params): Form<Params>) -> Result<Redirect> {
+pub async fn add(
+    current: CurrentUser,
+    audit: Audit,
+    State(ctx): State<AppContext>,
+    Form(params): Form<Params>,
+) -> Result<Redirect> {
+    current.require(Scope::Projects)?;
+    if params.primary_repo_id.is_some() {
+        return Err(invalid("a new project has no repos to make primary"));
+    }
     let mut item = ActiveModel {
+        health: Set(100.),
+        last_fetch: Set(Utc::now().naive_utc()),
         ..Default::default()
     };
-    params.update(&mut item);
-    item.insert(&ctx.db).await?;
+    params.update(&mut item)?;
+    let actor_id = current.user.id;
+    audit
+        .transaction(&ctx.db, |txn| {
+            Box::pin(async move {
+                let item = item.insert(txn).await?;
+                project_members::Entity::grant(txn, item.id, actor_id, Role::Owner).await?;
+                let event =
+                    NewEvent::change(audit_events::PROJECT_CREATED, actor_id, None, Some(&item));
+                Ok(((), event))
+            })
+        })
+        .await?;
     Ok(Redirect::to("projects"))
 }
 
 #[debug_handler]
-pub async fn remove(Path(id): Path<i32>, State(ctx): State<AppContext>) -> Result<Response> {
-    load_item(&ctx, id).await?.delete(&ctx.db).await?;
+pub async fn import_form(
+    CsrfToken(csrf_token): CsrfToken,
+    ViewEngine(v): ViewEngine<TeraView>,
+    State(_ctx): State<AppContext>,
+) -> Result<Response> {
+    views::project::import(&v, &ImportParams::default(), None, csrf_token.as_deref())
+}
+
+#[debug_handler]
+pub async fn import(
+    current: CurrentUser,
+    CsrfToken(csrf_token): CsrfToken,
+    ViewEngine(v): ViewEngine<TeraView>,
+    State(ctx): State<AppContext>,
+    Form(params): Form<ImportParams>,
+) -> Result<Response> {
+    current.require(Scope::Repos)?;
+    let account = match listing::parse_account(&params.account) {
+        Ok(account) => account,
+        Err(err) => {
+            let err = err.to_string();
+            return views::project::import(&v, &params, Some(&err), csrf_token.as_deref());
+        }
+    };
+    if let Some(project_id) = params.project_id {
+        if Entity::find_by_id(project_id).one(&ctx.db).await?.is_none() {
+            let err = format!("project {project_id} does not exist");
+            return views::project::import(&v, &params, Some(&err), csrf_token.as_deref());
+        }
+        current
+            .authorize(&ctx, project_id, Role::Maintainer)
+            .await?;
+    }
+
+    ImportReposWorker::perform_later(
+        &ctx,
+        ImportReposWorkerArgs {
+            account,
+            filter: params.filter(),
+            project_id: params.project_id,
+            user_id: Some(current.user.id),
+        },
+    )
+    .await?;
+    Ok(Redirect::to("/projects").into_response())
+}
+
+#[debug_handler]
+pub async fn remove(
+    Path(id): Path<i32>,
+    current: CurrentUser,
+    audit: Audit,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    current.require(Scope::Projects)?;
+    let item = load_item(&ctx, id).await?;
+    current.authorize(&ctx, item.id, Role::Owner).await?;
+    let actor_id = current.user.id;
+    audit
+        .transaction(&ctx.db, |txn| {
+            Box::pin(async move {
+                item.clone().delete(txn).await?;
+                let event =
+                    NewEvent::change(audit_events::PROJECT_DELETED, actor_id, Some(&item), None);
+                Ok(((), event))
+            })
+        })
+        .await?;
     format::empty()
 }
 
@@ -111,6 +287,8 @@ pub fn routes() -> Routes {
         .add("/", get(list))
         .add("/", post(add))
         .add("new", get(new))
+        .add("import", get(import_form))
+        .add("import", post(import))
         .add("{id}", get(show))
         .add("{id}/edit", get(edit))
         .add("{id}", delete(remove))
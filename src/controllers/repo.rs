@@ -7,41 +7,47 @@ use loco_rs::prelude::*;
 use sea_orm::{sea_query::Order, QueryOrder};
 use serde::{Deserialize, Serialize};
 
+use super::{audit::Audit, browser::CsrfToken, current_user::CurrentUser};
 use crate::{
-    models::_entities::repos::{ActiveModel, Column, Entity, Model},
+    forges::RepoRef,
+    models::{
+        _entities::repos::{Column, Entity, Model},
+        api_keys::Scope,
+        audit_events::{self, NewEvent},
+        project_members::Role,
+        projects,
+    },
+    settings::Settings,
     views,
+    workers::fetch_repo::{FetchRepoWorker, FetchRepoWorkerArgs},
 };
 
+/// The edit form. Stats and where the repo lives come from the forge, so a
+/// repo can only be moved to another project; any other field is rejected.
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Params {
     pub project_id: i32,
-    pub name: String,
-    pub owner: String,
-    pub stars: i32,
-    pub forks: i32,
-    pub issues: i32,
-    pub prs: i32,
-    pub contributors: i32,
-    pub commits_last_30d: i32,
-    pub watchers: i32,
-    pub license: Option<String>,
-    pub last_fetch: DateTime,
 }
 
-impl Params {
-    fn update(&self, item: &mut ActiveModel) {
-        item.project_id = Set(self.project_id);
-        item.name = Set(self.name.clone());
-        item.owner = Set(self.owner.clone());
-        item.stars = Set(self.stars);
-        item.forks = Set(self.forks);
-        item.issues = Set(self.issues);
-        item.prs = Set(self.prs);
-        item.contributors = Set(self.contributors);
-        item.commits_last_30d = Set(self.commits_last_30d);
-        item.watchers = Set(self.watchers);
-        item.license = Set(self.license.clone());
-        item.last_fetch = Set(self.last_fetch);
+/// A repo to import, as a URL or `owner/name`. Stats are never taken from
+/// the form, they are fetched from the forge.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ImportParams {
+    pub repo: String,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub project_id: Option<i32>,
+}
+
+/// An empty number input is submitted as `project_id=`.
+pub(crate) fn empty_as_none<'de, D>(deserializer: D) -> std::result::Result<Option<i32>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+    match value.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(value) => value.parse().map(Some).map_err(serde::de::Error::custom),
     }
 }
 
@@ -64,33 +70,63 @@ pub async fn list(
 
 #[debug_handler]
 pub async fn new(
+    CsrfToken(csrf_token): CsrfToken,
     ViewEngine(v): ViewEngine<TeraView>,
     State(_ctx): State<AppContext>,
 ) -> Result<Response> {
-    views::repo::create(&v)
+    views::repo::create(&v, "", None, None, csrf_token.as_deref())
 }
 
 #[debug_handler]
 pub async fn update(
     Path(id): Path<i32>,
+    current: CurrentUser,
+    audit: Audit,
     State(ctx): State<AppContext>,
     Form(params): Form<Params>,
 ) -> Result<Redirect> {
-    let item = load_item(&ctx, id).await?;
-    let mut item = item.into_active_model();
-    params.update(&mut item);
-    item.update(&ctx.db).await?;
+    current.require(Scope::Repos)?;
+    let before = load_item(&ctx, id).await?;
+    current
+        .authorize(&ctx, before.project_id, Role::Maintainer)
+        .await?;
+    if params.project_id != before.project_id {
+        current
+            .authorize(&ctx, params.project_id, Role::Maintainer)
+            .await?;
+    }
+    let mut item = before.clone().into_active_model();
+    item.project_id = Set(params.project_id);
+    let actor_id = current.user.id;
+    audit
+        .transaction(&ctx.db, |txn| {
+            Box::pin(async move {
+                let item = item.update(txn).await?;
+                for project_id in [before.project_id, item.project_id] {
+                    projects::Entity::recalculate_health_of(txn, project_id).await?;
+                }
+                let event = NewEvent::change(
+                    audit_events::REPO_UPDATED,
+                    actor_id,
+                    Some(&before),
+                    Some(&item),
+                );
+                Ok(((), event))
+            })
+        })
+        .await?;
     Ok(Redirect::to("../repos"))
 }
 
 #[debug_handler]
 pub async fn edit(
     Path(id): Path<i32>,
+    CsrfToken(csrf_token): CsrfToken,
     ViewEngine(v): ViewEngine<TeraView>,
     State(ctx): State<AppContext>,
 ) -> Result<Response> {
     let item = load_item(&ctx, id).await?;
-    views::repo::edit(&v, &item)
+    views::repo::edit(&v, &item, csrf_token.as_deref())
 }
 
 #[debug_handler]
@@ -104,18 +140,95 @@ pub async fn show(
 }
 
 #[debug_handler]
-pub async fn add(State(ctx): State<AppContext>, Form(params): Form<Params>) -> Result<Redirect> {
-    let mut item = ActiveModel {
-        ..Default::default()
+pub async fn add(
+    current: CurrentUser,
+    audit: Audit,
+    CsrfToken(csrf_token): CsrfToken,
+    ViewEngine(v): ViewEngine<TeraView>,
+    State(ctx): State<AppContext>,
+    Form(params): Form<ImportParams>,
+) -> Result<Response> {
+    current.require(Scope::Repos)?;
+    let settings = Settings::from_config(&ctx.config)?;
+    let repo = match RepoRef::parse(&params.repo, &settings.forges) {
+        Ok(repo) => repo,
+        Err(err) => {
+            return views::repo::create(
+                &v,
+                &params.repo,
+                params.project_id,
+                Some(&err.to_string()),
+                csrf_token.as_deref(),
+            );
+        }
     };
-    params.update(&mut item);
-    item.insert(&ctx.db).await?;
-    Ok(Redirect::to("repos"))
+
+    if let Some(project_id) = params.project_id {
+        current
+            .authorize(&ctx, project_id, Role::Maintainer)
+            .await?;
+        // moving a tracked repo also changes the project it is in now
+        let tracked =
+            Entity::find_by_ref(&ctx.db, repo.forge, &repo.host, &repo.owner, &repo.name).await?;
+        if let Some(tracked) = tracked.filter(|tracked| tracked.project_id != project_id) {
+            current
+                .authorize(&ctx, tracked.project_id, Role::Maintainer)
+                .await?;
+        }
+    }
+
+    NewEvent {
+        actor_id: Some(current.user.id),
+        details: Some(serde_json::json!({
+            "forge": repo.forge,
+            "host": repo.host,
+            "owner": repo.owner,
+            "name": repo.name,
+            "project_id": params.project_id,
+        })),
+        ..audit.event(audit_events::REPO_QUEUED)
+    }
+    .record(&ctx.db)
+    .await?;
+    FetchRepoWorker::perform_later(
+        &ctx,
+        FetchRepoWorkerArgs {
+            forge: repo.forge,
+            host: repo.host,
+            owner: repo.owner,
+            name: repo.name,
+            project_id: params.project_id,
+            user_id: Some(current.user.id),
+        },
+    )
+    .await?;
+    Ok(Redirect::to("repos").into_response())
 }
 
 #[debug_handler]
-pub async fn remove(Path(id): Path<i32>, State(ctx): State<AppContext>) -> Result<Response> {
-    load_item(&ctx, id).await?.delete(&ctx.db).await?;
+pub async fn remove(
+    Path(id): Path<i32>,
+    current: CurrentUser,
+    audit: Audit,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    current.require(Scope::Repos)?;
+    let item = load_item(&ctx, id).await?;
+    current
+        .authorize(&ctx, item.project_id, Role::Maintainer)
+        .await?;
+    let actor_id = current.user.id;
+    audit
+        .transaction(&ctx.db, |txn| {
+            Box::pin(async move {
+                item.clone().delete(txn).await?;
+                projects::Entity::recalculate_health_of(txn, item.project_id).await?;
+                let event =
+                    NewEvent::change(audit_events::REPO_DELETED, actor_id, Some(&item), None);
+                Ok(((), event))
+            })
+        })
+        .await?;
     format::empty()
 }
 
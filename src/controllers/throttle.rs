@@ -0,0 +1,117 @@
+//! Brute-force protection for the auth endpoints, see
+//! [`crate::models::auth_throttles`].
+use axum::http::{header, StatusCode};
+use chrono::Utc;
+use loco_rs::{controller::ErrorDetail, prelude::*};
+use serde_json::json;
+
+use super::audit::Audit;
+use crate::{
+    models::{
+        audit_events::{self, NewEvent},
+        auth_throttles,
+    },
+    settings::Settings,
+};
+
+/// An attempt at `action` for an account from an IP address, counted against
+/// both.
+pub struct Attempt {
+    settings: auth_throttles::Settings,
+    audit: Audit,
+    ip_key: Option<String>,
+    account_key: String,
+}
+
+impl Attempt {
+    /// An attempt at `action` for `account`, an email address or pid.
+    ///
+    /// # Errors
+    ///
+    /// When the `throttle` settings are invalid.
+    pub fn new(ctx: &AppContext, action: &str, audit: &Audit, account: &str) -> Result<Self> {
+        let settings = Settings::from_config(&ctx.config)?.throttle;
+        Ok(Self {
+            settings,
+            // without the remote_ip middleware all requests would share one key
+            ip_key: audit.ip.as_ref().map(|ip| format!("{action}:ip:{ip}")),
+            audit: audit.clone(),
+            account_key: format!("{action}:account:{}", account.trim().to_lowercase()),
+        })
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.ip_key
+            .iter()
+            .chain([&self.account_key])
+            .cloned()
+            .collect()
+    }
+
+    /// `429 Too Many Requests` while the IP address or the account is locked
+    /// out, `None` when the attempt may go ahead.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn locked_out(&self, ctx: &AppContext) -> Result<Option<Response>> {
+        let Some(until) = auth_throttles::Entity::locked_until(&ctx.db, &self.keys()).await? else {
+            return Ok(None);
+        };
+        let retry_after = (until.with_timezone(&Utc) - Utc::now())
+            .num_seconds()
+            .max(1);
+        tracing::info!(keys = ?self.keys(), retry_after, "auth attempt locked out");
+        format::render()
+            .header(header::RETRY_AFTER, retry_after.to_string())
+            .status(StatusCode::TOO_MANY_REQUESTS)
+            .json(ErrorDetail::new(
+                "too_many_requests",
+                &format!("too many attempts, try again in {retry_after} seconds"),
+            ))
+            .map(Some)
+    }
+
+    /// Count the attempt, locking out the IP address or the account when it
+    /// was one too many.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn count(&self, ctx: &AppContext) -> Result<()> {
+        let limits = self
+            .ip_key
+            .iter()
+            .map(|key| (key, self.settings.per_ip))
+            .chain([(&self.account_key, self.settings.per_account)]);
+        for (key, limit) in limits {
+            if let Some(throttle) =
+                auth_throttles::Entity::hit(&ctx.db, &self.settings, key, limit).await?
+            {
+                tracing::warn!(key, locked_until = ?throttle.locked_until, "auth lockout");
+                NewEvent {
+                    target: Some(key.clone()),
+                    details: Some(json!({
+                        "locked_until": throttle.locked_until,
+                        "lockouts": throttle.lockouts,
+                    })),
+                    ..self.audit.event(audit_events::AUTH_LOCKOUT)
+                }
+                .record(&ctx.db)
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Forget the failed attempts for the account after a successful one.
+    /// Those from the IP address still count.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn succeeded(&self, ctx: &AppContext) -> Result<()> {
+        auth_throttles::Entity::clear(&ctx.db, &self.account_key).await?;
+        Ok(())
+    }
+}
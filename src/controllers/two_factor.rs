@@ -0,0 +1,200 @@
+//! Two-factor authentication with TOTP codes from an authenticator app.
+//!
+//! A signed in user enrolls at `/api/auth/2fa/enroll`, which returns a secret
+//! and its `otpauth://` URI for a QR code, and turns it on by confirming a
+//! code at `/api/auth/2fa/confirm`, which returns recovery codes. From then
+//! on signing in answers with a challenge, see [`challenge`], that is traded
+//! with a code or a recovery code for a session at `/api/auth/2fa/verify`.
+use chrono::Utc;
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    controllers::{audit::Audit, auth::start_session, current_user::SignedIn, throttle::Attempt},
+    models::{
+        audit_events::{self, NewEvent},
+        recovery_codes, totp_credentials, users,
+    },
+    signed, totp,
+    views::auth::{EnrollResponse, RecoveryCodesResponse, TwoFactorResponse},
+};
+
+/// Shown as the account's issuer in authenticator apps.
+const ISSUER: &str = "gooncityhub";
+
+/// How long the second step may take after the first.
+const CHALLENGE_TTL_SECS: i64 = 300;
+
+/// What challenges are signed for, see [`signed`].
+const CHALLENGE_PURPOSE: &str = "2fa-challenge";
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CodeParams {
+    /// A code from the authenticator app, or a recovery code.
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct VerifyParams {
+    pub challenge: String,
+    /// A code from the authenticator app, or a recovery code.
+    pub code: String,
+}
+
+/// Answer the first step of signing in as `user` with a challenge for the
+/// second.
+///
+/// # Errors
+///
+/// When the JWT config, whose secret signs challenges, is missing.
+pub(crate) fn challenge(ctx: &AppContext, user: &users::Model) -> Result<Response> {
+    let secret = &ctx.config.get_jwt_config()?.secret;
+    let challenge = signed::sign(
+        secret,
+        CHALLENGE_PURPOSE,
+        &[&user.pid.to_string()],
+        Utc::now(),
+        CHALLENGE_TTL_SECS,
+    );
+    format::json(TwoFactorResponse::new(&challenge))
+}
+
+/// The pid of the user `challenge` was issued to, `None` when it was
+/// tampered with or has expired.
+fn challenged_pid(secret: &str, challenge: &str) -> Option<String> {
+    let fields = signed::verify(secret, CHALLENGE_PURPOSE, challenge, Utc::now())?;
+    let [pid] = fields.as_slice() else {
+        return None;
+    };
+    Some(pid.clone())
+}
+
+/// Whether `code` is a current code or an unused recovery code of `user_id`,
+/// using it up.
+async fn second_factor(ctx: &AppContext, user_id: i32, code: &str) -> Result<bool> {
+    let Some(credential) = totp_credentials::Entity::enabled_for(&ctx.db, user_id).await? else {
+        return Ok(false);
+    };
+    Ok(credential.check(&ctx.db, code).await?
+        || recovery_codes::Entity::redeem(&ctx.db, user_id, code).await?)
+}
+
+/// Start turning on two-factor authentication with a new secret.
+#[debug_handler]
+async fn enroll(auth: SignedIn, State(ctx): State<AppContext>) -> Result<Response> {
+    if totp_credentials::Entity::enabled_for(&ctx.db, auth.user.id)
+        .await?
+        .is_some()
+    {
+        return bad_request("two-factor authentication is already on");
+    }
+    let credential = totp_credentials::Entity::enroll(&ctx.db, auth.user.id).await?;
+    let uri = totp::provisioning_uri(&credential.secret_bytes(), ISSUER, &auth.user.email);
+    format::json(EnrollResponse {
+        secret: credential.secret,
+        uri,
+    })
+}
+
+/// Turn two-factor authentication on with a code of the enrolled secret.
+#[debug_handler]
+async fn confirm(
+    auth: SignedIn,
+    audit: Audit,
+    State(ctx): State<AppContext>,
+    Json(params): Json<CodeParams>,
+) -> Result<Response> {
+    let Some(credential) = totp_credentials::Entity::for_user(&ctx.db, auth.user.id)
+        .await?
+        .filter(|credential| !credential.is_enabled())
+    else {
+        return bad_request("enroll first");
+    };
+    if !credential.check(&ctx.db, &params.code).await? {
+        return bad_request("invalid code");
+    }
+    let credential = totp_credentials::Entity::find_by_id(credential.id)
+        .one(&ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    credential.confirm(&ctx.db).await?;
+    let recovery_codes = recovery_codes::Entity::regenerate(&ctx.db, auth.user.id).await?;
+    NewEvent {
+        actor_id: Some(auth.user.id),
+        target: Some(auth.user.pid.to_string()),
+        ..audit.event(audit_events::TWO_FACTOR_ENABLED)
+    }
+    .record(&ctx.db)
+    .await?;
+    format::json(RecoveryCodesResponse { recovery_codes })
+}
+
+/// Turn two-factor authentication off, which takes a code.
+#[debug_handler]
+async fn disable(
+    auth: SignedIn,
+    audit: Audit,
+    State(ctx): State<AppContext>,
+    Json(params): Json<CodeParams>,
+) -> Result<Response> {
+    if !second_factor(&ctx, auth.user.id, &params.code).await? {
+        return bad_request("invalid code");
+    }
+    totp_credentials::Entity::disable(&ctx.db, auth.user.id).await?;
+    NewEvent {
+        actor_id: Some(auth.user.id),
+        target: Some(auth.user.pid.to_string()),
+        ..audit.event(audit_events::TWO_FACTOR_DISABLED)
+    }
+    .record(&ctx.db)
+    .await?;
+    format::json(())
+}
+
+/// New recovery codes in place of the old ones, which takes a code.
+#[debug_handler]
+async fn regenerate_recovery_codes(
+    auth: SignedIn,
+    State(ctx): State<AppContext>,
+    Json(params): Json<CodeParams>,
+) -> Result<Response> {
+    if !second_factor(&ctx, auth.user.id, &params.code).await? {
+        return bad_request("invalid code");
+    }
+    let recovery_codes = recovery_codes::Entity::regenerate(&ctx.db, auth.user.id).await?;
+    format::json(RecoveryCodesResponse { recovery_codes })
+}
+
+/// Finish signing in with the challenge from the first step and a code.
+#[debug_handler]
+async fn verify(
+    State(ctx): State<AppContext>,
+    audit: Audit,
+    Json(params): Json<VerifyParams>,
+) -> Result<Response> {
+    let secret = &ctx.config.get_jwt_config()?.secret;
+    let Some(pid) = challenged_pid(secret, &params.challenge) else {
+        return unauthorized("invalid or expired challenge");
+    };
+    let attempt = Attempt::new(&ctx, "2fa", &audit, &pid)?;
+    if let Some(locked_out) = attempt.locked_out(&ctx).await? {
+        return Ok(locked_out);
+    }
+    let user = users::Model::find_by_pid(&ctx.db, &pid).await?;
+    if !second_factor(&ctx, user.id, &params.code).await? {
+        attempt.count(&ctx).await?;
+        return unauthorized("invalid code");
+    }
+    attempt.succeeded(&ctx).await?;
+    start_session(&ctx, &user, &audit).await
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("/api/auth/2fa")
+        .add("/enroll", post(enroll))
+        .add("/confirm", post(confirm))
+        .add("/disable", post(disable))
+        .add("/recovery_codes", post(regenerate_recovery_codes))
+        .add("/verify", post(verify))
+}
@@ -0,0 +1,68 @@
+use axum::{body::Bytes, http::HeaderMap};
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{settings::Settings, webhooks};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WebhookResponse {
+    pub status: String,
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+/// Receives GitHub webhook deliveries.
+///
+/// Deliveries are verified with the configured secret and applied to the
+/// matching repo once, keyed by `X-GitHub-Delivery`. Events for repos we do
+/// not track are acknowledged and dropped, so GitHub does not retry them.
+#[debug_handler]
+async fn github(
+    State(ctx): State<AppContext>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response> {
+    let settings = Settings::from_config(&ctx.config)?;
+    let Some(secret) = settings.webhooks.github.secret() else {
+        tracing::warn!("github webhook delivered, but no webhook secret is configured");
+        return unauthorized("webhooks are not configured");
+    };
+
+    let signature = header(&headers, "x-hub-signature-256").unwrap_or_default();
+    if !webhooks::github::verify_signature(secret, &body, signature) {
+        tracing::info!("github webhook with invalid signature");
+        return unauthorized("invalid signature");
+    }
+
+    let (Some(event), Some(delivery_id)) = (
+        header(&headers, "x-github-event"),
+        header(&headers, "x-github-delivery"),
+    ) else {
+        return bad_request("missing event or delivery id");
+    };
+
+    let outcome = webhooks::github::handle(&ctx.db, delivery_id, event, &body)
+        .await
+        .map_err(|err| match err {
+            Error::JSON(err) => Error::BadRequest(err.to_string()),
+            err => err,
+        })?;
+    tracing::debug!(
+        event,
+        delivery_id,
+        outcome = outcome.as_str(),
+        "github webhook"
+    );
+
+    format::json(WebhookResponse {
+        status: outcome.as_str().to_string(),
+    })
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("/api/webhooks")
+        .add("/github", post(github))
+}
@@ -0,0 +1,70 @@
+//! Which email domains may sign in with a magic link.
+//!
+//! The rules come from the `magic_link` settings, or from
+//! `email_domain_rules` when an admin has set any there.
+use serde::{Deserialize, Serialize};
+
+/// Domain patterns: `corp.example` matches that domain, `*.corp.example` its
+/// subdomains and `*` every domain. A denied domain is rejected even when it
+/// is also allowed.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Rules {
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            allow: vec!["example.com".to_string(), "gmail.com".to_string()],
+            deny: vec![],
+        }
+    }
+}
+
+/// Why an email may not sign in with a magic link.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Rejected {
+    #[error("`{0}` is not an email address")]
+    NoDomain(String),
+    #[error("magic links are disabled for `{0}` addresses")]
+    Denied(String),
+    #[error("magic links are not enabled for `{0}` addresses")]
+    NotAllowed(String),
+}
+
+/// Whether `domain` matches `pattern`, both lowercase.
+fn matches(pattern: &str, domain: &str) -> bool {
+    match pattern.strip_prefix('*') {
+        Some("") => true,
+        Some(suffix) if suffix.starts_with('.') => domain.ends_with(suffix),
+        _ => pattern == domain,
+    }
+}
+
+impl Rules {
+    /// # Errors
+    ///
+    /// [`Rejected`] when `email` may not sign in with a magic link.
+    pub fn check(&self, email: &str) -> Result<(), Rejected> {
+        let domain = email
+            .rsplit_once('@')
+            .map(|(_, domain)| domain.trim().to_lowercase())
+            .filter(|domain| !domain.is_empty())
+            .ok_or_else(|| Rejected::NoDomain(email.to_string()))?;
+        let any = |patterns: &[String]| {
+            patterns
+                .iter()
+                .any(|pattern| matches(&pattern.trim().to_lowercase(), &domain))
+        };
+        if any(&self.deny) {
+            Err(Rejected::Denied(domain))
+        } else if any(&self.allow) {
+            Ok(())
+        } else {
+            Err(Rejected::NotAllowed(domain))
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use reqwest::{Client, StatusCode, Url};
+use serde::Deserialize;
+
+use super::{ApiCalls, Error, Forge, RepoSource, RepoStats, Result};
+
+pub const HOST: &str = "codeberg.org";
+pub const DEFAULT_BASE_URL: &str = "https://codeberg.org";
+
+/// Repo stats from the Gitea API (v1), which Forgejo and codeberg.org serve
+/// unchanged.
+pub struct Gitea {
+    client: Client,
+    base_url: Url,
+    token: Option<String>,
+    calls: ApiCalls,
+}
+
+#[derive(Debug, Deserialize)]
+struct Repository {
+    id: i64,
+    name: String,
+    owner: Owner,
+    #[serde(default)]
+    stars_count: i32,
+    #[serde(default)]
+    forks_count: i32,
+    #[serde(default)]
+    watchers_count: i32,
+    #[serde(default)]
+    open_issues_count: i32,
+    #[serde(default)]
+    open_pr_counter: i32,
+    #[serde(default)]
+    release_counter: i32,
+    /// SPDX ids, only reported by newer instances.
+    #[serde(default)]
+    licenses: Vec<String>,
+    #[serde(default)]
+    archived: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Owner {
+    login: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Commit {
+    commit: CommitDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommitDetail {
+    author: CommitAuthor,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommitAuthor {
+    email: String,
+}
+
+impl Gitea {
+    /// Build a client for the instance at `base_url`, e.g. `https://codeberg.org`.
+    ///
+    /// # Errors
+    ///
+    /// When `base_url` is not a valid URL or the client can not be built.
+    pub fn new(base_url: &str, token: Option<&str>) -> Result<Self> {
+        Ok(Self {
+            client: super::http_client()?,
+            base_url: super::base_url(base_url)?,
+            token: token.map(ToString::to_string),
+            calls: ApiCalls::default(),
+        })
+    }
+
+    /// `{base}/api/v1/repos/{owner}/{name}/{tail...}`
+    fn repo_url(&self, owner: &str, name: &str, tail: &[&str]) -> Url {
+        let mut url = self.base_url.clone();
+        {
+            // checked in `new`
+            let mut segments = url.path_segments_mut().expect("base url");
+            segments
+                .pop_if_empty()
+                .extend(["api", "v1", "repos", owner, name])
+                .extend(tail);
+        }
+        url
+    }
+
+    async fn get(&self, url: Url, query: &[(&str, &str)]) -> Result<reqwest::Response> {
+        self.calls.inc();
+        let mut req = self.client.get(url).query(query);
+        if let Some(token) = &self.token {
+            req = req.header("Authorization", format!("token {token}"));
+        }
+        Ok(req.send().await?)
+    }
+
+    /// Commits of the default branch, without the expensive extras.
+    async fn commits(
+        &self,
+        owner: &str,
+        name: &str,
+        query: &[(&str, &str)],
+    ) -> Result<reqwest::Response> {
+        let mut query = query.to_vec();
+        query.extend([
+            ("stat", "false"),
+            ("verification", "false"),
+            ("files", "false"),
+        ]);
+        Ok(self
+            .get(self.repo_url(owner, name, &["commits"]), &query)
+            .await?
+            .error_for_status()?)
+    }
+
+    /// Stats of the repo in the response `res` to a repo request, which is
+    /// `what` in errors.
+    async fn stats(&self, res: reqwest::Response, what: &str) -> Result<RepoStats> {
+        if matches!(res.status(), StatusCode::NOT_FOUND | StatusCode::GONE) {
+            return Err(Error::NotFound(what.to_string()));
+        }
+        let repo: Repository = res.error_for_status()?.json().await?;
+        let (owner, name) = (repo.owner.login.as_str(), repo.name.as_str());
+
+        // the total is in a header, a single item is enough
+        let since = (Utc::now() - chrono::Duration::days(30)).to_rfc3339();
+        let res = self
+            .commits(owner, name, &[("since", &since), ("limit", "1")])
+            .await?;
+        let total = res
+            .headers()
+            .get("x-total-count")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<i32>().ok());
+        let commits_last_30d = match total {
+            Some(total) => total,
+            None => i32::try_from(res.json::<Vec<Commit>>().await?.len())?,
+        };
+
+        // Gitea has no contributors endpoint, count the distinct authors of
+        // the most recent commits instead (a page is capped by the instance).
+        let commits: Vec<Commit> = self
+            .commits(owner, name, &[("limit", "100")])
+            .await?
+            .json()
+            .await?;
+        let contributors = commits
+            .iter()
+            .map(|c| c.commit.author.email.to_lowercase())
+            .collect::<HashSet<_>>()
+            .len();
+
+        Ok(RepoStats {
+            forge_id: Some(repo.id),
+            owner: repo.owner.login.clone(),
+            name: repo.name.clone(),
+            stars: repo.stars_count,
+            forks: repo.forks_count,
+            issues: repo.open_issues_count,
+            prs: repo.open_pr_counter,
+            contributors: i32::try_from(contributors)?,
+            commits_last_30d,
+            watchers: repo.watchers_count,
+            releases: repo.release_counter,
+            license: repo.licenses.into_iter().next(),
+            archived: repo.archived,
+        })
+    }
+}
+
+#[async_trait]
+impl RepoSource for Gitea {
+    fn forge(&self) -> Forge {
+        Forge::Gitea
+    }
+
+    fn host(&self) -> String {
+        self.base_url.host_str().unwrap_or(HOST).to_lowercase()
+    }
+
+    fn api_calls(&self) -> u32 {
+        self.calls.get()
+    }
+
+    async fn fetch(&self, owner: &str, name: &str) -> Result<RepoStats> {
+        // Gitea redirects the old name of a renamed or transferred repo
+        let res = self.get(self.repo_url(owner, name, &[]), &[]).await?;
+        self.stats(res, &format!("{owner}/{name}")).await
+    }
+
+    async fn fetch_by_id(&self, id: i64) -> Result<RepoStats> {
+        let mut url = self.base_url.clone();
+        // checked in `new`
+        url.path_segments_mut()
+            .expect("base url")
+            .pop_if_empty()
+            .extend(["api", "v1", "repositories", &id.to_string()]);
+        let res = self.get(url, &[]).await?;
+        self.stats(res, &format!("#{id}")).await
+    }
+}
@@ -0,0 +1,237 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use octocrab::{models::Repository, params::State, Octocrab, Page};
+use serde::{Deserialize, Serialize};
+
+use super::{ApiCalls, Error, Forge, ListedRepo, RepoSource, RepoStats, Result};
+
+pub const HOST: &str = "github.com";
+
+/// Repo stats from the GitHub REST API.
+pub struct GitHub {
+    client: Octocrab,
+    calls: ApiCalls,
+}
+
+impl GitHub {
+    /// Build a client against `base_url` (api.github.com when `None`).
+    ///
+    /// # Errors
+    ///
+    /// When the base URL is invalid or the client can not be built.
+    pub fn new(base_url: Option<&str>, token: Option<&str>) -> Result<Self> {
+        let mut builder = Octocrab::builder();
+        if let Some(base_url) = base_url {
+            builder = builder.base_uri(base_url)?;
+        }
+        if let Some(token) = token {
+            builder = builder.personal_token(token.to_string());
+        }
+        Ok(Self {
+            client: builder.build()?,
+            calls: ApiCalls::default(),
+        })
+    }
+
+    /// Build a client against api.github.com using `GITHUB_TOKEN` when set.
+    ///
+    /// # Errors
+    ///
+    /// When the client can not be built.
+    pub fn from_env() -> Result<Self> {
+        let token = std::env::var("GITHUB_TOKEN").ok();
+        if token.is_some() {
+            tracing::info!("Using GitHub API token.");
+        } else {
+            tracing::info!(
+                "No GITHUB_TOKEN found. Using unauthenticated GitHub API (rate limits apply)."
+            );
+        }
+        Self::new(None, token.as_deref())
+    }
+
+    /// All public repos owned by a user or organization.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::AccountNotFound`] when there is no such account, or on API errors.
+    pub async fn list_repos(&self, account: &str) -> Result<Vec<ListedRepo>> {
+        #[derive(Serialize)]
+        struct Query {
+            r#type: &'static str,
+            per_page: u8,
+        }
+        #[derive(Deserialize)]
+        struct Owner {
+            login: String,
+        }
+        #[derive(Deserialize)]
+        struct Listed {
+            #[serde(default)]
+            id: Option<i64>,
+            name: String,
+            owner: Owner,
+            #[serde(default)]
+            archived: bool,
+            #[serde(default)]
+            fork: bool,
+            #[serde(default)]
+            topics: Vec<String>,
+            #[serde(default)]
+            stargazers_count: i32,
+        }
+
+        self.calls.inc();
+        let mut page: Page<Listed> = self
+            .client
+            .get(
+                format!("/users/{account}/repos"),
+                Some(&Query {
+                    r#type: "owner",
+                    per_page: 100,
+                }),
+            )
+            .await
+            .map_err(|err| not_found(err, || Error::AccountNotFound(account.to_string())))?;
+
+        let mut listed = page.take_items();
+        while page.next.is_some() {
+            self.calls.inc();
+            let Some(next) = self.client.get_page(&page.next).await? else {
+                break;
+            };
+            page = next;
+            listed.append(&mut page.take_items());
+        }
+
+        Ok(listed
+            .into_iter()
+            .map(|repo| ListedRepo {
+                forge_id: repo.id,
+                owner: repo.owner.login,
+                name: repo.name,
+                archived: repo.archived,
+                fork: repo.fork,
+                topics: repo.topics,
+                stars: repo.stargazers_count,
+            })
+            .collect())
+    }
+
+    /// Stats of `gh_repo`, counting what the repo object does not carry.
+    async fn stats(&self, gh_repo: Repository) -> Result<RepoStats> {
+        let owner = gh_repo.owner.map(|o| o.login).unwrap_or_default();
+        let name = gh_repo.name;
+
+        self.calls.inc();
+        let prs = self
+            .client
+            .pulls(&owner, &name)
+            .list()
+            .state(State::Open)
+            .send()
+            .await?
+            .items
+            .len();
+
+        self.calls.inc();
+        let contributors = self
+            .client
+            .repos(&owner, &name)
+            .list_contributors()
+            .send()
+            .await?
+            .items
+            .len();
+
+        self.calls.inc();
+        let commits_last_30d = self
+            .client
+            .repos(&owner, &name)
+            .list_commits()
+            .since(Utc::now() - chrono::Duration::days(30))
+            .per_page(100)
+            .send()
+            .await?
+            .items
+            .len();
+
+        self.calls.inc();
+        let releases = self
+            .client
+            .repos(&owner, &name)
+            .releases()
+            .list()
+            .per_page(100)
+            .send()
+            .await?
+            .items
+            .len();
+
+        Ok(RepoStats {
+            forge_id: Some(i64::try_from(gh_repo.id.into_inner())?),
+            owner,
+            name,
+            stars: gh_repo.stargazers_count.unwrap_or(0).cast_signed(),
+            forks: gh_repo.forks_count.unwrap_or(0).cast_signed(),
+            issues: gh_repo.open_issues_count.unwrap_or(0).cast_signed(),
+            watchers: gh_repo.watchers_count.unwrap_or(0).cast_signed(),
+            prs: i32::try_from(prs)?,
+            contributors: i32::try_from(contributors)?,
+            commits_last_30d: i32::try_from(commits_last_30d)?,
+            releases: i32::try_from(releases)?,
+            license: gh_repo.license.map(|l| l.name),
+            archived: gh_repo.archived.unwrap_or(false),
+        })
+    }
+}
+
+/// Map a 404 from GitHub into the error built by `missing`.
+fn not_found(err: octocrab::Error, missing: impl FnOnce() -> Error) -> Error {
+    match &err {
+        octocrab::Error::GitHub { source, .. }
+            if source.status_code == reqwest::StatusCode::NOT_FOUND =>
+        {
+            missing()
+        }
+        _ => err.into(),
+    }
+}
+
+#[async_trait]
+impl RepoSource for GitHub {
+    fn forge(&self) -> Forge {
+        Forge::Github
+    }
+
+    fn host(&self) -> String {
+        HOST.to_string()
+    }
+
+    fn api_calls(&self) -> u32 {
+        self.calls.get()
+    }
+
+    async fn fetch(&self, owner: &str, name: &str) -> Result<RepoStats> {
+        // a renamed or transferred repo redirects to its new location
+        self.calls.inc();
+        let gh_repo = self
+            .client
+            .repos(owner, name)
+            .get()
+            .await
+            .map_err(|err| not_found(err, || Error::NotFound(format!("{owner}/{name}"))))?;
+        self.stats(gh_repo).await
+    }
+
+    async fn fetch_by_id(&self, id: i64) -> Result<RepoStats> {
+        self.calls.inc();
+        let gh_repo = self
+            .client
+            .repos_by_id(u64::try_from(id)?)
+            .get()
+            .await
+            .map_err(|err| not_found(err, || Error::NotFound(format!("#{id}"))))?;
+        self.stats(gh_repo).await
+    }
+}
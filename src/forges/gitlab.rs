@@ -0,0 +1,178 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use reqwest::{Client, StatusCode, Url};
+use serde::Deserialize;
+
+use super::{ApiCalls, Error, Forge, RepoSource, RepoStats, Result};
+
+pub const HOST: &str = "gitlab.com";
+pub const DEFAULT_BASE_URL: &str = "https://gitlab.com";
+
+/// Repo stats from the GitLab REST API (v4), gitlab.com or self-hosted.
+///
+/// On GitLab `owner` is the full namespace path, which may contain subgroups
+/// (`group/subgroup`), and `name` is the project path.
+pub struct GitLab {
+    client: Client,
+    base_url: Url,
+    token: Option<String>,
+    calls: ApiCalls,
+}
+
+#[derive(Debug, Deserialize)]
+struct Project {
+    id: i64,
+    path: String,
+    namespace: Namespace,
+    #[serde(default)]
+    star_count: i32,
+    #[serde(default)]
+    forks_count: i32,
+    /// Missing when the issue tracker is disabled.
+    #[serde(default)]
+    open_issues_count: i32,
+    #[serde(default)]
+    license: Option<License>,
+    #[serde(default)]
+    archived: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Namespace {
+    full_path: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct License {
+    name: String,
+}
+
+impl GitLab {
+    /// Build a client for the instance at `base_url`, e.g. `https://gitlab.com`.
+    ///
+    /// # Errors
+    ///
+    /// When `base_url` is not a valid URL or the client can not be built.
+    pub fn new(base_url: &str, token: Option<&str>) -> Result<Self> {
+        Ok(Self {
+            client: super::http_client()?,
+            base_url: super::base_url(base_url)?,
+            token: token.map(ToString::to_string),
+            calls: ApiCalls::default(),
+        })
+    }
+
+    /// `{base}/api/v4/projects/{project}/{tail...}`, where `project` is the
+    /// numeric id or the `owner/name` path (which gets encoded).
+    fn project_url(&self, project: &str, tail: &[&str]) -> Url {
+        let mut url = self.base_url.clone();
+        {
+            // checked in `new`
+            let mut segments = url.path_segments_mut().expect("base url");
+            segments
+                .pop_if_empty()
+                .extend(["api", "v4", "projects"])
+                .push(project)
+                .extend(tail);
+        }
+        url
+    }
+
+    async fn get(&self, url: Url, query: &[(&str, &str)]) -> Result<reqwest::Response> {
+        self.calls.inc();
+        let mut req = self.client.get(url).query(query);
+        if let Some(token) = &self.token {
+            req = req.header("PRIVATE-TOKEN", token);
+        }
+        Ok(req.send().await?)
+    }
+
+    /// Number of items in a collection. Uses the `X-Total` header when the
+    /// instance sends it, otherwise the size of the first page.
+    async fn count(&self, project: &str, tail: &[&str], query: &[(&str, &str)]) -> Result<i32> {
+        let mut query = query.to_vec();
+        query.push(("per_page", "100"));
+        let res = self
+            .get(self.project_url(project, tail), &query)
+            .await?
+            .error_for_status()?;
+
+        let total = res
+            .headers()
+            .get("x-total")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<i32>().ok());
+        if let Some(total) = total {
+            return Ok(total);
+        }
+
+        let items: Vec<serde_json::Value> = res.json().await?;
+        Ok(i32::try_from(items.len())?)
+    }
+    /// Stats of `project`, a numeric id or an `owner/name` path.
+    async fn fetch_project(&self, project: &str) -> Result<RepoStats> {
+        let res = self
+            .get(self.project_url(project, &[]), &[("license", "true")])
+            .await?;
+        if matches!(res.status(), StatusCode::NOT_FOUND | StatusCode::GONE) {
+            return Err(Error::NotFound(project.to_string()));
+        }
+        let project: Project = res.error_for_status()?.json().await?;
+        // follow-up requests by id, which are not affected by renames
+        let id = project.id.to_string();
+
+        let since = (Utc::now() - chrono::Duration::days(30)).to_rfc3339();
+
+        let prs = self
+            .count(&id, &["merge_requests"], &[("state", "opened")])
+            .await?;
+        let contributors = self
+            .count(&id, &["repository", "contributors"], &[])
+            .await?;
+        let commits_last_30d = self
+            .count(&id, &["repository", "commits"], &[("since", &since)])
+            .await?;
+        let releases = self.count(&id, &["releases"], &[]).await?;
+
+        Ok(RepoStats {
+            forge_id: Some(project.id),
+            owner: project.namespace.full_path,
+            name: project.path,
+            stars: project.star_count,
+            forks: project.forks_count,
+            issues: project.open_issues_count,
+            prs,
+            contributors,
+            commits_last_30d,
+            // GitLab has no separate watcher count, GitHub reports stars here too
+            watchers: project.star_count,
+            releases,
+            license: project.license.map(|l| l.name),
+            archived: project.archived,
+        })
+    }
+}
+
+#[async_trait]
+impl RepoSource for GitLab {
+    fn forge(&self) -> Forge {
+        Forge::Gitlab
+    }
+
+    fn host(&self) -> String {
+        self.base_url.host_str().unwrap_or(HOST).to_lowercase()
+    }
+
+    fn api_calls(&self) -> u32 {
+        self.calls.get()
+    }
+
+    async fn fetch(&self, owner: &str, name: &str) -> Result<RepoStats> {
+        // GitLab redirects the old path of a renamed or transferred project
+        self.fetch_project(&format!("{owner}/{name}")).await
+    }
+
+    async fn fetch_by_id(&self, id: i64) -> Result<RepoStats> {
+        self.fetch_project(&id.to_string()).await
+    }
+}
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Error, Result};
+
+/// A repo as listed for an account, with just enough to decide whether to
+/// import it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListedRepo {
+    /// The forge's own id of the repo, which outlives renames.
+    pub forge_id: Option<i64>,
+    pub owner: String,
+    pub name: String,
+    pub archived: bool,
+    pub fork: bool,
+    pub topics: Vec<String>,
+    pub stars: i32,
+}
+
+/// Which repos of an account to import. By default every repo that is
+/// neither archived nor a fork.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportFilter {
+    #[serde(default)]
+    pub include_archived: bool,
+    #[serde(default)]
+    pub include_forks: bool,
+    /// Only repos tagged with this topic.
+    #[serde(default)]
+    pub topic: Option<String>,
+    #[serde(default)]
+    pub min_stars: i32,
+}
+
+impl ImportFilter {
+    #[must_use]
+    pub fn matches(&self, repo: &ListedRepo) -> bool {
+        (self.include_archived || !repo.archived)
+            && (self.include_forks || !repo.fork)
+            && repo.stars >= self.min_stars
+            && self
+                .topic
+                .as_deref()
+                .is_none_or(|topic| repo.topics.iter().any(|t| t.eq_ignore_ascii_case(topic)))
+    }
+}
+
+/// Parse a GitHub account as typed in: `rust-lang`, `@rust-lang` or
+/// `https://github.com/rust-lang`.
+///
+/// # Errors
+///
+/// [`Error::InvalidAccount`] when the input is not an account name.
+pub fn parse_account(input: &str) -> Result<String> {
+    let account = input.trim().trim_end_matches('/');
+    let account = account
+        .split_once("github.com/")
+        .map_or(account, |(_, account)| account);
+    let account = account.strip_prefix('@').unwrap_or(account);
+    let valid = !account.is_empty()
+        && !account.starts_with('-')
+        && account
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-');
+    if valid {
+        Ok(account.to_string())
+    } else {
+        Err(Error::InvalidAccount(input.to_string()))
+    }
+}
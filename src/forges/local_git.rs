@@ -0,0 +1,129 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use async_trait::async_trait;
+use tokio::process::Command;
+
+use super::{Error, Forge, RepoSource, RepoStats, Result};
+
+pub const HOST: &str = "localhost";
+
+/// Repo stats computed from a git clone (or bare mirror) on disk, without
+/// any forge API. Needs the `git` binary.
+///
+/// Only history based stats are available: commits in the last 30 days,
+/// distinct authors as contributors and tags as releases. Everything a forge
+/// would count (stars, forks, issues, PRs, watchers) is zero.
+pub struct LocalGit {
+    path: PathBuf,
+}
+
+impl LocalGit {
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Owner and name from the path, `.../owner/name(.git)`.
+    #[must_use]
+    pub fn owner_and_name(&self) -> (String, String) {
+        let path = self
+            .path
+            .canonicalize()
+            .unwrap_or_else(|_| self.path.clone());
+        let segment = |p: Option<&Path>| {
+            p.and_then(Path::file_name)
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default()
+        };
+        let name = segment(Some(&path));
+        let name = name.strip_suffix(".git").unwrap_or(&name).to_string();
+        (segment(path.parent()), name)
+    }
+
+    async fn git(&self, args: &[&str]) -> Result<String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.path)
+            .args(args)
+            .output()
+            .await?;
+        if !output.status.success() {
+            return Err(Error::Git(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+#[async_trait]
+impl RepoSource for LocalGit {
+    fn forge(&self) -> Forge {
+        Forge::Local
+    }
+
+    fn host(&self) -> String {
+        HOST.to_string()
+    }
+
+    fn local_path(&self) -> Option<String> {
+        let path = self
+            .path
+            .canonicalize()
+            .unwrap_or_else(|_| self.path.clone());
+        Some(path.to_string_lossy().into_owned())
+    }
+
+    async fn fetch(&self, owner: &str, name: &str) -> Result<RepoStats> {
+        if self.git(&["rev-parse", "--git-dir"]).await.is_err() {
+            return Err(Error::NotFound(self.path.display().to_string()));
+        }
+
+        // an empty repo has no HEAD yet and therefore no history
+        let has_history = self
+            .git(&["rev-parse", "--verify", "--quiet", "HEAD"])
+            .await
+            .is_ok();
+
+        let (commits_last_30d, contributors) = if has_history {
+            let commits = self
+                .git(&["rev-list", "--count", "--since=30.days.ago", "HEAD"])
+                .await?;
+            let authors = self.git(&["log", "--format=%aE", "HEAD"]).await?;
+            let authors = authors
+                .lines()
+                .map(|email| email.trim().to_lowercase())
+                .filter(|email| !email.is_empty())
+                .collect::<HashSet<_>>()
+                .len();
+            (
+                commits.trim().parse::<i32>().unwrap_or_default(),
+                i32::try_from(authors)?,
+            )
+        } else {
+            (0, 0)
+        };
+
+        let tags = self.git(&["tag", "--list"]).await?;
+        let releases = i32::try_from(tags.lines().filter(|t| !t.is_empty()).count())?;
+
+        Ok(RepoStats {
+            forge_id: None,
+            owner: owner.to_string(),
+            name: name.to_string(),
+            stars: 0,
+            forks: 0,
+            issues: 0,
+            prs: 0,
+            contributors,
+            commits_last_30d,
+            watchers: 0,
+            releases,
+            license: None,
+            archived: false,
+        })
+    }
+}
@@ -0,0 +1,321 @@
+//! Code forges that repository stats can be ingested from.
+//!
+//! Every forge implements [`RepoSource`] and produces the same [`RepoStats`],
+//! so the rest of the app does not care where a repo is hosted.
+use std::{
+    fmt,
+    str::FromStr,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use async_trait::async_trait;
+use sea_orm::DbErr;
+use serde::{Deserialize, Serialize};
+
+pub mod gitea;
+pub mod github;
+pub mod gitlab;
+pub mod listing;
+pub mod local_git;
+pub mod repo_ref;
+
+pub use gitea::Gitea;
+pub use github::GitHub;
+pub use gitlab::GitLab;
+pub use listing::{ImportFilter, ListedRepo};
+pub use local_git::LocalGit;
+pub use repo_ref::RepoRef;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("repository {0} not found")]
+    NotFound(String),
+    #[error("account {0} not found")]
+    AccountNotFound(String),
+    #[error("unknown forge `{0}`")]
+    UnknownForge(String),
+    #[error("no forge configured for host `{0}`")]
+    UnknownHost(String),
+    #[error("`{0}` is not a repository url or `owner/name`")]
+    InvalidRepoRef(String),
+    #[error("`{0}` is not a GitHub user or organization")]
+    InvalidAccount(String),
+    #[error("{0} repos have no stable id")]
+    NoStableIds(Forge),
+    #[error("local repos can only be fetched by path")]
+    LocalNeedsPath,
+    #[error("{repo} is tracked in project {project_id}, which you can not change")]
+    TrackedElsewhere { repo: String, project_id: i32 },
+    #[error("git: {0}")]
+    Git(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("invalid forge url `{0}`")]
+    InvalidUrl(String),
+    #[error(transparent)]
+    GitHub(#[from] octocrab::Error),
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error(transparent)]
+    Count(#[from] std::num::TryFromIntError),
+    #[error(transparent)]
+    Db(#[from] DbErr),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The kind of forge a repo lives on. Stored as text in `repos.forge`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Forge {
+    Github,
+    Gitlab,
+    /// Gitea and its fork Forgejo (which runs codeberg.org) share one API.
+    Gitea,
+    /// A git repository on disk, see [`LocalGit`].
+    Local,
+}
+
+impl Forge {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Github => "github",
+            Self::Gitlab => "gitlab",
+            Self::Gitea => "gitea",
+            Self::Local => "local",
+        }
+    }
+}
+
+impl fmt::Display for Forge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Forge {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "github" => Ok(Self::Github),
+            "gitlab" => Ok(Self::Gitlab),
+            "gitea" | "forgejo" => Ok(Self::Gitea),
+            "local" => Ok(Self::Local),
+            other => Err(Error::UnknownForge(other.to_string())),
+        }
+    }
+}
+
+/// Stats of a single repository, as reported by its forge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoStats {
+    /// The forge's own id of the repo, which survives renames and transfers.
+    pub forge_id: Option<i64>,
+    pub owner: String,
+    pub name: String,
+    pub stars: i32,
+    pub forks: i32,
+    pub issues: i32,
+    pub prs: i32,
+    pub contributors: i32,
+    pub commits_last_30d: i32,
+    pub watchers: i32,
+    pub releases: i32,
+    pub license: Option<String>,
+    pub archived: bool,
+}
+
+#[async_trait]
+pub trait RepoSource: Send + Sync {
+    /// The forge written to `repos.forge` for repos fetched from this source.
+    fn forge(&self) -> Forge;
+
+    /// The web host of the instance, written to `repos.host`.
+    fn host(&self) -> String;
+
+    /// Where the repo is on disk, written to `repos.local_path` so that it
+    /// can be fetched again. `None` for forges.
+    fn local_path(&self) -> Option<String> {
+        None
+    }
+
+    /// Fetch the current stats of `owner/name`.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::NotFound`] when the forge does not know the repo, any
+    /// transport error otherwise.
+    async fn fetch(&self, owner: &str, name: &str) -> Result<RepoStats>;
+
+    /// Fetch the current stats of the repo with the forge's stable `id`,
+    /// which finds it under its current owner and name after a rename or
+    /// transfer.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::NotFound`] when the repo is gone, [`Error::NoStableIds`] for
+    /// sources without ids, any transport error otherwise.
+    async fn fetch_by_id(&self, id: i64) -> Result<RepoStats> {
+        let _ = id;
+        Err(Error::NoStableIds(self.forge()))
+    }
+
+    /// API requests this source has made so far, to log what a fetch cost.
+    fn api_calls(&self) -> u32 {
+        0
+    }
+}
+
+/// Counts the API requests a source makes.
+#[derive(Debug, Default)]
+pub struct ApiCalls(AtomicU32);
+
+impl ApiCalls {
+    pub fn inc(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub fn get(&self) -> u32 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A forge instance as configured under `settings.forges`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct InstanceSettings {
+    pub base_url: String,
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+impl InstanceSettings {
+    /// The configured token, treating an empty string as no token so that
+    /// `get_env(..., default="")` in the config files does the right thing.
+    #[must_use]
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref().filter(|t| !t.is_empty())
+    }
+
+    /// Host part of `base_url`, used to match repo URLs to an instance.
+    #[must_use]
+    pub fn host(&self) -> Option<String> {
+        reqwest::Url::parse(&self.base_url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_lowercase))
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Settings {
+    /// GitLab instances, gitlab.com and any self-hosted ones.
+    #[serde(default)]
+    pub gitlab: Vec<InstanceSettings>,
+    /// Gitea and Forgejo instances, codeberg.org and any self-hosted ones.
+    #[serde(default)]
+    pub gitea: Vec<InstanceSettings>,
+}
+
+impl Settings {
+    /// Build the client for `forge`, on `host` for self-hosted instances.
+    ///
+    /// # Errors
+    ///
+    /// When the client can not be built.
+    pub fn source(&self, forge: Forge, host: Option<&str>) -> Result<Box<dyn RepoSource>> {
+        Ok(match forge {
+            Forge::Github => Box::new(GitHub::from_env()?),
+            Forge::Gitlab => Box::new(self.gitlab(host)?),
+            Forge::Gitea => Box::new(self.gitea(host)?),
+            Forge::Local => return Err(Error::LocalNeedsPath),
+        })
+    }
+
+    /// Which forge serves repos on `host`. The public instances are always
+    /// known, self-hosted ones have to be configured.
+    #[must_use]
+    pub fn forge_for_host(&self, host: &str) -> Option<Forge> {
+        let host = host.to_lowercase();
+        let host = host.strip_prefix("www.").unwrap_or(&host);
+        let configured = |instances: &[InstanceSettings]| {
+            instances.iter().any(|i| i.host().as_deref() == Some(host))
+        };
+        if host == github::HOST {
+            Some(Forge::Github)
+        } else if host == gitlab::HOST || configured(&self.gitlab) {
+            Some(Forge::Gitlab)
+        } else if host == gitea::HOST || configured(&self.gitea) {
+            Some(Forge::Gitea)
+        } else {
+            None
+        }
+    }
+
+    /// Build a GitLab client for `host`, or for the first configured instance
+    /// when no host is given. Falls back to gitlab.com without a token.
+    ///
+    /// # Errors
+    ///
+    /// When the HTTP client can not be built.
+    pub fn gitlab(&self, host: Option<&str>) -> Result<GitLab> {
+        if let Some(instance) = find_instance(&self.gitlab, host) {
+            return GitLab::new(&instance.base_url, instance.token());
+        }
+        let base_url = host.map_or_else(
+            || gitlab::DEFAULT_BASE_URL.to_string(),
+            |host| format!("https://{host}"),
+        );
+        GitLab::new(&base_url, None)
+    }
+
+    /// Build a Gitea/Forgejo client for `host`, or for the first configured
+    /// instance when no host is given. Falls back to codeberg.org without a
+    /// token.
+    ///
+    /// # Errors
+    ///
+    /// When the HTTP client can not be built.
+    pub fn gitea(&self, host: Option<&str>) -> Result<Gitea> {
+        if let Some(instance) = find_instance(&self.gitea, host) {
+            return Gitea::new(&instance.base_url, instance.token());
+        }
+        let base_url = host.map_or_else(
+            || gitea::DEFAULT_BASE_URL.to_string(),
+            |host| format!("https://{host}"),
+        );
+        Gitea::new(&base_url, None)
+    }
+}
+
+/// HTTP client shared by the forges that are not covered by octocrab.
+pub(crate) fn http_client() -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .user_agent(concat!("gooncityhub/", env!("CARGO_PKG_VERSION")))
+        .build()
+}
+
+/// Parse and check a forge base URL, it must be able to take API paths.
+pub(crate) fn base_url(base_url: &str) -> Result<reqwest::Url> {
+    reqwest::Url::parse(base_url)
+        .ok()
+        .filter(|url| !url.cannot_be_a_base() && url.host_str().is_some())
+        .ok_or_else(|| Error::InvalidUrl(base_url.to_string()))
+}
+
+/// The instance configured for `host`, or the first one when no host is given.
+fn find_instance<'a>(
+    instances: &'a [InstanceSettings],
+    host: Option<&str>,
+) -> Option<&'a InstanceSettings> {
+    host.map_or_else(
+        || instances.first(),
+        |host| {
+            let host = host.to_lowercase();
+            instances
+                .iter()
+                .find(|i| i.host().as_deref() == Some(host.as_str()))
+        },
+    )
+}
@@ -0,0 +1,95 @@
+use super::{Error, Forge, Result, Settings};
+
+/// A repository on some forge, as typed in by a user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoRef {
+    pub forge: Forge,
+    pub host: String,
+    pub owner: String,
+    pub name: String,
+}
+
+impl RepoRef {
+    /// Parse a repo URL (`https://codeberg.org/owner/name`), a URL without
+    /// scheme (`gitlab.com/group/sub/name`), an scp-like git remote
+    /// (`git@github.com:owner/name.git`) or a plain `owner/name`, which is
+    /// taken to be on GitHub.
+    ///
+    /// Trailing `.git` and extra path parts such as `/tree/main` or
+    /// `/-/merge_requests` are dropped.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::InvalidRepoRef`] when the input is not a repo reference,
+    /// [`Error::UnknownHost`] when no forge is known for its host.
+    pub fn parse(input: &str, settings: &Settings) -> Result<Self> {
+        let invalid = || Error::InvalidRepoRef(input.to_string());
+        let input = input.trim();
+
+        let (host, path) = if let Some((_, rest)) = input.split_once("://") {
+            rest.split_once('/').ok_or_else(invalid)?
+        } else if let Some((host, path)) = input
+            .strip_prefix("git@")
+            .and_then(|rest| rest.split_once(':'))
+        {
+            (host, path)
+        } else {
+            match input.split_once('/') {
+                Some((host, path)) if looks_like_host(host) => (host, path),
+                _ => (super::github::HOST, input),
+            }
+        };
+        // drop credentials and port
+        let host = host.rsplit('@').next().unwrap_or(host);
+        let host = host.split(':').next().unwrap_or(host).to_lowercase();
+        let host = host.strip_prefix("www.").unwrap_or(&host).to_string();
+
+        let forge = settings
+            .forge_for_host(&host)
+            .ok_or_else(|| Error::UnknownHost(host.clone()))?;
+
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        let mut segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        match forge {
+            // GitLab groups nest, the project path ends where `/-/` starts
+            Forge::Gitlab => {
+                if let Some(end) = segments.iter().position(|s| *s == "-") {
+                    segments.truncate(end);
+                }
+            }
+            Forge::Github | Forge::Gitea | Forge::Local => segments.truncate(2),
+        }
+
+        let (name, owner) = segments.split_last().ok_or_else(invalid)?;
+        let name = name.strip_suffix(".git").unwrap_or(name);
+        if owner.is_empty() || !owner.iter().chain([&name]).all(|s| is_valid_segment(s)) {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            forge,
+            host,
+            owner: owner.join("/"),
+            name: name.to_string(),
+        })
+    }
+
+    /// Web URL of the repo.
+    #[must_use]
+    pub fn url(&self) -> String {
+        format!("https://{}/{}/{}", self.host, self.owner, self.name)
+    }
+}
+
+fn looks_like_host(s: &str) -> bool {
+    s.contains('.') && !s.starts_with('.') && !s.ends_with('.')
+}
+
+fn is_valid_segment(segment: &str) -> bool {
+    !segment.is_empty()
+        && segment != "."
+        && segment != ".."
+        && segment
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
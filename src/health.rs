@@ -0,0 +1,418 @@
+//! How repo health is scored.
+//!
+//! A repo gets a score from 0 to 100 out of four factors, each its stats
+//! measured against a cap and clamped to 0.0–1.0, then weighted. The
+//! defaults can be changed by an admin, stored in `health_configs`.
+//!
+//! With [`Normalization::Percentile`] a stat is instead measured against
+//! the other tracked repos, so popular repos do not all tie at the caps.
+//! Where they stand is a [`Distribution`], stored in `health_distributions`
+//! and rebuilt whenever every project is recalculated.
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::_entities::repos;
+
+/// How much each factor counts. Weights are relative, they need not add up
+/// to 1.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Weights {
+    /// Commits in the last 30 days.
+    pub activity: f64,
+    /// Contributors.
+    pub community: f64,
+    /// Stars.
+    pub adoption: f64,
+    /// Pull requests, less so with many open issues.
+    pub maintenance: f64,
+}
+
+/// The value of a stat at which its factor is full.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Caps {
+    pub commits_last_30d: f64,
+    pub contributors: f64,
+    pub stars: f64,
+    pub prs: f64,
+    /// Open issues at which maintenance drops to nothing.
+    pub issues: f64,
+}
+
+/// How a stat becomes a factor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Normalization {
+    /// The stat against its cap.
+    #[default]
+    Caps,
+    /// The share of tracked repos in the same [`Bucket`] with a lower
+    /// stat. Falls back to the caps until a [`Distribution`] is built.
+    Percentile,
+}
+
+/// Which repos a repo is measured against with
+/// [`Normalization::Percentile`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Bucket {
+    /// Every tracked repo.
+    #[default]
+    All,
+    /// Repos with about as many contributors, see [`Bucket::of`].
+    Size,
+}
+
+impl Bucket {
+    /// The bucket `repo` falls in, the key of its [`Quantiles`].
+    #[must_use]
+    pub fn of(self, repo: &repos::Model) -> &'static str {
+        match self {
+            Self::All => "all",
+            Self::Size => match repo.contributors {
+                ..5 => "size:small",
+                5..50 => "size:medium",
+                _ => "size:large",
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub weights: Weights,
+    pub caps: Caps,
+    #[serde(default)]
+    pub normalization: Normalization,
+    #[serde(default)]
+    pub bucket: Bucket,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            weights: Weights {
+                activity: 0.35,
+                community: 0.25,
+                adoption: 0.15,
+                maintenance: 0.25,
+            },
+            caps: Caps {
+                commits_last_30d: 30.0,
+                contributors: 10.0,
+                stars: 100.0,
+                prs: 10.0,
+                issues: 50.0,
+            },
+            normalization: Normalization::default(),
+            bucket: Bucket::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Check that the config can score repos.
+    ///
+    /// # Errors
+    ///
+    /// What is wrong with it.
+    pub fn validate(&self) -> Result<(), String> {
+        let Weights {
+            activity,
+            community,
+            adoption,
+            maintenance,
+        } = self.weights;
+        let weights = [activity, community, adoption, maintenance];
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+            return Err("weights must be zero or more".to_string());
+        }
+        if weights.iter().sum::<f64>() <= 0.0 {
+            return Err("at least one weight must be more than zero".to_string());
+        }
+        let Caps {
+            commits_last_30d,
+            contributors,
+            stars,
+            prs,
+            issues,
+        } = self.caps;
+        if [commits_last_30d, contributors, stars, prs, issues]
+            .iter()
+            .any(|c| !c.is_finite() || *c <= 0.0)
+        {
+            return Err("caps must be more than zero".to_string());
+        }
+        Ok(())
+    }
+
+    /// The health of `repo`, from 0 to 100, against the caps.
+    #[must_use]
+    pub fn score(&self, repo: &repos::Model) -> f32 {
+        self.score_within(repo, None)
+    }
+
+    /// The health of `repo`, from 0 to 100, against the other tracked repos
+    /// in `distribution` when normalizing by percentile.
+    #[allow(clippy::cast_possible_truncation)]
+    #[must_use]
+    pub fn score_within(&self, repo: &repos::Model, distribution: Option<&Distribution>) -> f32 {
+        let quantiles = match self.normalization {
+            Normalization::Caps => None,
+            Normalization::Percentile => {
+                distribution.and_then(|d| d.buckets.get(self.bucket.of(repo)))
+            }
+        };
+        let (activity, community, adoption, maintenance) = if let Some(q) = quantiles {
+            (
+                q.commits_last_30d.percentile(repo.commits_last_30d),
+                q.contributors.percentile(repo.contributors),
+                q.stars.percentile(repo.stars),
+                q.prs.percentile(repo.prs) * (1.0 - q.issues.percentile(repo.issues)),
+            )
+        } else {
+            let factor = |value: i32, cap: f64| (f64::from(value) / cap).clamp(0.0, 1.0);
+            (
+                factor(repo.commits_last_30d, self.caps.commits_last_30d),
+                factor(repo.contributors, self.caps.contributors),
+                factor(repo.stars, self.caps.stars),
+                // many pull requests make up for some open issues
+                (f64::from(repo.prs.max(0)) / self.caps.prs
+                    * (1.0 - factor(repo.issues, self.caps.issues)))
+                .min(1.0),
+            )
+        };
+
+        let w = &self.weights;
+        let total = w.activity + w.community + w.adoption + w.maintenance;
+        if total <= 0.0 {
+            return 0.0;
+        }
+        let score = (activity * w.activity
+            + community * w.community
+            + adoption * w.adoption
+            + maintenance * w.maintenance)
+            / total;
+        (score * 100.0).clamp(0.0, 100.0) as f32
+    }
+}
+
+/// How a project's health is made from the health of its repos, chosen per
+/// project and stored in `projects.health_strategy`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Strategy {
+    /// Every repo counts the same.
+    #[default]
+    Average,
+    /// Repos count by their stars.
+    Stars,
+    /// Repos count by their commits in the last 30 days.
+    Activity,
+    /// Only the project's primary repo counts.
+    Primary,
+    /// The healthiest repo.
+    Max,
+    /// The middle repo, so a few outliers do not count.
+    Median,
+}
+
+impl Strategy {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Average => "average",
+            Self::Stars => "stars",
+            Self::Activity => "activity",
+            Self::Primary => "primary",
+            Self::Max => "max",
+            Self::Median => "median",
+        }
+    }
+
+    /// The health of a project with `repos`, from 0 to 100, and the
+    /// strategy that made it. Each repo is scored with
+    /// [`Config::score_within`] against `distribution`.
+    ///
+    /// The strategy falls back to `Average` when it can not be applied: the
+    /// primary repo is not one of `repos`, or none of them has stars or
+    /// recent commits to weigh by.
+    ///
+    /// A project without repos is healthy.
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn aggregate(
+        self,
+        config: &Config,
+        distribution: Option<&Distribution>,
+        repos: &[repos::Model],
+        primary_repo_id: Option<i32>,
+    ) -> (f32, Self) {
+        if repos.is_empty() {
+            return (100.0, self);
+        }
+        let scores: Vec<f32> = repos
+            .iter()
+            .map(|repo| config.score_within(repo, distribution))
+            .collect();
+        let weighted = |weight: fn(&repos::Model) -> i32| {
+            let weights: Vec<f32> = repos
+                .iter()
+                .map(|repo| weight(repo).max(0) as f32)
+                .collect();
+            let total: f32 = weights.iter().sum();
+            (total > 0.0).then(|| {
+                scores
+                    .iter()
+                    .zip(&weights)
+                    .map(|(score, weight)| score * weight)
+                    .sum::<f32>()
+                    / total
+            })
+        };
+
+        let health = match self {
+            Self::Average => None,
+            Self::Stars => weighted(|repo| repo.stars),
+            Self::Activity => weighted(|repo| repo.commits_last_30d),
+            Self::Primary => repos
+                .iter()
+                .position(|repo| Some(repo.id) == primary_repo_id)
+                .map(|i| scores[i]),
+            Self::Max => scores.iter().copied().reduce(f32::max),
+            Self::Median => {
+                let mut sorted = scores.clone();
+                sorted.sort_by(f32::total_cmp);
+                let mid = sorted.len() / 2;
+                Some(if sorted.len().is_multiple_of(2) {
+                    (sorted[mid - 1] + sorted[mid]) / 2.0
+                } else {
+                    sorted[mid]
+                })
+            }
+        };
+        health.map_or_else(
+            || {
+                (
+                    scores.iter().sum::<f32>() / scores.len() as f32,
+                    Self::Average,
+                )
+            },
+            |health| (health, self),
+        )
+    }
+}
+
+impl std::str::FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "average" => Ok(Self::Average),
+            "stars" => Ok(Self::Stars),
+            "activity" => Ok(Self::Activity),
+            "primary" => Ok(Self::Primary),
+            "max" => Ok(Self::Max),
+            "median" => Ok(Self::Median),
+            other => Err(format!(
+                "unknown health strategy `{other}`, use average, stars, activity, primary, max or median"
+            )),
+        }
+    }
+}
+
+/// How many values of a stat a [`Quantiles`] keeps, however many repos are
+/// tracked.
+const SAMPLES: usize = 101;
+
+/// Where tracked repos stand on each stat, per bucket of every [`Bucket`]
+/// kind.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct Distribution {
+    pub buckets: BTreeMap<String, Quantiles>,
+}
+
+impl Distribution {
+    /// The distribution of `repos`, every tracked one.
+    #[must_use]
+    pub fn of(repos: &[repos::Model]) -> Self {
+        let mut grouped: BTreeMap<&str, Vec<&repos::Model>> = BTreeMap::new();
+        for repo in repos {
+            for bucket in [Bucket::All, Bucket::Size] {
+                grouped.entry(bucket.of(repo)).or_default().push(repo);
+            }
+        }
+        Self {
+            buckets: grouped
+                .into_iter()
+                .map(|(bucket, repos)| (bucket.to_string(), Quantiles::of(&repos)))
+                .collect(),
+        }
+    }
+}
+
+/// A sample of each stat over the repos of a bucket.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct Quantiles {
+    pub commits_last_30d: Sample,
+    pub contributors: Sample,
+    pub stars: Sample,
+    pub prs: Sample,
+    pub issues: Sample,
+}
+
+impl Quantiles {
+    fn of(repos: &[&repos::Model]) -> Self {
+        let sample = |stat: fn(&repos::Model) -> i32| {
+            Sample::of(repos.iter().map(|repo| stat(repo)).collect())
+        };
+        Self {
+            commits_last_30d: sample(|repo| repo.commits_last_30d),
+            contributors: sample(|repo| repo.contributors),
+            stars: sample(|repo| repo.stars),
+            prs: sample(|repo| repo.prs),
+            issues: sample(|repo| repo.issues),
+        }
+    }
+}
+
+/// Evenly spaced values of a stat, from the lowest to the highest, at most
+/// [`SAMPLES`] of them.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Sample(Vec<i32>);
+
+impl Sample {
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    fn of(mut values: Vec<i32>) -> Self {
+        values.sort_unstable();
+        if values.len() <= SAMPLES {
+            return Self(values);
+        }
+        let last = (values.len() - 1) as f64;
+        Self(
+            (0..SAMPLES)
+                .map(|i| values[(i as f64 * last / (SAMPLES - 1) as f64).round() as usize])
+                .collect(),
+        )
+    }
+
+    /// The share of the sample below `value`, counting ties as half, from
+    /// 0.0 to 1.0. Nothing, like no stars at all, is always 0.0.
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn percentile(&self, value: i32) -> f64 {
+        if value <= 0 || self.0.is_empty() {
+            return 0.0;
+        }
+        let below = self.0.partition_point(|v| *v < value);
+        let ties = self.0.partition_point(|v| *v <= value) - below;
+        (below as f64 + ties as f64 / 2.0) / self.0.len() as f64
+    }
+}
@@ -1,9 +1,17 @@
 pub mod app;
 pub mod controllers;
 pub mod data;
+pub mod email_domains;
+pub mod forges;
+pub mod health;
 pub mod initializers;
 pub mod mailers;
 pub mod models;
+pub mod oauth;
+pub mod settings;
+pub mod signed;
 pub mod tasks;
+pub mod totp;
 pub mod views;
+pub mod webhooks;
 pub mod workers;
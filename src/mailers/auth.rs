@@ -4,11 +4,13 @@
 use loco_rs::prelude::*;
 use serde_json::json;
 
-use crate::models::users;
+use crate::models::{data_exports, email_changes, users};
 
 static welcome: Dir<'_> = include_dir!("src/mailers/auth/welcome");
 static forgot: Dir<'_> = include_dir!("src/mailers/auth/forgot");
 static magic_link: Dir<'_> = include_dir!("src/mailers/auth/magic_link");
+static email_change: Dir<'_> = include_dir!("src/mailers/auth/email_change");
+static data_export: Dir<'_> = include_dir!("src/mailers/auth/data_export");
 
 #[allow(clippy::module_name_repetitions)]
 pub struct AuthMailer {}
@@ -87,4 +89,61 @@ impl AuthMailer {
 
         Ok(())
     }
+
+    /// Sends the link that confirms `change` to the new address.
+    ///
+    /// # Errors
+    ///
+    /// When email sending is failed
+    pub async fn send_email_change(
+        ctx: &AppContext,
+        user: &users::Model,
+        change: &email_changes::Model,
+    ) -> Result<()> {
+        Self::mail_template(
+            ctx,
+            &email_change,
+            mailer::Args {
+                to: change.new_email.clone(),
+                locals: json!({
+                  "name": user.name,
+                  "token": change.token,
+                  "domain": ctx.config.server.full_url()
+                }),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Sends the link to a finished data export.
+    ///
+    /// # Errors
+    ///
+    /// When email sending is failed
+    pub async fn send_data_export(
+        ctx: &AppContext,
+        user: &users::Model,
+        export: &data_exports::Model,
+    ) -> Result<()> {
+        Self::mail_template(
+            ctx,
+            &data_export,
+            mailer::Args {
+                to: user.email.clone(),
+                locals: json!({
+                  "name": user.name,
+                  "token": export.token,
+                  "days": data_exports::EXPIRATION_DAYS,
+                  "domain": ctx.config.server.full_url()
+                }),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        Ok(())
+    }
 }
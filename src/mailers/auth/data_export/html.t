@@ -0,0 +1,12 @@
+;<html>
+
+<body>
+  Hey {{name}},
+  The export of your data you asked for is ready. Download it with the link below within {{days}} days:
+  <a href="{{domain}}/api/account/export/{{token}}">
+    Download Your Data
+  </a>
+  <p>Best regards,<br>The Loco Team</p>
+</body>
+
+</html>
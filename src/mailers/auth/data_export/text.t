@@ -0,0 +1,4 @@
+Hey {{name}},
+  The export of your data you asked for is ready. Download it with the link below within {{days}} days:
+
+  {{domain}}/api/account/export/{{token}}
@@ -0,0 +1,13 @@
+;<html>
+
+<body>
+  Hey {{name}},
+  Confirm that you want to sign in with this address from now on by clicking the link below:
+  <a href="{{domain}}/api/account/email/{{token}}">
+    Confirm Your New Address
+  </a>
+  If you didn't ask for this, please ignore this email.
+  <p>Best regards,<br>The Loco Team</p>
+</body>
+
+</html>
@@ -0,0 +1,6 @@
+Hey {{name}},
+  Confirm that you want to sign in with this address from now on by following the link below:
+
+  {{domain}}/api/account/email/{{token}}
+
+  If you didn't ask for this, please ignore this email.
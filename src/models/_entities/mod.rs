@@ -2,6 +2,22 @@
 
 pub mod prelude;
 
+pub mod activity_events;
+pub mod api_keys;
+pub mod audit_events;
+pub mod auth_throttles;
+pub mod data_exports;
+pub mod email_changes;
+pub mod email_domain_rules;
+pub mod health_configs;
+pub mod health_distributions;
+pub mod project_members;
 pub mod projects;
+pub mod recovery_codes;
 pub mod repos;
+pub mod sessions;
+pub mod sync_runs;
+pub mod totp_credentials;
+pub mod user_identities;
 pub mod users;
+pub mod webhook_deliveries;
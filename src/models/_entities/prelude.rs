@@ -1,5 +1,21 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.19
 
+pub use super::activity_events::Entity as ActivityEvents;
+pub use super::api_keys::Entity as ApiKeys;
+pub use super::audit_events::Entity as AuditEvents;
+pub use super::auth_throttles::Entity as AuthThrottles;
+pub use super::data_exports::Entity as DataExports;
+pub use super::email_changes::Entity as EmailChanges;
+pub use super::email_domain_rules::Entity as EmailDomainRules;
+pub use super::health_configs::Entity as HealthConfigs;
+pub use super::health_distributions::Entity as HealthDistributions;
+pub use super::project_members::Entity as ProjectMembers;
 pub use super::projects::Entity as Projects;
+pub use super::recovery_codes::Entity as RecoveryCodes;
 pub use super::repos::Entity as Repos;
+pub use super::sessions::Entity as Sessions;
+pub use super::sync_runs::Entity as SyncRuns;
+pub use super::totp_credentials::Entity as TotpCredentials;
+pub use super::user_identities::Entity as UserIdentities;
 pub use super::users::Entity as Users;
+pub use super::webhook_deliveries::Entity as WebhookDeliveries;
@@ -17,14 +17,25 @@ pub struct Model {
     #[sea_orm(column_type = "Float")]
     pub health: f32,
     pub last_fetch: DateTime,
+    pub health_strategy: String,
+    pub health_strategy_used: Option<String>,
+    pub primary_repo_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::project_members::Entity")]
+    ProjectMembers,
     #[sea_orm(has_many = "super::repos::Entity")]
     Repos,
 }
 
+impl Related<super::project_members::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProjectMembers.def()
+    }
+}
+
 impl Related<super::repos::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Repos.def()
@@ -22,10 +22,20 @@ pub struct Model {
     pub license: Option<String>,
     pub last_fetch: DateTime,
     pub project_id: i32,
+    pub forge: String,
+    pub releases: i32,
+    pub host: String,
+    pub sync_status: String,
+    pub failed_syncs: i32,
+    pub forge_id: Option<i64>,
+    pub archived: bool,
+    pub local_path: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::activity_events::Entity")]
+    ActivityEvents,
     #[sea_orm(
         belongs_to = "super::projects::Entity",
         from = "Column::ProjectId",
@@ -34,6 +44,14 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Projects,
+    #[sea_orm(has_many = "super::sync_runs::Entity")]
+    SyncRuns,
+}
+
+impl Related<super::activity_events::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ActivityEvents.def()
+    }
 }
 
 impl Related<super::projects::Entity> for Entity {
@@ -41,3 +59,9 @@ impl Related<super::projects::Entity> for Entity {
         Relation::Projects.def()
     }
 }
+
+impl Related<super::sync_runs::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SyncRuns.def()
+    }
+}
@@ -14,8 +14,6 @@ pub struct Model {
     #[sea_orm(unique)]
     pub email: String,
     pub password: String,
-    #[sea_orm(unique)]
-    pub api_key: String,
     pub name: String,
     pub reset_token: Option<String>,
     pub reset_sent_at: Option<DateTimeWithTimeZone>,
@@ -24,7 +22,73 @@ pub struct Model {
     pub email_verified_at: Option<DateTimeWithTimeZone>,
     pub magic_link_token: Option<String>,
     pub magic_link_expiration: Option<DateTimeWithTimeZone>,
+    pub is_admin: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::api_keys::Entity")]
+    ApiKeys,
+    #[sea_orm(has_many = "super::data_exports::Entity")]
+    DataExports,
+    #[sea_orm(has_many = "super::email_changes::Entity")]
+    EmailChanges,
+    #[sea_orm(has_many = "super::project_members::Entity")]
+    ProjectMembers,
+    #[sea_orm(has_many = "super::recovery_codes::Entity")]
+    RecoveryCodes,
+    #[sea_orm(has_many = "super::sessions::Entity")]
+    Sessions,
+    #[sea_orm(has_one = "super::totp_credentials::Entity")]
+    TotpCredentials,
+    #[sea_orm(has_many = "super::user_identities::Entity")]
+    UserIdentities,
+}
+
+impl Related<super::api_keys::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ApiKeys.def()
+    }
+}
+
+impl Related<super::data_exports::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::DataExports.def()
+    }
+}
+
+impl Related<super::email_changes::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::EmailChanges.def()
+    }
+}
+
+impl Related<super::project_members::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProjectMembers.def()
+    }
+}
+
+impl Related<super::recovery_codes::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RecoveryCodes.def()
+    }
+}
+
+impl Related<super::sessions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Sessions.def()
+    }
+}
+
+impl Related<super::totp_credentials::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TotpCredentials.def()
+    }
+}
+
+impl Related<super::user_identities::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserIdentities.def()
+    }
+}
@@ -0,0 +1,58 @@
+pub use super::_entities::activity_events::{ActiveModel, Entity, Model};
+use sea_orm::{
+    entity::prelude::*,
+    sea_query::{Expr, Func},
+    Condition, QueryOrder, QuerySelect,
+};
+
+use super::_entities::{activity_events::Column, repos, user_identities};
+pub type ActivityEvents = Entity;
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {}
+
+// implement your write-oriented logic here
+impl ActiveModel {}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {
+    /// Events by the linked accounts `identities`, each on repos of the forge
+    /// the account is on, newest first. Nothing for no identities.
+    #[must_use]
+    pub fn by_identities(identities: &[user_identities::Model]) -> Select<Self> {
+        let by_any = identities.iter().fold(Condition::any(), |cond, identity| {
+            cond.add(
+                Condition::all()
+                    .add(repos::Column::Forge.eq(identity.provider.as_str()))
+                    .add(
+                        Expr::expr(Func::lower(Expr::col((Self, Column::Actor))))
+                            .eq(identity.login.to_lowercase()),
+                    ),
+            )
+        });
+        Self::find()
+            .join(
+                sea_orm::JoinType::InnerJoin,
+                super::_entities::activity_events::Relation::Repos.def(),
+            )
+            .filter(by_any)
+            .order_by_desc(Column::OccurredAt)
+            .order_by_desc(Column::Id)
+    }
+}
@@ -0,0 +1,205 @@
+pub use super::_entities::api_keys::{ActiveModel, Column, Entity, Model};
+use chrono::Utc;
+use loco_rs::prelude::Set;
+use sea_orm::{entity::prelude::*, IntoActiveModel, QueryOrder};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+pub type ApiKeys = Entity;
+
+/// Keys start with this, so they are easy to spot in logs and secret scanners.
+pub const KEY_PREFIX: &str = "gch_";
+
+/// How much of a key is stored in the clear, to tell keys apart.
+const SHOWN_CHARS: usize = 11;
+
+/// What a key may change. Reads need no key at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    /// Create, change and delete projects.
+    Projects,
+    /// Import, move and remove repos.
+    Repos,
+    /// Manage project members.
+    Members,
+}
+
+impl Scope {
+    pub const ALL: [Self; 3] = [Self::Projects, Self::Repos, Self::Members];
+
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Projects => "projects",
+            Self::Repos => "repos",
+            Self::Members => "members",
+        }
+    }
+}
+
+impl std::str::FromStr for Scope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or_else(|| format!("unknown scope `{s}`, use projects, repos or members"))
+    }
+}
+
+/// A key as handed out, the only time its secret is known.
+#[derive(Debug)]
+pub struct Issued {
+    pub key: Model,
+    pub secret: String,
+}
+
+fn hash(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+fn new_secret() -> String {
+    format!("{KEY_PREFIX}{}", Uuid::new_v4().simple())
+}
+
+fn shown(secret: &str) -> String {
+    secret.chars().take(SHOWN_CHARS).collect()
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+impl Model {
+    #[must_use]
+    pub fn scopes(&self) -> Vec<Scope> {
+        self.scopes
+            .split_whitespace()
+            .filter_map(|s| s.parse().ok())
+            .collect()
+    }
+
+    #[must_use]
+    pub fn allows(&self, scope: Scope) -> bool {
+        self.scopes().contains(&scope)
+    }
+
+    /// Replace the secret of this key, the old one stops working at once.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn rotate<C>(self, db: &C) -> Result<Issued, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let secret = new_secret();
+        let mut key = self.into_active_model();
+        key.key_hash = Set(hash(&secret));
+        key.prefix = Set(shown(&secret));
+        key.last_used_at = Set(None);
+        Ok(Issued {
+            key: key.update(db).await?,
+            secret,
+        })
+    }
+
+    /// Stop accepting this key. Revoked keys stay listed.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn revoke<C>(self, db: &C) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let mut key = self.into_active_model();
+        key.revoked_at = Set(Some(Utc::now().into()));
+        key.update(db).await
+    }
+}
+
+impl Entity {
+    /// Create a key for `user_id`.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn issue<C>(
+        db: &C,
+        user_id: i32,
+        name: &str,
+        scopes: &[Scope],
+    ) -> Result<Issued, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let secret = new_secret();
+        let key = ActiveModel {
+            user_id: Set(user_id),
+            name: Set(name.to_string()),
+            key_hash: Set(hash(&secret)),
+            prefix: Set(shown(&secret)),
+            scopes: Set(scopes
+                .iter()
+                .map(|s| s.as_str())
+                .collect::<Vec<_>>()
+                .join(" ")),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+        Ok(Issued { key, secret })
+    }
+
+    /// The live key with this secret, marked as used now.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn authenticate<C>(db: &C, secret: &str) -> Result<Option<Model>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let Some(key) = Self::find()
+            .filter(Column::KeyHash.eq(hash(secret)))
+            .filter(Column::RevokedAt.is_null())
+            .one(db)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let mut key = key.into_active_model();
+        key.last_used_at = Set(Some(Utc::now().into()));
+        Ok(Some(key.update(db).await?))
+    }
+
+    /// All keys of `user_id`, revoked ones included.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn for_user<C>(db: &C, user_id: i32) -> Result<Vec<Model>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Self::find()
+            .filter(Column::UserId.eq(user_id))
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await
+    }
+}
@@ -0,0 +1,242 @@
+pub use super::_entities::audit_events::{ActiveModel, Column, Entity, Model};
+use chrono::Utc;
+use loco_rs::prelude::{query, Set};
+use sea_orm::{entity::prelude::*, Condition};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+pub type AuditEvents = Entity;
+
+/// A key was locked out after too many attempts, see [`super::auth_throttles`].
+pub const AUTH_LOCKOUT: &str = "auth.lockout";
+/// Someone registered.
+pub const AUTH_REGISTERED: &str = "auth.registered";
+/// A user verified their email address.
+pub const AUTH_VERIFIED: &str = "auth.verified";
+/// A user signed in and got a session.
+pub const AUTH_SIGNED_IN: &str = "auth.signed_in";
+/// A password did not match, or nobody has the email address.
+pub const AUTH_SIGN_IN_FAILED: &str = "auth.sign_in_failed";
+/// A user ended one or all of their sessions.
+pub const AUTH_SIGNED_OUT: &str = "auth.signed_out";
+/// A user set a new password with a reset link.
+pub const AUTH_PASSWORD_RESET: &str = "auth.password_reset";
+/// A user turned two-factor authentication on.
+pub const TWO_FACTOR_ENABLED: &str = "auth.2fa_enabled";
+/// A user turned two-factor authentication off.
+pub const TWO_FACTOR_DISABLED: &str = "auth.2fa_disabled";
+/// A user linked an account on another site.
+pub const IDENTITY_LINKED: &str = "auth.identity_linked";
+/// A user unlinked an account on another site.
+pub const IDENTITY_UNLINKED: &str = "auth.identity_unlinked";
+/// A user created an API key.
+pub const API_KEY_CREATED: &str = "api_key.created";
+/// A user rotated an API key.
+pub const API_KEY_ROTATED: &str = "api_key.rotated";
+/// A user revoked an API key.
+pub const API_KEY_REVOKED: &str = "api_key.revoked";
+/// A user confirmed a new email address.
+pub const EMAIL_CHANGED: &str = "account.email_changed";
+/// A user changed their password, knowing the old one.
+pub const PASSWORD_CHANGED: &str = "account.password_changed";
+/// A user deleted their account.
+pub const ACCOUNT_DELETED: &str = "account.deleted";
+/// An admin made another user an admin.
+pub const ADMIN_GRANTED: &str = "admin.granted";
+/// An admin took the admin role from another user.
+pub const ADMIN_REVOKED: &str = "admin.revoked";
+/// An admin signed a user out everywhere.
+pub const SESSIONS_REVOKED: &str = "admin.sessions_revoked";
+/// An admin queued a repo to be fetched again.
+pub const REPO_RESYNC: &str = "admin.repo_resync";
+/// An admin changed how repo health is scored.
+pub const HEALTH_CONFIG_CHANGED: &str = "admin.health_config_changed";
+/// A project was created.
+pub const PROJECT_CREATED: &str = "project.created";
+/// A project was edited.
+pub const PROJECT_UPDATED: &str = "project.updated";
+/// A project was deleted.
+pub const PROJECT_DELETED: &str = "project.deleted";
+/// The health of a project changed when it was recalculated.
+pub const PROJECT_HEALTH_CHANGED: &str = "project.health_changed";
+/// Someone was given a role in a project, or a new one.
+pub const MEMBER_GRANTED: &str = "project.member_granted";
+/// Someone lost their role in a project.
+pub const MEMBER_REMOVED: &str = "project.member_removed";
+/// A repo was queued to be imported from its forge.
+pub const REPO_QUEUED: &str = "repo.queued";
+/// A repo was edited or moved to another project.
+pub const REPO_UPDATED: &str = "repo.updated";
+/// A repo was deleted.
+pub const REPO_DELETED: &str = "repo.deleted";
+
+/// Fields left out of [`changes`], they change on every save.
+const UNTRACKED: &[&str] = &["updated_at"];
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+/// Something to write to the audit log, see [`NewEvent::record`].
+#[derive(Debug, Default)]
+pub struct NewEvent {
+    /// What happened, like [`AUTH_LOCKOUT`].
+    pub action: String,
+    /// The user who did it, `None` when nobody was signed in.
+    pub actor_id: Option<i32>,
+    /// What it happened to.
+    pub target: Option<String>,
+    /// Where the request came from.
+    pub ip: Option<String>,
+    pub details: Option<serde_json::Value>,
+    /// What it changed, see [`changes`].
+    pub changes: Option<serde_json::Value>,
+    /// The request it happened in, to find the related events and logs.
+    pub request_id: Option<String>,
+}
+
+impl NewEvent {
+    #[must_use]
+    pub fn new(action: &str) -> Self {
+        Self {
+            action: action.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// `actor_id` created, edited or deleted a row, with what changed.
+    /// `before` is `None` for a new row, `after` for a deleted one.
+    #[must_use]
+    pub fn change<T: Audited>(
+        action: &str,
+        actor_id: i32,
+        before: Option<&T>,
+        after: Option<&T>,
+    ) -> Self {
+        Self {
+            actor_id: Some(actor_id),
+            target: after.or(before).map(Audited::audit_target),
+            changes: changes(before, after),
+            ..Self::new(action)
+        }
+    }
+
+    /// Write the event.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn record<C>(self, db: &C) -> Result<Model, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        ActiveModel {
+            action: Set(self.action),
+            actor_id: Set(self.actor_id),
+            target: Set(self.target),
+            ip: Set(self.ip),
+            details: Set(self.details),
+            changes: Set(self.changes),
+            request_id: Set(self.request_id),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+    }
+}
+
+/// A row whose edits are written to the audit log.
+pub trait Audited: Serialize {
+    /// How events name the row, like `project:1`.
+    fn audit_target(&self) -> String;
+}
+
+/// The fields that differ between `before` and `after`, as
+/// `{"field": {"before": .., "after": ..}}`. `None` for `before` is a row
+/// being created, for `after` one being deleted.
+///
+/// Only give it rows without secrets, every field is written out.
+#[must_use]
+pub fn changes<T: Serialize>(before: Option<&T>, after: Option<&T>) -> Option<Value> {
+    let fields = |row: Option<&T>| match row.map(serde_json::to_value) {
+        Some(Ok(Value::Object(fields))) => fields,
+        _ => Map::new(),
+    };
+    let (before, after) = (fields(before), fields(after));
+    let mut changed = Map::new();
+    for key in before.keys().chain(after.keys()) {
+        if UNTRACKED.contains(&key.as_str()) || changed.contains_key(key) {
+            continue;
+        }
+        let (old, new) = (before.get(key), after.get(key));
+        if old != new {
+            changed.insert(
+                key.clone(),
+                json!({ "before": old.unwrap_or(&Value::Null), "after": new.unwrap_or(&Value::Null) }),
+            );
+        }
+    }
+    Some(Value::Object(changed))
+        .filter(|changed| changed.as_object().is_some_and(|c| !c.is_empty()))
+}
+
+/// Which events the viewer shows, all when empty.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Filter {
+    /// An action, or its first part like `auth` for all `auth.*` ones.
+    pub action: Option<String>,
+    pub actor_id: Option<i32>,
+    pub target: Option<String>,
+    pub request_id: Option<String>,
+    pub since: Option<DateTimeWithTimeZone>,
+    pub until: Option<DateTimeWithTimeZone>,
+}
+
+impl Filter {
+    #[must_use]
+    pub fn condition(&self) -> Condition {
+        let mut condition = query::condition();
+        if let Some(action) = self
+            .action
+            .as_deref()
+            .map(str::trim)
+            .filter(|a| !a.is_empty())
+        {
+            condition = if action.contains('.') {
+                condition.eq(Column::Action, action)
+            } else {
+                condition.starts_with(Column::Action, format!("{action}."))
+            };
+        }
+        if let Some(actor_id) = self.actor_id {
+            condition = condition.eq(Column::ActorId, actor_id);
+        }
+        for (column, value) in [
+            (Column::Target, &self.target),
+            (Column::RequestId, &self.request_id),
+        ] {
+            if let Some(value) = value.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+                condition = condition.eq(column, value);
+            }
+        }
+        if let Some(since) = self.since {
+            condition = condition.gte(Column::CreatedAt, since);
+        }
+        if let Some(until) = self.until {
+            condition = condition.lt(Column::CreatedAt, until);
+        }
+        condition.build()
+    }
+}
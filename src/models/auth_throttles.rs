@@ -0,0 +1,226 @@
+//! Counting attempts at the auth endpoints, to lock out whoever makes too
+//! many. Attempts are counted per key, like `login:ip:192.0.2.1` or
+//! `login:account:someone@example.com`, in the database so that lockouts
+//! survive restarts and hold across instances.
+pub use super::_entities::auth_throttles::{ActiveModel, Column, Entity, Model};
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use loco_rs::prelude::Set;
+use sea_orm::{
+    entity::prelude::*,
+    sea_query::{Expr, OnConflict},
+};
+use serde::Deserialize;
+
+pub type AuthThrottles = Entity;
+
+/// Thresholds, read from the `throttle` settings.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Settings {
+    /// Seconds attempts are counted over.
+    #[serde(default = "default_window")]
+    pub window: u64,
+    /// Attempts one IP address may make at an endpoint per window.
+    #[serde(default = "default_per_ip")]
+    pub per_ip: u32,
+    /// Attempts that may be made for one account at an endpoint per window.
+    #[serde(default = "default_per_account")]
+    pub per_account: u32,
+    /// Seconds of the first lockout, each one after it is twice as long.
+    #[serde(default = "default_lockout")]
+    pub lockout: u64,
+    /// Seconds no lockout is longer than.
+    #[serde(default = "default_max_lockout")]
+    pub max_lockout: u64,
+}
+
+const fn default_window() -> u64 {
+    15 * 60
+}
+
+const fn default_per_ip() -> u32 {
+    20
+}
+
+const fn default_per_account() -> u32 {
+    5
+}
+
+const fn default_lockout() -> u64 {
+    60
+}
+
+const fn default_max_lockout() -> u64 {
+    60 * 60
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            window: default_window(),
+            per_ip: default_per_ip(),
+            per_account: default_per_account(),
+            lockout: default_lockout(),
+            max_lockout: default_max_lockout(),
+        }
+    }
+}
+
+fn seconds(secs: u64) -> Duration {
+    Duration::seconds(i64::try_from(secs).unwrap_or(i64::MAX))
+}
+
+impl Settings {
+    /// How long the lockout after `lockouts` earlier ones lasts.
+    #[must_use]
+    pub fn lockout_after(&self, lockouts: i32) -> Duration {
+        let doublings = u32::try_from(lockouts).unwrap_or_default().min(32);
+        seconds(
+            self.lockout
+                .saturating_mul(2u64.saturating_pow(doublings))
+                .min(self.max_lockout),
+        )
+    }
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+impl Model {
+    #[must_use]
+    pub fn is_locked(&self) -> bool {
+        self.locked_until.is_some_and(|until| until > Utc::now())
+    }
+}
+
+impl Entity {
+    /// When the longest running lockout of `keys` ends, `None` when none of
+    /// them is locked out.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn locked_until<C>(
+        db: &C,
+        keys: &[String],
+    ) -> Result<Option<DateTime<FixedOffset>>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Self::find()
+            .filter(Column::Key.is_in(keys))
+            .filter(Column::LockedUntil.gt(Utc::now()))
+            .all(db)
+            .await?
+            .into_iter()
+            .filter_map(|throttle| throttle.locked_until)
+            .max())
+    }
+
+    /// Count an attempt against `key`, locking it out once it made `limit`
+    /// within the window. Returns the throttle when this locked it out.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn hit<C>(
+        db: &C,
+        settings: &Settings,
+        key: &str,
+        limit: u32,
+    ) -> Result<Option<Model>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let now: DateTime<FixedOffset> = Utc::now().into();
+        Self::insert(ActiveModel {
+            key: Set(key.to_string()),
+            attempts: Set(0),
+            window_started_at: Set(now),
+            lockouts: Set(0),
+            ..Default::default()
+        })
+        .on_conflict(OnConflict::column(Column::Key).do_nothing().to_owned())
+        .do_nothing()
+        .exec(db)
+        .await?;
+        // counted in one statement, so concurrent attempts all add up
+        let window_over = Expr::col(Column::WindowStartedAt).lte(now - seconds(settings.window));
+        // lockouts only keep doubling while they keep coming
+        let calm = Expr::col(Column::LockedUntil)
+            .is_null()
+            .or(Expr::col(Column::LockedUntil).lt(now - seconds(settings.max_lockout)));
+        let counted = Self::update_many()
+            .col_expr(
+                Column::Attempts,
+                Expr::case(window_over.clone(), 1)
+                    .finally(Expr::col(Column::Attempts).add(1))
+                    .into(),
+            )
+            .col_expr(
+                Column::Lockouts,
+                Expr::case(window_over.clone().and(calm), 0)
+                    .finally(Expr::col(Column::Lockouts))
+                    .into(),
+            )
+            .col_expr(
+                Column::WindowStartedAt,
+                Expr::case(window_over, now)
+                    .finally(Expr::col(Column::WindowStartedAt))
+                    .into(),
+            )
+            .col_expr(Column::UpdatedAt, Expr::value(now))
+            .filter(Column::Key.eq(key))
+            .exec_with_returning(db)
+            .await?
+            .pop()
+            .ok_or_else(|| DbErr::RecordNotFound(key.to_string()))?;
+        if i64::from(counted.attempts) < i64::from(limit) {
+            return Ok(None);
+        }
+
+        // of concurrent attempts over the limit, only one locks it out
+        Ok(Self::update_many()
+            .col_expr(
+                Column::LockedUntil,
+                Expr::value(now + settings.lockout_after(counted.lockouts)),
+            )
+            .col_expr(Column::Lockouts, Expr::col(Column::Lockouts).add(1))
+            .col_expr(Column::Attempts, Expr::value(0))
+            .col_expr(Column::WindowStartedAt, Expr::value(now))
+            .col_expr(Column::UpdatedAt, Expr::value(now))
+            .filter(Column::Key.eq(key))
+            .filter(Column::Attempts.gte(limit))
+            .exec_with_returning(db)
+            .await?
+            .pop())
+    }
+
+    /// Forget the attempts against `key`, after it was used successfully.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn clear<C>(db: &C, key: &str) -> Result<(), DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Self::delete_many()
+            .filter(Column::Key.eq(key))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+}
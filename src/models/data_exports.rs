@@ -0,0 +1,192 @@
+pub use super::_entities::data_exports::{ActiveModel, Column, Entity, Model};
+use chrono::{Duration, Utc};
+use loco_rs::prelude::Set;
+use sea_orm::{entity::prelude::*, IntoActiveModel, QueryOrder};
+use serde_json::json;
+use uuid::Uuid;
+
+use super::{
+    api_keys, audit_events, project_members, projects, sessions, totp_credentials, user_identities,
+    users,
+};
+
+pub type DataExports = Entity;
+
+/// Days the link to a finished export works for.
+pub const EXPIRATION_DAYS: i64 = 7;
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+/// Everything stored about `user`, as JSON. Secrets are left out: password
+/// and key hashes, tokens and the two-factor secret.
+///
+/// # Errors
+///
+/// DB Error.
+pub async fn assemble<C>(db: &C, user: &users::Model) -> Result<serde_json::Value, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let identities = user_identities::Entity::for_user(db, user.id).await?;
+    let memberships = project_members::Entity::find()
+        .filter(project_members::Column::UserId.eq(user.id))
+        .find_also_related(projects::Entity)
+        .order_by_asc(project_members::Column::ProjectId)
+        .all(db)
+        .await?;
+    let keys = api_keys::Entity::find()
+        .filter(api_keys::Column::UserId.eq(user.id))
+        .order_by_asc(api_keys::Column::Id)
+        .all(db)
+        .await?;
+    let sessions = sessions::Entity::find()
+        .filter(sessions::Column::UserId.eq(user.id))
+        .order_by_asc(sessions::Column::Id)
+        .all(db)
+        .await?;
+    let two_factor = totp_credentials::Entity::enabled_for(db, user.id).await?;
+    let events = audit_events::Entity::find()
+        .filter(audit_events::Column::ActorId.eq(user.id))
+        .order_by_asc(audit_events::Column::Id)
+        .all(db)
+        .await?;
+
+    Ok(json!({
+        "exported_at": Utc::now(),
+        "profile": {
+            "pid": user.pid,
+            "name": user.name,
+            "email": user.email,
+            "created_at": user.created_at,
+            "email_verified_at": user.email_verified_at,
+            "two_factor_enabled": two_factor.is_some(),
+        },
+        "identities": identities.iter().map(|identity| json!({
+            "provider": identity.provider,
+            "provider_user_id": identity.provider_user_id,
+            "login": identity.login,
+            "linked_at": identity.created_at,
+        })).collect::<Vec<_>>(),
+        "project_memberships": memberships.iter().map(|(member, project)| json!({
+            "project_id": member.project_id,
+            "project": project.as_ref().map(|project| &project.name),
+            "role": member.role,
+            "since": member.created_at,
+        })).collect::<Vec<_>>(),
+        "api_keys": keys.iter().map(|key| json!({
+            "name": key.name,
+            "prefix": key.prefix,
+            "scopes": key.scopes,
+            "created_at": key.created_at,
+            "last_used_at": key.last_used_at,
+            "revoked_at": key.revoked_at,
+        })).collect::<Vec<_>>(),
+        "sessions": sessions.iter().map(|session| json!({
+            "user_agent": session.user_agent,
+            "created_at": session.created_at,
+            "last_used_at": session.last_used_at,
+            "expires_at": session.expires_at,
+            "revoked_at": session.revoked_at,
+        })).collect::<Vec<_>>(),
+        "audit_events": events.iter().map(|event| json!({
+            "action": event.action,
+            "target": event.target,
+            "ip": event.ip,
+            "details": event.details,
+            "at": event.created_at,
+        })).collect::<Vec<_>>(),
+    }))
+}
+
+impl Model {
+    #[must_use]
+    pub const fn is_complete(&self) -> bool {
+        self.completed_at.is_some()
+    }
+
+    /// Store the assembled `archive`, starting the time the link works for.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn complete<C>(self, db: &C, archive: serde_json::Value) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let now = Utc::now();
+        let mut export = self.into_active_model();
+        export.archive = Set(Some(archive));
+        export.completed_at = Set(Some(now.into()));
+        export.expires_at = Set(Some((now + Duration::days(EXPIRATION_DAYS)).into()));
+        export.update(db).await
+    }
+}
+
+impl Entity {
+    /// Ask for an export of the data of `user_id`, to be assembled by
+    /// [`crate::workers::data_export::DataExportWorker`].
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn request<C>(db: &C, user_id: i32) -> Result<Model, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        ActiveModel {
+            user_id: Set(user_id),
+            token: Set(Uuid::new_v4().simple().to_string()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+    }
+
+    /// The finished export `token` links to, `None` when it is unknown,
+    /// still being assembled or expired.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn find_ready<C>(db: &C, token: &str) -> Result<Option<Model>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Self::find()
+            .filter(Column::Token.eq(token))
+            .filter(Column::CompletedAt.is_not_null())
+            .filter(Column::ExpiresAt.gt(Utc::now()))
+            .one(db)
+            .await
+    }
+
+    /// Forget exports whose links expired, returning how many.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn delete_expired<C>(db: &C) -> Result<u64, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let res = Self::delete_many()
+            .filter(Column::ExpiresAt.lt(Utc::now()))
+            .exec(db)
+            .await?;
+        Ok(res.rows_affected)
+    }
+}
@@ -0,0 +1,107 @@
+pub use super::_entities::email_changes::{ActiveModel, Column, Entity, Model};
+use chrono::{Duration, Utc};
+use loco_rs::{model::ModelError, prelude::Set};
+use sea_orm::{entity::prelude::*, IntoActiveModel, TransactionTrait};
+use uuid::Uuid;
+
+use super::users;
+
+pub type EmailChanges = Entity;
+
+/// Hours the link to confirm a new address works for.
+pub const EXPIRATION_HOURS: i64 = 24;
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+impl Model {
+    /// Move the user over to the new address, which is verified by following
+    /// the link. Links sent to the old address stop working.
+    ///
+    /// # Errors
+    ///
+    /// [`ModelError::EntityAlreadyExists`] when another user took the address
+    /// in the meantime, or DB Error.
+    pub async fn complete(self, db: &DatabaseConnection) -> Result<users::Model, ModelError> {
+        let txn = db.begin().await?;
+        let taken = users::Entity::find()
+            .filter(users::users::Column::Email.eq(&self.new_email))
+            .one(&txn)
+            .await?
+            .is_some();
+        if taken {
+            return Err(ModelError::EntityAlreadyExists);
+        }
+        let user = users::Entity::find_by_id(self.user_id)
+            .one(&txn)
+            .await?
+            .ok_or(ModelError::EntityNotFound)?;
+        let mut user = user.into_active_model();
+        user.email = Set(self.new_email.clone());
+        user.email_verified_at = Set(Some(Utc::now().into()));
+        user.reset_token = Set(None);
+        user.reset_sent_at = Set(None);
+        user.magic_link_token = Set(None);
+        user.magic_link_expiration = Set(None);
+        let user = user.update(&txn).await?;
+        self.delete(&txn).await?;
+        txn.commit().await?;
+        Ok(user)
+    }
+}
+
+impl Entity {
+    /// Start changing the address of `user_id` to `new_email`, replacing a
+    /// change that was not confirmed.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn start<C>(db: &C, user_id: i32, new_email: &str) -> Result<Model, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Self::delete_many()
+            .filter(Column::UserId.eq(user_id))
+            .exec(db)
+            .await?;
+        ActiveModel {
+            user_id: Set(user_id),
+            new_email: Set(new_email.to_string()),
+            token: Set(Uuid::new_v4().to_string()),
+            expires_at: Set((Utc::now() + Duration::hours(EXPIRATION_HOURS)).into()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+    }
+
+    /// The change `token` confirms, `None` when it is unknown or expired.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn find_pending<C>(db: &C, token: &str) -> Result<Option<Model>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Self::find()
+            .filter(Column::Token.eq(token))
+            .filter(Column::ExpiresAt.gt(Utc::now()))
+            .one(db)
+            .await
+    }
+}
@@ -0,0 +1,92 @@
+pub use super::_entities::email_domain_rules::{ActiveModel, Column, Entity, Model};
+use loco_rs::prelude::Set;
+use sea_orm::{entity::prelude::*, QueryOrder, TransactionTrait};
+
+use crate::email_domains::Rules;
+
+pub type EmailDomainRules = Entity;
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+impl Entity {
+    /// The rules set by an admin, `None` when the configured ones apply.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn overrides<C>(db: &C) -> Result<Option<Rules>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let rows = Self::find().order_by_asc(Column::Id).all(db).await?;
+        if rows.is_empty() {
+            return Ok(None);
+        }
+        let (allow, deny): (Vec<_>, Vec<_>) = rows.into_iter().partition(|row| row.allow);
+        let patterns = |rows: Vec<Model>| rows.into_iter().map(|row| row.pattern).collect();
+        Ok(Some(Rules {
+            allow: patterns(allow),
+            deny: patterns(deny),
+        }))
+    }
+
+    /// The rules that apply, `configured` unless an admin overrode them.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn effective<C>(db: &C, configured: &Rules) -> Result<Rules, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Self::overrides(db)
+            .await?
+            .unwrap_or_else(|| configured.clone()))
+    }
+
+    /// Override the configured rules with `rules`. Empty rules go back to
+    /// the configured ones.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn replace(db: &DatabaseConnection, rules: &Rules) -> Result<(), DbErr> {
+        let txn = db.begin().await?;
+        Self::delete_many().exec(&txn).await?;
+        // a pattern both allowed and denied is denied anyway
+        let rows = rules
+            .deny
+            .iter()
+            .map(|pattern| (pattern, false))
+            .chain(rules.allow.iter().map(|pattern| (pattern, true)));
+        let mut seen = std::collections::HashSet::new();
+        for (pattern, allow) in rows {
+            let pattern = pattern.trim().to_lowercase();
+            if pattern.is_empty() || !seen.insert(pattern.clone()) {
+                continue;
+            }
+            ActiveModel {
+                pattern: Set(pattern),
+                allow: Set(allow),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await?;
+        }
+        txn.commit().await
+    }
+}
@@ -0,0 +1,69 @@
+pub use super::_entities::health_configs::{ActiveModel, Column, Entity, Model};
+use loco_rs::prelude::Set;
+use sea_orm::{entity::prelude::*, IntoActiveModel, QueryOrder};
+
+use crate::health::Config;
+
+pub type HealthConfigs = Entity;
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+impl Entity {
+    /// The health config repos are scored with, the default one until an
+    /// admin changes it.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn current<C>(db: &C) -> Result<Config, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let Some(row) = Self::find().order_by_asc(Column::Id).one(db).await? else {
+            return Ok(Config::default());
+        };
+        serde_json::from_value(row.config).map_err(|err| DbErr::Json(err.to_string()))
+    }
+
+    /// Score repos with `config` from now on.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn replace<C>(db: &C, config: &Config) -> Result<(), DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let value = serde_json::to_value(config).map_err(|err| DbErr::Json(err.to_string()))?;
+        match Self::find().order_by_asc(Column::Id).one(db).await? {
+            Some(row) => {
+                let mut row = row.into_active_model();
+                row.config = Set(value);
+                row.update(db).await?;
+            }
+            None => {
+                ActiveModel {
+                    config: Set(value),
+                    ..Default::default()
+                }
+                .insert(db)
+                .await?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+pub use super::_entities::health_distributions::{ActiveModel, Column, Entity, Model};
+use loco_rs::prelude::Set;
+use sea_orm::{entity::prelude::*, IntoActiveModel, QueryOrder};
+
+use crate::health::Distribution;
+
+pub type HealthDistributions = Entity;
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+impl Entity {
+    /// Where tracked repos stood when every project was last recalculated,
+    /// `None` before that.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn current<C>(db: &C) -> Result<Option<Distribution>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let Some(row) = Self::find().order_by_asc(Column::Id).one(db).await? else {
+            return Ok(None);
+        };
+        serde_json::from_value(row.distribution)
+            .map(Some)
+            .map_err(|err| DbErr::Json(err.to_string()))
+    }
+
+    /// Score repos against `distribution` from now on.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn replace<C>(db: &C, distribution: &Distribution) -> Result<(), DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let value =
+            serde_json::to_value(distribution).map_err(|err| DbErr::Json(err.to_string()))?;
+        match Self::find().order_by_asc(Column::Id).one(db).await? {
+            Some(row) => {
+                let mut row = row.into_active_model();
+                row.distribution = Set(value);
+                row.update(db).await?;
+            }
+            None => {
+                ActiveModel {
+                    distribution: Set(value),
+                    ..Default::default()
+                }
+                .insert(db)
+                .await?;
+            }
+        }
+        Ok(())
+    }
+}
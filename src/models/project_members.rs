@@ -0,0 +1,140 @@
+pub use super::_entities::project_members::{ActiveModel, Column, Entity, Model};
+use loco_rs::prelude::Set;
+use sea_orm::{entity::prelude::*, sea_query::OnConflict};
+
+pub type ProjectMembers = Entity;
+
+/// What a member may do with a project, stored in `project_members.role`.
+///
+/// Roles are ordered, every role may do what the ones before it may.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    /// Listed on the project, can not change it.
+    Viewer,
+    /// Changes the project and adds, moves and removes its repos.
+    Maintainer,
+    /// Also deletes the project and manages its members.
+    Owner,
+}
+
+impl Role {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Viewer => "viewer",
+            Self::Maintainer => "maintainer",
+            Self::Owner => "owner",
+        }
+    }
+}
+
+impl std::str::FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "viewer" => Ok(Self::Viewer),
+            "maintainer" => Ok(Self::Maintainer),
+            "owner" => Ok(Self::Owner),
+            other => Err(format!(
+                "unknown role `{other}`, use viewer, maintainer or owner"
+            )),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+impl Model {
+    #[must_use]
+    pub fn role(&self) -> Option<Role> {
+        self.role.parse().ok()
+    }
+}
+
+impl Entity {
+    /// The role of `user_id` in `project_id`, `None` when not a member.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn role_of<C>(db: &C, project_id: i32, user_id: i32) -> Result<Option<Role>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Self::find()
+            .filter(Column::ProjectId.eq(project_id))
+            .filter(Column::UserId.eq(user_id))
+            .one(db)
+            .await?
+            .and_then(|member| member.role()))
+    }
+
+    /// Give `user_id` `role` in `project_id`, replacing the role they had.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn grant<C>(db: &C, project_id: i32, user_id: i32, role: Role) -> Result<(), DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Self::insert(ActiveModel {
+            project_id: Set(project_id),
+            user_id: Set(user_id),
+            role: Set(role.as_str().to_string()),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::columns([Column::ProjectId, Column::UserId])
+                .update_columns([Column::Role, Column::UpdatedAt])
+                .to_owned(),
+        )
+        .exec(db)
+        .await?;
+        Ok(())
+    }
+
+    /// The projects only `user_id` owns that have other members, who would
+    /// be left without an owner if the user went away.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn left_without_owner<C>(db: &C, user_id: i32) -> Result<Vec<i32>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let owned = Self::find()
+            .filter(Column::UserId.eq(user_id))
+            .filter(Column::Role.eq(Role::Owner.as_str()))
+            .all(db)
+            .await?;
+        let mut projects = Vec::new();
+        for membership in owned {
+            let others = Self::find()
+                .filter(Column::ProjectId.eq(membership.project_id))
+                .filter(Column::UserId.ne(user_id))
+                .all(db)
+                .await?;
+            if !others.is_empty() && !others.iter().any(|m| m.role() == Some(Role::Owner)) {
+                projects.push(membership.project_id);
+            }
+        }
+        Ok(projects)
+    }
+}
@@ -1,7 +1,76 @@
-pub use super::_entities::projects::{ActiveModel, Entity, Model};
-use sea_orm::entity::prelude::*;
+pub use super::_entities::projects::{ActiveModel, Column, Entity, Model};
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+};
+
+use sea_orm::{entity::prelude::*, TransactionTrait};
+use tokio::task::JoinSet;
+
+use super::{
+    _entities::repos,
+    audit_events::{self, Audited, NewEvent},
+    health_configs, health_distributions,
+};
+use crate::health::{Config, Distribution, Strategy};
+
 pub type Projects = Entity;
 
+/// How many projects [`Entity::recalculate_all_health`] saves at once,
+/// unless told otherwise.
+pub const RECALCULATE_CONCURRENCY: usize = 8;
+
+/// Projects whose repos changed, to recalculate once each when the changes
+/// are committed, however many of their repos changed.
+#[derive(Debug, Default)]
+pub struct StaleHealth(BTreeSet<i32>);
+
+impl StaleHealth {
+    pub fn add(&mut self, project_id: i32) {
+        self.0.insert(project_id);
+    }
+
+    /// Recalculate every project added, reading the config and their repos
+    /// once. Returns how many projects were recalculated.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn recalculate<C>(self, db: &C) -> Result<usize, DbErr>
+    where
+        C: ConnectionTrait + TransactionTrait,
+    {
+        if self.0.is_empty() {
+            return Ok(0);
+        }
+        let ids: Vec<i32> = self.0.into_iter().collect();
+        let config = health_configs::Entity::current(db).await?;
+        let distribution = health_distributions::Entity::current(db).await?;
+        let mut by_project = repos_by_project(
+            repos::Entity::find()
+                .filter(repos::Column::ProjectId.is_in(ids.clone()))
+                .all(db)
+                .await?,
+        );
+        let projects = Entity::find().filter(Column::Id.is_in(ids)).all(db).await?;
+        for project in &projects {
+            let repos = by_project.remove(&project.id).unwrap_or_default();
+            project
+                .save_health(db, &config, distribution.as_ref(), &repos)
+                .await?;
+        }
+        Ok(projects.len())
+    }
+}
+
+fn repos_by_project(repos: Vec<repos::Model>) -> HashMap<i32, Vec<repos::Model>> {
+    let mut by_project: HashMap<i32, Vec<repos::Model>> = HashMap::new();
+    for repo in repos {
+        by_project.entry(repo.project_id).or_default().push(repo);
+    }
+    by_project
+}
+
 #[async_trait::async_trait]
 impl ActiveModelBehavior for ActiveModel {
     async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
@@ -20,48 +89,158 @@ impl ActiveModelBehavior for ActiveModel {
 
 // implement your read-oriented logic here
 impl Model {
+    /// Whether the repo `repo_id` belongs to this project.
+    ///
     /// # Errors
     ///
     /// DB Error.
-    #[allow(clippy::cast_precision_loss)]
-    pub async fn recalculate_health<C>(&self, db: &C) -> Result<f32, DbErr>
+    pub async fn has_repo<C>(&self, db: &C, repo_id: i32) -> Result<bool, DbErr>
     where
         C: ConnectionTrait,
     {
-        use sea_orm::EntityTrait;
+        let repo = crate::models::repos::Entity::find_by_id(repo_id)
+            .one(db)
+            .await?;
+        Ok(repo.is_some_and(|repo| repo.project_id == self.id))
+    }
+
+    /// How health is combined, `Average` if the stored one is unknown.
+    #[must_use]
+    pub fn strategy(&self) -> Strategy {
+        self.health_strategy.parse().unwrap_or_default()
+    }
 
-        // Fetch all repos for this project
-        let repos = crate::models::repos::Entity::find()
-            .filter(crate::models::_entities::repos::Column::ProjectId.eq(self.id))
+    /// Combine the health of the project's repos, scored with the current
+    /// [`crate::health::Config`], the way its [`Strategy`] says.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn recalculate_health<C>(&self, db: &C) -> Result<f32, DbErr>
+    where
+        C: ConnectionTrait + TransactionTrait,
+    {
+        let repos = repos::Entity::find()
+            .filter(repos::Column::ProjectId.eq(self.id))
             .all(db)
             .await?;
+        let config = health_configs::Entity::current(db).await?;
+        let distribution = health_distributions::Entity::current(db).await?;
+        self.save_health(db, &config, distribution.as_ref(), &repos)
+            .await
+    }
 
-        // Compute average health
-        let health = if repos.is_empty() {
-            100.0
-        } else {
-            let sum: f32 = repos
-                .iter()
-                .map(super::_entities::repos::Model::health)
-                .sum();
-            sum / repos.len() as f32
-        };
-
-        // Update self in DB
-        crate::models::projects::ActiveModel {
+    /// Store the health of this project with `repos`, all of its repos.
+    /// A change is recorded in the audit log along with it.
+    async fn save_health<C>(
+        &self,
+        db: &C,
+        config: &Config,
+        distribution: Option<&Distribution>,
+        repos: &[repos::Model],
+    ) -> Result<f32, DbErr>
+    where
+        C: ConnectionTrait + TransactionTrait,
+    {
+        let (health, used) =
+            self.strategy()
+                .aggregate(config, distribution, repos, self.primary_repo_id);
+        let txn = db.begin().await?;
+        let saved = ActiveModel {
             id: sea_orm::ActiveValue::Set(self.id),
             health: sea_orm::ActiveValue::Set(health),
+            health_strategy_used: sea_orm::ActiveValue::Set(Some(used.as_str().to_string())),
             ..Default::default()
         }
-        .update(db)
+        .update(&txn)
         .await?;
-
+        if let Some(changes) = audit_events::changes(Some(self), Some(&saved)) {
+            NewEvent {
+                target: Some(self.audit_target()),
+                changes: Some(changes),
+                ..NewEvent::new(audit_events::PROJECT_HEALTH_CHANGED)
+            }
+            .record(&txn)
+            .await?;
+        }
+        txn.commit().await?;
         Ok(health)
     }
 }
 
+impl Audited for Model {
+    fn audit_target(&self) -> String {
+        format!("project:{}", self.id)
+    }
+}
+
 // implement your write-oriented logic here
 impl ActiveModel {}
 
 // implement your custom finders, selectors oriented logic here
-impl Entity {}
+impl Entity {
+    /// Recalculate the health of the project `id`, if it still exists.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn recalculate_health_of<C>(db: &C, id: i32) -> Result<(), DbErr>
+    where
+        C: ConnectionTrait + TransactionTrait,
+    {
+        if let Some(project) = Self::find_by_id(id).one(db).await? {
+            project.recalculate_health(db).await?;
+        }
+        Ok(())
+    }
+
+    /// Recalculate the health of every project, after the way repos are
+    /// scored changed. The config and all repos are read in one pass, which
+    /// also rebuilds the [`Distribution`] percentiles are taken from, and at
+    /// most `concurrency` projects are saved at a time.
+    ///
+    /// # Errors
+    ///
+    /// DB Error, after the projects already being saved are done.
+    pub async fn recalculate_all_health(
+        db: &DatabaseConnection,
+        concurrency: usize,
+    ) -> Result<usize, DbErr> {
+        let config = Arc::new(health_configs::Entity::current(db).await?);
+        let repos = repos::Entity::find().all(db).await?;
+        let distribution = Arc::new(Distribution::of(&repos));
+        health_distributions::Entity::replace(db, &distribution).await?;
+        let mut by_project = repos_by_project(repos);
+        let projects = Self::find().all(db).await?;
+        let count = projects.len();
+
+        let mut saving = JoinSet::new();
+        let mut failed = None;
+        for project in projects {
+            if saving.len() >= concurrency.max(1) {
+                if let Some(Err(err)) = saving.join_next().await.map(joined) {
+                    failed = Some(err);
+                    break;
+                }
+            }
+            let repos = by_project.remove(&project.id).unwrap_or_default();
+            let (db, config, distribution) =
+                (db.clone(), Arc::clone(&config), Arc::clone(&distribution));
+            saving.spawn(async move {
+                project
+                    .save_health(&db, &config, Some(&distribution), &repos)
+                    .await
+            });
+        }
+        while let Some(res) = saving.join_next().await {
+            if let Err(err) = joined(res) {
+                failed.get_or_insert(err);
+            }
+        }
+        failed.map_or(Ok(count), Err)
+    }
+}
+
+fn joined(res: Result<Result<f32, DbErr>, tokio::task::JoinError>) -> Result<f32, DbErr> {
+    res.map_err(|err| DbErr::Custom(err.to_string()))?
+}
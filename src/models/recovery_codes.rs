@@ -0,0 +1,92 @@
+pub use super::_entities::recovery_codes::{ActiveModel, Column, Entity, Model};
+use chrono::Utc;
+use loco_rs::prelude::Set;
+use sea_orm::{entity::prelude::*, IntoActiveModel};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+pub type RecoveryCodes = Entity;
+
+/// Codes handed out at a time.
+pub const COUNT: usize = 10;
+
+/// Codes are compared without case or separators, as people type them.
+fn hash(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    hex::encode(Sha256::digest(normalized.as_bytes()))
+}
+
+fn new_code() -> String {
+    let hex = Uuid::new_v4().simple().to_string();
+    format!("{}-{}", &hex[..5], &hex[5..10])
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+impl Entity {
+    /// New recovery codes for `user_id`, the old ones stop working.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn regenerate<C>(db: &C, user_id: i32) -> Result<Vec<String>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Self::delete_many()
+            .filter(Column::UserId.eq(user_id))
+            .exec(db)
+            .await?;
+        let codes: Vec<String> = (0..COUNT).map(|_| new_code()).collect();
+        Self::insert_many(codes.iter().map(|code| ActiveModel {
+            user_id: Set(user_id),
+            code_hash: Set(hash(code)),
+            ..Default::default()
+        }))
+        .exec(db)
+        .await?;
+        Ok(codes)
+    }
+
+    /// Use up `code` of `user_id`, `false` when it is unknown or used.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn redeem<C>(db: &C, user_id: i32, code: &str) -> Result<bool, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let Some(found) = Self::find()
+            .filter(Column::UserId.eq(user_id))
+            .filter(Column::CodeHash.eq(hash(code)))
+            .filter(Column::UsedAt.is_null())
+            .one(db)
+            .await?
+        else {
+            return Ok(false);
+        };
+        let mut found = found.into_active_model();
+        found.used_at = Set(Some(Utc::now().into()));
+        found.update(db).await?;
+        Ok(true)
+    }
+}
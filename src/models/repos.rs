@@ -1,13 +1,44 @@
-pub use super::_entities::repos::{ActiveModel, Entity, Model};
+pub use super::_entities::repos::{ActiveModel, Column, Entity, Model};
 use chrono::Utc;
 use loco_rs::prelude::Set;
-use octocrab::{models::Repository, params::State, Octocrab};
 use sea_orm::prelude::*;
-use sea_orm::TryIntoModel;
+use sea_orm::sea_query::{Expr, Func};
+use sea_orm::{IntoActiveModel, QueryOrder, SqlErr, TransactionTrait, TryIntoModel};
+use std::collections::BTreeMap;
+
+use crate::forges::{self, Forge, GitHub, ImportFilter, LocalGit, RepoSource, RepoStats};
 
 pub type Repos = Entity;
 
-use crate::models::projects::{ActiveModel as ProjectActiveModel, Model as ProjectModel};
+/// What importing the repos of an account did.
+#[derive(Debug, Default)]
+pub struct AccountImport {
+    /// The project the repos were added to, `None` when nothing matched.
+    pub project_id: Option<i32>,
+    pub imported: Vec<Model>,
+    /// `owner/name` of matching repos that were already tracked.
+    pub skipped: Vec<String>,
+    /// `owner/name` and error of matching repos that could not be fetched.
+    pub failed: Vec<(String, String)>,
+}
+
+/// Duplicate rows of one repo that were merged into the row kept.
+#[derive(Debug)]
+pub struct Merge {
+    pub kept: Model,
+    /// Ids of the repo rows that were deleted.
+    pub removed: Vec<i32>,
+    /// Ids of the projects created for the deleted rows and left empty.
+    pub removed_projects: Vec<i32>,
+}
+
+use crate::models::{
+    _entities::activity_events,
+    audit_events::Audited,
+    project_members::{self, Role},
+    projects::{self, ActiveModel as ProjectActiveModel, Model as ProjectModel, StaleHealth},
+    sync_runs,
+};
 
 #[async_trait::async_trait]
 impl ActiveModelBehavior for ActiveModel {
@@ -39,18 +70,6 @@ impl ActiveModelBehavior for ActiveModel {
             self.project_id = Set(project.id);
         }
 
-        // recalculate project health if project_id exists
-        if let sea_orm::ActiveValue::Set(project_id) | sea_orm::ActiveValue::Unchanged(project_id) =
-            self.project_id
-        {
-            if let Some(project) = crate::models::projects::Entity::find_by_id(project_id)
-                .one(db)
-                .await?
-            {
-                let _ = project.recalculate_health(db).await;
-            }
-        }
-
         Ok(self)
     }
 }
@@ -65,110 +84,528 @@ impl Entity {
         repo_name: &str,
         db: &DbConn,
     ) -> Result<Model, Box<dyn std::error::Error>> {
-        // Try loading GitHub token (optional)
-        let token = std::env::var("GITHUB_TOKEN").ok();
-
-        let octocrab = if let Some(token) = token {
-            println!("Using GitHub API token.");
-            Octocrab::builder().personal_token(token).build()?
-        } else {
-            println!(
-                "No GITHUB_TOKEN found. Using unauthenticated GitHub API (rate limits apply)."
-            );
-            Octocrab::builder().build()?
+        Ok(Self::fetch(&GitHub::from_env()?, owner, repo_name, db).await?)
+    }
+
+    /// Fetch repository from any forge and persist in DB
+    /// # Errors
+    ///
+    /// Any errors in the fetch from the forge or when saving.
+    pub async fn fetch(
+        source: &dyn RepoSource,
+        owner: &str,
+        repo_name: &str,
+        db: &DbConn,
+    ) -> forges::Result<Model> {
+        Self::fetch_into(source, owner, repo_name, None, None, db).await
+    }
+
+    /// Fetch repository from any forge and persist it under `project_id`,
+    /// or under a new project named after the repo when `None`.
+    ///
+    /// `importer` becomes the owner of the project when it was created for
+    /// this repo, never of the project of a repo that was already tracked.
+    ///
+    /// A repo that is already tracked under its stable forge id is updated
+    /// in place, also when it was renamed or transferred since. Every attempt
+    /// is recorded in `sync_runs`, see [`sync_runs::Entity::record`].
+    ///
+    /// The health of the projects involved is recalculated once the repo
+    /// is saved.
+    /// # Errors
+    ///
+    /// Any errors in the fetch from the forge or when saving.
+    pub async fn fetch_into(
+        source: &dyn RepoSource,
+        owner: &str,
+        repo_name: &str,
+        project_id: Option<i32>,
+        importer: Option<i32>,
+        db: &DbConn,
+    ) -> forges::Result<Model> {
+        let mut stale = StaleHealth::default();
+        let result = Self::fetch_and_record(
+            source, owner, repo_name, project_id, importer, &mut stale, db,
+        )
+        .await;
+        stale.recalculate(db).await?;
+        result
+    }
+
+    /// [`Self::fetch_into`], leaving the projects to recalculate in `stale`.
+    async fn fetch_and_record(
+        source: &dyn RepoSource,
+        owner: &str,
+        repo_name: &str,
+        project_id: Option<i32>,
+        importer: Option<i32>,
+        stale: &mut StaleHealth,
+        db: &DbConn,
+    ) -> forges::Result<Model> {
+        let started_at = Utc::now();
+        let calls_before = source.api_calls();
+
+        let result = match source.fetch(owner, repo_name).await {
+            Ok(stats) => Self::save_stats(source, stats, project_id, importer, stale, db).await,
+            Err(err) => Err(err),
+        };
+
+        let attempt = sync_runs::Attempt {
+            forge: source.forge(),
+            host: source.host(),
+            owner: owner.to_string(),
+            name: repo_name.to_string(),
+            started_at,
+            api_calls: source.api_calls().saturating_sub(calls_before),
+            repo_id: None,
+            by_id: false,
         };
+        sync_runs::Entity::record(db, attempt, result.as_ref()).await?;
+        result
+    }
+
+    /// Store fetched `stats`, updating the repo that is already tracked
+    /// under the same forge id or, failing that, under the same name.
+    async fn save_stats(
+        source: &dyn RepoSource,
+        stats: RepoStats,
+        project_id: Option<i32>,
+        importer: Option<i32>,
+        stale: &mut StaleHealth,
+        db: &DbConn,
+    ) -> forges::Result<Model> {
+        let forge = source.forge();
+        let host = source.host();
+
+        let find =
+            || Self::find_tracked(db, forge, &host, stats.forge_id, &stats.owner, &stats.name);
+        if let Some(tracked) = find().await? {
+            return Self::update_stats(source, tracked, stats, project_id, importer, stale, db)
+                .await;
+        }
 
-        // Fetch GitHub repository
-        let gh_repo: Repository = octocrab.repos(owner, repo_name).get().await?;
+        let mut model: ActiveModel = Default::default();
+        Self::apply_stats(&mut model, source, stats.clone());
+        if let Some(project_id) = project_id {
+            model.project_id = Set(project_id);
+        }
 
-        // Fetch counts
-        let prs_count = i32::try_from(
-            octocrab
-                .pulls(owner, repo_name)
-                .list()
-                .state(State::Open)
-                .send()
+        // a concurrent fetch may have inserted the repo in the meantime, the
+        // rollback drops the project `before_save` created for this row
+        let txn = db.begin().await?;
+        match model.insert(&txn).await {
+            Ok(model) => {
+                if let (None, Some(user_id)) = (project_id, importer) {
+                    project_members::Entity::grant(&txn, model.project_id, user_id, Role::Owner)
+                        .await?;
+                }
+                txn.commit().await?;
+                stale.add(model.project_id);
+                return Ok(model);
+            }
+            Err(err) if matches!(err.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+                txn.rollback().await?;
+            }
+            Err(err) => return Err(err.into()),
+        }
+        let tracked = find()
+            .await?
+            .ok_or_else(|| DbErr::RecordNotFound(format!("{}/{}", stats.owner, stats.name)))?;
+        Self::update_stats(source, tracked, stats, project_id, importer, stale, db).await
+    }
+
+    /// Update the `tracked` row, moving it to `project_id` only when
+    /// `importer` may also change the project it is in now.
+    async fn update_stats(
+        source: &dyn RepoSource,
+        tracked: Model,
+        stats: RepoStats,
+        project_id: Option<i32>,
+        importer: Option<i32>,
+        stale: &mut StaleHealth,
+        db: &DbConn,
+    ) -> forges::Result<Model> {
+        if let (Some(project_id), Some(user_id)) = (project_id, importer) {
+            if project_id != tracked.project_id {
+                let role =
+                    project_members::Entity::role_of(db, tracked.project_id, user_id).await?;
+                if role < Some(Role::Maintainer) {
+                    return Err(forges::Error::TrackedElsewhere {
+                        repo: format!("{}/{}", tracked.owner, tracked.name),
+                        project_id: tracked.project_id,
+                    });
+                }
+            }
+        }
+        stale.add(tracked.project_id);
+        let mut model = tracked.into_active_model();
+        Self::apply_stats(&mut model, source, stats);
+        if let Some(project_id) = project_id {
+            model.project_id = Set(project_id);
+        }
+        let model = model.update(db).await?;
+        stale.add(model.project_id);
+        Ok(model)
+    }
+
+    /// The tracked row of the repo `owner/name`, ignoring case.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn find_by_ref<C>(
+        db: &C,
+        forge: Forge,
+        host: &str,
+        owner: &str,
+        name: &str,
+    ) -> Result<Option<Model>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Self::find_tracked(db, forge, host, None, owner, name).await
+    }
+
+    /// The tracked row of a repo: by its stable forge id, then by owner and
+    /// name ignoring case, like forges do.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn find_tracked<C>(
+        db: &C,
+        forge: Forge,
+        host: &str,
+        forge_id: Option<i64>,
+        owner: &str,
+        name: &str,
+    ) -> Result<Option<Model>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let on_forge = || {
+            Self::find()
+                .filter(Column::Forge.eq(forge.as_str()))
+                .filter(Column::Host.eq(host))
+        };
+        if let Some(forge_id) = forge_id {
+            if let Some(repo) = on_forge()
+                .filter(Column::ForgeId.eq(forge_id))
+                .one(db)
                 .await?
-                .items
-                .len(),
-        );
-
-        let contributors_count = i32::try_from(
-            octocrab
-                .repos(owner, repo_name)
-                .list_contributors()
-                .send()
+            {
+                return Ok(Some(repo));
+            }
+        }
+        on_forge()
+            .filter(Expr::expr(Func::lower(Expr::col(Column::Owner))).eq(owner.to_lowercase()))
+            .filter(Expr::expr(Func::lower(Expr::col(Column::Name))).eq(name.to_lowercase()))
+            .order_by_desc(Column::LastFetch)
+            .one(db)
+            .await
+    }
+
+    /// Merge rows that track the same repo: the same forge id, or the same
+    /// owner and name ignoring case. The row fetched last is kept and takes
+    /// over the activity and sync history of the others. Projects that were
+    /// created for a deleted row and are left empty are deleted as well.
+    /// # Errors
+    ///
+    /// DB errors, in which case nothing is merged.
+    pub async fn merge_duplicates(db: &DbConn) -> Result<Vec<Merge>, DbErr> {
+        let txn = db.begin().await?;
+        let mut merges = Vec::new();
+
+        for by_forge_id in [true, false] {
+            let repos = Self::find()
+                .order_by_desc(Column::LastFetch)
+                .order_by_desc(Column::Id)
+                .all(&txn)
+                .await?;
+
+            let mut groups: BTreeMap<(String, String, String), Vec<Model>> = BTreeMap::new();
+            for repo in repos {
+                let key = if by_forge_id {
+                    let Some(forge_id) = repo.forge_id else {
+                        continue;
+                    };
+                    format!("#{forge_id}")
+                } else {
+                    format!("{}/{}", repo.owner, repo.name).to_lowercase()
+                };
+                groups
+                    .entry((repo.forge.clone(), repo.host.clone(), key))
+                    .or_default()
+                    .push(repo);
+            }
+
+            for mut group in groups.into_values().filter(|g| g.len() > 1) {
+                let extra = group.split_off(1);
+                let kept = group.remove(0);
+                merges.push(Self::merge_into(&txn, kept, extra).await?);
+            }
+        }
+
+        txn.commit().await?;
+        Ok(merges)
+    }
+
+    async fn merge_into<C>(db: &C, kept: Model, extra: Vec<Model>) -> Result<Merge, DbErr>
+    where
+        C: ConnectionTrait + TransactionTrait,
+    {
+        let removed: Vec<i32> = extra.iter().map(|r| r.id).collect();
+
+        activity_events::Entity::update_many()
+            .col_expr(activity_events::Column::RepoId, Expr::value(kept.id))
+            .filter(activity_events::Column::RepoId.is_in(removed.clone()))
+            .exec(db)
+            .await?;
+        sync_runs::Entity::update_many()
+            .col_expr(sync_runs::Column::RepoId, Expr::value(kept.id))
+            .filter(sync_runs::Column::RepoId.is_in(removed.clone()))
+            .exec(db)
+            .await?;
+        Self::delete_many()
+            .filter(Column::Id.is_in(removed.clone()))
+            .exec(db)
+            .await?;
+
+        // keep the stable id when only a deleted row had it
+        let kept = match extra.iter().find_map(|r| r.forge_id) {
+            Some(forge_id) if kept.forge_id.is_none() => {
+                let mut model = kept.into_active_model();
+                model.forge_id = Set(Some(forge_id));
+                model.update(db).await?
+            }
+            _ => kept,
+        };
+
+        let mut removed_projects = Vec::new();
+        for repo in &extra {
+            if repo.project_id == kept.project_id || removed_projects.contains(&repo.project_id) {
+                continue;
+            }
+            let Some(project) = projects::Entity::find_by_id(repo.project_id)
+                .one(db)
                 .await?
-                .items
-                .len(),
-        );
-
-        let commits_last_30d = i32::try_from(
-            octocrab
-                .repos(owner, repo_name)
-                .list_commits()
-                .since(Utc::now() - chrono::Duration::days(30))
-                .per_page(100)
-                .send()
+            else {
+                continue;
+            };
+            let created_for_repo = project.owner.eq_ignore_ascii_case(&repo.owner)
+                && project.name.eq_ignore_ascii_case(&repo.name);
+            let empty = Self::find()
+                .filter(Column::ProjectId.eq(project.id))
+                .one(db)
                 .await?
-                .items
-                .len(),
-        );
-
-        // Build model
-        let model =
-            Self::build_active_model(gh_repo, prs_count?, contributors_count?, commits_last_30d?);
-
-        // Persist and return
-        Ok(model.save(db).await?.try_into()?)
-    }
-
-    /// Map GitHub repo + stats into `ActiveModel`
-    fn build_active_model(
-        gh_repo: Repository,
-        prs: i32,
-        contributors: i32,
-        commits_last_30d: i32,
-    ) -> ActiveModel {
-        ActiveModel {
-            name: Set(gh_repo.name),
-            owner: Set(gh_repo.owner.map(|o| o.login).unwrap_or_default()),
-            stars: Set(gh_repo.stargazers_count.unwrap_or(0).cast_signed()),
-            forks: Set(gh_repo.forks_count.unwrap_or(0).cast_signed()),
-            issues: Set(gh_repo.open_issues_count.unwrap_or(0).cast_signed()),
-            watchers: Set(gh_repo.watchers_count.unwrap_or(0).cast_signed()),
-            prs: Set(prs),
-            contributors: Set(contributors),
-            commits_last_30d: Set(commits_last_30d),
-            license: gh_repo.license.map_or(Set(None), |l| Set(Some(l.name))),
-            last_fetch: Set(Utc::now().naive_utc()),
+                .is_none();
+            if created_for_repo && empty {
+                removed_projects.push(project.id);
+                project.delete(db).await?;
+            }
+        }
+
+        if let Some(project) = projects::Entity::find_by_id(kept.project_id)
+            .one(db)
+            .await?
+        {
+            project.recalculate_health(db).await?;
+        }
+
+        Ok(Merge {
+            kept,
+            removed,
+            removed_projects,
+        })
+    }
+
+    /// Fetch all repos of a GitHub user or organization that match `filter`
+    /// and add them to `project_id`, or to a new project named after the
+    /// account when `None`.
+    ///
+    /// Repos that are already tracked, under their forge id or their name,
+    /// are skipped. A repo that fails to fetch is reported and does not stop
+    /// the import. The project's health is recalculated once, after all repos
+    /// are saved. `importer` becomes the owner of the project when the import
+    /// created it, and must maintain the project of any repo it would move.
+    /// # Errors
+    ///
+    /// When the account can not be listed, or on DB errors.
+    pub async fn import_account(
+        github: &GitHub,
+        account: &str,
+        filter: &ImportFilter,
+        project_id: Option<i32>,
+        importer: Option<i32>,
+        db: &DbConn,
+    ) -> forges::Result<AccountImport> {
+        let listed: Vec<_> = github
+            .list_repos(account)
+            .await?
+            .into_iter()
+            .filter(|repo| filter.matches(repo))
+            .collect();
+        if listed.is_empty() {
+            return Ok(AccountImport::default());
+        }
+
+        let project_id = match project_id {
+            Some(project_id) => project_id,
+            None => {
+                let txn = db.begin().await?;
+                let project = ProjectActiveModel {
+                    name: Set(account.to_string()),
+                    owner: Set(account.to_string()),
+                    health: Set(100.),
+                    last_fetch: Set(Utc::now().naive_utc()),
+                    ..Default::default()
+                }
+                .insert(&txn)
+                .await?;
+                if let Some(user_id) = importer {
+                    project_members::Entity::grant(&txn, project.id, user_id, Role::Owner).await?;
+                }
+                txn.commit().await?;
+                project.id
+            }
+        };
+
+        let mut import = AccountImport {
+            project_id: Some(project_id),
             ..Default::default()
+        };
+        let mut stale = StaleHealth::default();
+        for repo in listed {
+            let full_name = format!("{}/{}", repo.owner, repo.name);
+            let tracked = Self::find_tracked(
+                db,
+                Forge::Github,
+                &github.host(),
+                repo.forge_id,
+                &repo.owner,
+                &repo.name,
+            )
+            .await?
+            .is_some();
+            if tracked {
+                import.skipped.push(full_name);
+                continue;
+            }
+            match Self::fetch_and_record(
+                github,
+                &repo.owner,
+                &repo.name,
+                Some(project_id),
+                importer,
+                &mut stale,
+                db,
+            )
+            .await
+            {
+                Ok(model) => import.imported.push(model),
+                Err(err) => {
+                    tracing::warn!(repo = full_name, error = %err, "import failed");
+                    import.failed.push((full_name, err.to_string()));
+                }
+            }
         }
+        stale.recalculate(db).await?;
+        Ok(import)
+    }
+
+    /// Map forge stats into `ActiveModel`
+    fn apply_stats(model: &mut ActiveModel, source: &dyn RepoSource, stats: RepoStats) {
+        model.forge = Set(source.forge().to_string());
+        model.host = Set(source.host());
+        model.local_path = Set(source.local_path());
+        model.forge_id = Set(stats.forge_id);
+        model.name = Set(stats.name);
+        model.owner = Set(stats.owner);
+        model.stars = Set(stats.stars);
+        model.forks = Set(stats.forks);
+        model.issues = Set(stats.issues);
+        model.watchers = Set(stats.watchers);
+        model.prs = Set(stats.prs);
+        model.contributors = Set(stats.contributors);
+        model.commits_last_30d = Set(stats.commits_last_30d);
+        model.releases = Set(stats.releases);
+        model.license = Set(stats.license);
+        model.archived = Set(stats.archived);
+        model.last_fetch = Set(Utc::now().naive_utc());
     }
 }
 
 impl Model {
-    #[allow(clippy::cast_possible_truncation)]
-    #[must_use]
-    pub fn health(&self) -> f32 {
-        // Convert all i32 fields to f64 once
-        let commits = f64::from(self.commits_last_30d);
-        let contributors = f64::from(self.contributors);
-        let prs = f64::from(self.prs);
-        let stars = f64::from(self.stars);
-        let issues = f64::from(self.issues);
+    /// The source to fetch this repo again from: its forge, or the path it
+    /// was fetched from for local repos.
+    ///
+    /// # Errors
+    ///
+    /// When the forge is unknown or its client can not be built, or
+    /// [`forges::Error::LocalNeedsPath`] for a local repo tracked without
+    /// its path.
+    pub fn source(&self, settings: &forges::Settings) -> forges::Result<Box<dyn RepoSource>> {
+        let forge: Forge = self.forge.parse()?;
+        match (forge, &self.local_path) {
+            (Forge::Local, Some(path)) => Ok(Box::new(LocalGit::new(path))),
+            _ => settings.source(forge, Some(&self.host)),
+        }
+    }
+
+    /// Fetch this repo again and update it in place.
+    ///
+    /// Repos with a stable forge id are fetched by it, which follows renames
+    /// and transfers and tells a deleted repo apart from a failing forge.
+    /// The project's health is recalculated once the repo is saved.
+    /// # Errors
+    ///
+    /// Any errors in the fetch from the forge or when saving.
+    pub async fn refresh(&self, source: &dyn RepoSource, db: &DbConn) -> forges::Result<Self> {
+        let started_at = Utc::now();
+        let calls_before = source.api_calls();
+
+        let fetched = match self.forge_id {
+            Some(forge_id) => source.fetch_by_id(forge_id).await,
+            None => source.fetch(&self.owner, &self.name).await,
+        };
+        let result = match fetched {
+            Ok(stats) => {
+                let mut model = self.clone().into_active_model();
+                Entity::apply_stats(&mut model, source, stats);
+                model.update(db).await.map_err(Into::into)
+            }
+            Err(err) => Err(err),
+        };
 
-        // Compute normalized factors (clamped 0.0–1.0)
-        let activity = (commits / 30.0).min(1.0);
-        let community = (contributors / 10.0).min(1.0);
-        let adoption = (stars / 100.0).min(1.0);
-        let maintenance = (prs / 10.0 * (1.0 - (issues / 50.0).min(1.0))).min(1.0);
+        let attempt = sync_runs::Attempt {
+            forge: source.forge(),
+            host: source.host(),
+            owner: self.owner.clone(),
+            name: self.name.clone(),
+            started_at,
+            api_calls: source.api_calls().saturating_sub(calls_before),
+            repo_id: Some(self.id),
+            by_id: self.forge_id.is_some(),
+        };
+        sync_runs::Entity::record(db, attempt, result.as_ref()).await?;
+        if result.is_ok() {
+            projects::Entity::recalculate_health_of(db, self.project_id).await?;
+        }
+        result
+    }
 
-        // Weighted sum
-        let score = activity * 0.35 + community * 0.25 + adoption * 0.15 + maintenance * 0.25;
+    /// The health of this repo with the default scoring, see
+    /// [`crate::health::Config`] for the one an admin set.
+    #[must_use]
+    pub fn health(&self) -> f32 {
+        crate::health::Config::default().score(self)
+    }
+}
 
-        // Scale to 0–100 and cast to f32 at the very end
-        (score * 100.0).clamp(0.0, 100.0) as f32
+impl Audited for Model {
+    fn audit_target(&self) -> String {
+        format!("repo:{}", self.id)
     }
 }
+
 impl ActiveModel {}
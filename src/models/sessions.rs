@@ -0,0 +1,248 @@
+pub use super::_entities::sessions::{ActiveModel, Column, Entity, Model};
+use chrono::{Duration, Utc};
+use loco_rs::prelude::Set;
+use sea_orm::{entity::prelude::*, IntoActiveModel, QueryOrder};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+pub type Sessions = Entity;
+
+/// Refresh tokens start with this, to tell them from access tokens.
+pub const TOKEN_PREFIX: &str = "gcr_";
+
+/// How long sessions last, read from the `sessions` settings.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Settings {
+    /// Seconds a refresh token stays valid. Every refresh starts the period
+    /// again.
+    #[serde(default = "default_refresh_expiration")]
+    pub refresh_expiration: u64,
+}
+
+const fn default_refresh_expiration() -> u64 {
+    30 * 24 * 60 * 60
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            refresh_expiration: default_refresh_expiration(),
+        }
+    }
+}
+
+impl Settings {
+    fn expires_at(&self) -> DateTimeWithTimeZone {
+        let secs = i64::try_from(self.refresh_expiration).unwrap_or(i64::MAX);
+        (Utc::now() + Duration::seconds(secs)).into()
+    }
+}
+
+/// A session with the refresh token that continues it, the only time the
+/// token is known.
+#[derive(Debug)]
+pub struct Started {
+    pub session: Model,
+    pub refresh_token: String,
+}
+
+fn hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn new_token() -> String {
+    format!("{TOKEN_PREFIX}{}", Uuid::new_v4().simple())
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+impl Model {
+    /// End this session, its refresh token stops working.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn revoke<C>(self, db: &C) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let mut session = self.into_active_model();
+        session.revoked_at = Set(Some(Utc::now().into()));
+        session.update(db).await
+    }
+}
+
+impl Entity {
+    /// Start a session for `user_id`, signed in from `user_agent`.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn start<C>(
+        db: &C,
+        settings: &Settings,
+        user_id: i32,
+        user_agent: Option<&str>,
+    ) -> Result<Started, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let refresh_token = new_token();
+        let session = ActiveModel {
+            user_id: Set(user_id),
+            refresh_hash: Set(hash(&refresh_token)),
+            user_agent: Set(user_agent.map(ToString::to_string)),
+            expires_at: Set(settings.expires_at()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+        Ok(Started {
+            session,
+            refresh_token,
+        })
+    }
+
+    /// Trade `refresh_token` for a new one on the same session. `None` when
+    /// the token is unknown, expired or revoked.
+    ///
+    /// A token that was already traded in ends its session: either it leaked
+    /// or the client lost the new one, and both need a new sign in.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn refresh<C>(
+        db: &C,
+        settings: &Settings,
+        refresh_token: &str,
+    ) -> Result<Option<Started>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let hashed = hash(refresh_token);
+        let Some(session) = Self::find()
+            .filter(Column::RefreshHash.eq(&hashed))
+            .one(db)
+            .await?
+        else {
+            if let Some(reused) = Self::find()
+                .filter(Column::PreviousHash.eq(&hashed))
+                .filter(Column::RevokedAt.is_null())
+                .one(db)
+                .await?
+            {
+                tracing::warn!(
+                    session_id = reused.id,
+                    user_id = reused.user_id,
+                    "refresh token reused, revoking session"
+                );
+                reused.revoke(db).await?;
+            }
+            return Ok(None);
+        };
+        if session.revoked_at.is_some() || session.expires_at < Utc::now() {
+            return Ok(None);
+        }
+
+        let refresh_token = new_token();
+        let mut session = session.into_active_model();
+        session.previous_hash = Set(Some(hashed));
+        session.refresh_hash = Set(hash(&refresh_token));
+        session.expires_at = Set(settings.expires_at());
+        session.last_used_at = Set(Some(Utc::now().into()));
+        Ok(Some(Started {
+            session: session.update(db).await?,
+            refresh_token,
+        }))
+    }
+
+    /// Session `id` of `user_id` when it is neither revoked nor expired.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn live<C>(db: &C, id: i32, user_id: i32) -> Result<Option<Model>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Self::find_by_id(id)
+            .filter(Column::UserId.eq(user_id))
+            .filter(Column::RevokedAt.is_null())
+            .filter(Column::ExpiresAt.gt(Utc::now()))
+            .one(db)
+            .await
+    }
+
+    /// Sessions of `user_id` that can still be refreshed, newest first.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn active_for_user<C>(db: &C, user_id: i32) -> Result<Vec<Model>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Self::find()
+            .filter(Column::UserId.eq(user_id))
+            .filter(Column::RevokedAt.is_null())
+            .filter(Column::ExpiresAt.gt(Utc::now()))
+            .order_by_desc(Column::CreatedAt)
+            .order_by_desc(Column::Id)
+            .all(db)
+            .await
+    }
+
+    /// End every session of `user_id`, returning how many were live.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn revoke_all<C>(db: &C, user_id: i32) -> Result<u64, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let res = Self::update_many()
+            .col_expr(Column::RevokedAt, Expr::value(Utc::now()))
+            .filter(Column::UserId.eq(user_id))
+            .filter(Column::RevokedAt.is_null())
+            .exec(db)
+            .await?;
+        Ok(res.rows_affected)
+    }
+
+    /// End every session of `user_id` except `keep`, the one the request
+    /// came from, returning how many were live.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn revoke_others<C>(db: &C, user_id: i32, keep: Option<i32>) -> Result<u64, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let mut update = Self::update_many()
+            .col_expr(Column::RevokedAt, Expr::value(Utc::now()))
+            .filter(Column::UserId.eq(user_id))
+            .filter(Column::RevokedAt.is_null());
+        if let Some(keep) = keep {
+            update = update.filter(Column::Id.ne(keep));
+        }
+        Ok(update.exec(db).await?.rows_affected)
+    }
+}
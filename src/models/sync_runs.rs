@@ -0,0 +1,193 @@
+pub use super::_entities::sync_runs::{ActiveModel, Column, Entity, Model};
+use chrono::{DateTime, Utc};
+use loco_rs::prelude::Set;
+use sea_orm::{entity::prelude::*, IntoActiveModel, QueryOrder, QuerySelect};
+
+use super::_entities::repos;
+use crate::forges::{self, Forge};
+
+pub type SyncRuns = Entity;
+
+/// Consecutive failed syncs after which a repo is marked stale, or not found
+/// when the forge keeps answering 404 (renamed or deleted upstream).
+pub const FAILURES_BEFORE_MARKING: i32 = 3;
+
+/// How a sync run ended, stored in `sync_runs.outcome`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Ok,
+    Failed,
+    NotFound,
+}
+
+impl Outcome {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Failed => "failed",
+            Self::NotFound => "not_found",
+        }
+    }
+}
+
+/// Whether a repo still syncs, stored in `repos.sync_status`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncStatus {
+    Ok,
+    /// Fetching failed repeatedly, the stats are out of date.
+    Stale,
+    /// The forge repeatedly did not know the repo.
+    NotFound,
+    /// The forge no longer knows the repo by its stable id, it was deleted
+    /// upstream.
+    Gone,
+}
+
+impl SyncStatus {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Stale => "stale",
+            Self::NotFound => "not_found",
+            Self::Gone => "gone",
+        }
+    }
+}
+
+/// A fetch of `owner/name` from a forge, to be recorded.
+#[derive(Debug, Clone)]
+pub struct Attempt {
+    pub forge: Forge,
+    pub host: String,
+    pub owner: String,
+    pub name: String,
+    pub started_at: DateTime<Utc>,
+    pub api_calls: u32,
+    /// The tracked repo that was refreshed, looked up by name when `None`.
+    pub repo_id: Option<i32>,
+    /// Whether the repo was fetched by its stable id, so that a 404 means
+    /// it is gone.
+    pub by_id: bool,
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {}
+
+// implement your write-oriented logic here
+impl ActiveModel {}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {
+    /// Record how `attempt` went.
+    ///
+    /// A failure counts against the tracked repo, which is marked
+    /// [`SyncStatus::Stale`] or [`SyncStatus::NotFound`] after
+    /// [`FAILURES_BEFORE_MARKING`] failures in a row, or right away
+    /// [`SyncStatus::Gone`] when its stable id is not found. A success resets
+    /// it. A refused move, [`forges::Error::TrackedElsewhere`], leaves the
+    /// repo as it is.
+    ///
+    /// # Errors
+    ///
+    /// DB errors.
+    pub async fn record<C>(
+        db: &C,
+        attempt: Attempt,
+        result: Result<&repos::Model, &forges::Error>,
+    ) -> Result<Model, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let (outcome, error) = match result {
+            Ok(_) => (Outcome::Ok, None),
+            Err(forges::Error::NotFound(_)) => (Outcome::NotFound, None),
+            Err(err) => (Outcome::Failed, Some(err.to_string())),
+        };
+
+        let repo = match (result, attempt.repo_id) {
+            (Ok(repo), _) => Some(repo.clone()),
+            (Err(_), Some(repo_id)) => repos::Entity::find_by_id(repo_id).one(db).await?,
+            (Err(_), None) => {
+                repos::Entity::find_by_ref(
+                    db,
+                    attempt.forge,
+                    &attempt.host,
+                    &attempt.owner,
+                    &attempt.name,
+                )
+                .await?
+            }
+        };
+
+        let refused = matches!(result, Err(forges::Error::TrackedElsewhere { .. }));
+        if let Some(repo) = repo.as_ref().filter(|_| !refused) {
+            let failed_syncs = match outcome {
+                Outcome::Ok => 0,
+                Outcome::Failed | Outcome::NotFound => repo.failed_syncs.saturating_add(1),
+            };
+            let status = match outcome {
+                Outcome::NotFound if attempt.by_id => SyncStatus::Gone,
+                _ if failed_syncs < FAILURES_BEFORE_MARKING => SyncStatus::Ok,
+                Outcome::NotFound => SyncStatus::NotFound,
+                Outcome::Ok | Outcome::Failed => SyncStatus::Stale,
+            };
+            if repo.failed_syncs != failed_syncs || repo.sync_status != status.as_str() {
+                let mut item = repo.clone().into_active_model();
+                item.failed_syncs = Set(failed_syncs);
+                item.sync_status = Set(status.as_str().to_string());
+                item.update(db).await?;
+            }
+        }
+
+        ActiveModel {
+            forge: Set(attempt.forge.to_string()),
+            host: Set(attempt.host),
+            owner: Set(attempt.owner),
+            name: Set(attempt.name),
+            started_at: Set(attempt.started_at.into()),
+            finished_at: Set(Utc::now().into()),
+            api_calls: Set(i32::try_from(attempt.api_calls).unwrap_or(i32::MAX)),
+            outcome: Set(outcome.as_str().to_string()),
+            error: Set(error),
+            repo_id: Set(repo.map(|r| r.id)),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+    }
+
+    /// The latest runs that did not succeed, newest first.
+    ///
+    /// # Errors
+    ///
+    /// DB errors.
+    pub async fn recent_failures<C>(db: &C, limit: u64) -> Result<Vec<Model>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Self::find()
+            .filter(Column::Outcome.ne(Outcome::Ok.as_str()))
+            .order_by_desc(Column::FinishedAt)
+            .limit(limit)
+            .all(db)
+            .await
+    }
+}
@@ -0,0 +1,167 @@
+pub use super::_entities::totp_credentials::{ActiveModel, Column, Entity, Model};
+use chrono::Utc;
+use loco_rs::prelude::Set;
+use rand::{rngs::OsRng, RngCore};
+use sea_orm::{entity::prelude::*, IntoActiveModel};
+
+use super::_entities::recovery_codes;
+use crate::totp;
+
+pub type TotpCredentials = Entity;
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+fn new_secret() -> [u8; totp::SECRET_LEN] {
+    let mut secret = [0; totp::SECRET_LEN];
+    OsRng.fill_bytes(&mut secret);
+    secret
+}
+
+impl Model {
+    #[must_use]
+    pub fn secret_bytes(&self) -> Vec<u8> {
+        totp::base32_decode(&self.secret).unwrap_or_default()
+    }
+
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.confirmed_at.is_some()
+    }
+
+    /// Whether `code` is a code of this secret now. Each code is accepted
+    /// once.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn check<C>(&self, db: &C, code: &str) -> Result<bool, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let now = u64::try_from(Utc::now().timestamp()).unwrap_or_default();
+        self.check_at(db, code, now).await
+    }
+
+    /// Whether `code` is a code of this secret at `unix_secs`, like
+    /// [`Self::check`].
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn check_at<C>(&self, db: &C, code: &str, unix_secs: u64) -> Result<bool, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let used = self
+            .last_used_step
+            .and_then(|step| u64::try_from(step).ok());
+        let Some(step) = totp::verify(&self.secret_bytes(), code, unix_secs, used) else {
+            return Ok(false);
+        };
+        let mut credential = self.clone().into_active_model();
+        credential.last_used_step = Set(i64::try_from(step).ok());
+        credential.update(db).await?;
+        Ok(true)
+    }
+
+    /// Turn two-factor authentication on with this secret.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn confirm<C>(self, db: &C) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let mut credential = self.into_active_model();
+        credential.confirmed_at = Set(Some(Utc::now().into()));
+        credential.update(db).await
+    }
+}
+
+impl Entity {
+    /// The secret of `user_id`, confirmed or not.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn for_user<C>(db: &C, user_id: i32) -> Result<Option<Model>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Self::find()
+            .filter(Column::UserId.eq(user_id))
+            .one(db)
+            .await
+    }
+
+    /// The secret of `user_id` when two-factor authentication is on.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn enabled_for<C>(db: &C, user_id: i32) -> Result<Option<Model>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Self::for_user(db, user_id).await?.filter(Model::is_enabled))
+    }
+
+    /// A new, unconfirmed secret for `user_id`, replacing an unconfirmed one.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn enroll<C>(db: &C, user_id: i32) -> Result<Model, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Self::delete_many()
+            .filter(Column::UserId.eq(user_id))
+            .filter(Column::ConfirmedAt.is_null())
+            .exec(db)
+            .await?;
+        ActiveModel {
+            user_id: Set(user_id),
+            secret: Set(totp::base32_encode(&new_secret())),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+    }
+
+    /// Turn two-factor authentication off for `user_id`, forgetting the
+    /// secret and recovery codes.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn disable<C>(db: &C, user_id: i32) -> Result<(), DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Self::delete_many()
+            .filter(Column::UserId.eq(user_id))
+            .exec(db)
+            .await?;
+        recovery_codes::Entity::delete_many()
+            .filter(recovery_codes::Column::UserId.eq(user_id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+}
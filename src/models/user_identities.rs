@@ -0,0 +1,183 @@
+pub use super::_entities::user_identities::{ActiveModel, Column, Entity, Model};
+use loco_rs::{hash, model::ModelError, model::ModelResult, prelude::Set};
+use sea_orm::{entity::prelude::*, IntoActiveModel};
+
+use super::users::{self, RegisterParams};
+use crate::{forges::Forge, oauth::Account};
+
+pub type UserIdentities = Entity;
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+impl Entity {
+    /// The link to `account` on `provider`, if any user has it.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn find_account<C>(
+        db: &C,
+        provider: Forge,
+        account_id: &str,
+    ) -> Result<Option<Model>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Self::find()
+            .filter(Column::Provider.eq(provider.as_str()))
+            .filter(Column::ProviderUserId.eq(account_id))
+            .one(db)
+            .await
+    }
+
+    /// All accounts linked to `user_id`.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn for_user<C>(db: &C, user_id: i32) -> Result<Vec<Model>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Self::find()
+            .filter(Column::UserId.eq(user_id))
+            .all(db)
+            .await
+    }
+
+    /// Link `account` on `provider` to `user_id`, replacing the account of
+    /// that provider the user had linked before.
+    ///
+    /// # Errors
+    ///
+    /// [`ModelError::EntityAlreadyExists`] when another user has the account,
+    /// or DB Error.
+    pub async fn link<C>(
+        db: &C,
+        user_id: i32,
+        provider: Forge,
+        account: &Account,
+    ) -> ModelResult<Model>
+    where
+        C: ConnectionTrait,
+    {
+        let existing = match Self::find_account(db, provider, &account.id).await? {
+            Some(identity) if identity.user_id != user_id => {
+                return Err(ModelError::EntityAlreadyExists)
+            }
+            Some(identity) => Some(identity),
+            None => {
+                Self::find()
+                    .filter(Column::Provider.eq(provider.as_str()))
+                    .filter(Column::UserId.eq(user_id))
+                    .one(db)
+                    .await?
+            }
+        };
+        let identity = match existing {
+            Some(identity) => {
+                let mut identity = identity.into_active_model();
+                identity.provider_user_id = Set(account.id.clone());
+                identity.login = Set(account.login.clone());
+                identity.update(db).await?
+            }
+            None => {
+                ActiveModel {
+                    user_id: Set(user_id),
+                    provider: Set(provider.as_str().to_string()),
+                    provider_user_id: Set(account.id.clone()),
+                    login: Set(account.login.clone()),
+                    ..Default::default()
+                }
+                .insert(db)
+                .await?
+            }
+        };
+        Ok(identity)
+    }
+
+    /// Remove the `provider` account linked to `user_id`, `false` when there
+    /// was none.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn unlink<C>(db: &C, user_id: i32, provider: Forge) -> Result<bool, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let res = Self::delete_many()
+            .filter(Column::Provider.eq(provider.as_str()))
+            .filter(Column::UserId.eq(user_id))
+            .exec(db)
+            .await?;
+        Ok(res.rows_affected > 0)
+    }
+
+    /// The user signing in with `account` on `provider`.
+    ///
+    /// That is the user the account is linked to, else the user with the
+    /// account's verified email, else a new, verified user. The account is
+    /// linked to the user in the last two cases. `None` when the account is
+    /// not linked and has no verified email.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn sign_in(
+        db: &DatabaseConnection,
+        provider: Forge,
+        account: &Account,
+    ) -> ModelResult<Option<users::Model>> {
+        if let Some(identity) = Self::find_account(db, provider, &account.id).await? {
+            // follow renames of the account
+            if identity.login != account.login {
+                let mut identity = identity.clone().into_active_model();
+                identity.login = Set(account.login.clone());
+                identity.update(db).await?;
+            }
+            let user = users::Entity::find_by_id(identity.user_id).one(db).await?;
+            return user.ok_or_else(|| ModelError::EntityNotFound).map(Some);
+        }
+
+        let Some(email) = &account.email else {
+            return Ok(None);
+        };
+        let user = match users::Model::find_by_email(db, email).await {
+            Ok(user) => user,
+            Err(ModelError::EntityNotFound) => {
+                let user = users::Model::create_with_password(
+                    db,
+                    &RegisterParams {
+                        email: email.clone(),
+                        password: hash::random_string(32),
+                        name: account
+                            .name
+                            .clone()
+                            .unwrap_or_else(|| account.login.clone()),
+                    },
+                )
+                .await?;
+                // the provider verified the email
+                user.into_active_model().verified(db).await?
+            }
+            Err(err) => return Err(err),
+        };
+        Self::link(db, user.id, provider, account).await?;
+        Ok(Some(user))
+    }
+}
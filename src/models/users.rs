@@ -9,6 +9,8 @@ pub use super::_entities::users::{self, ActiveModel, Entity, Model};
 
 pub const MAGIC_LINK_LENGTH: i8 = 32;
 pub const MAGIC_LINK_EXPIRATION_MIN: i8 = 5;
+/// The claim that ties an access token to its session.
+pub const SESSION_CLAIM: &str = "sid";
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct LoginParams {
@@ -50,7 +52,6 @@ impl ActiveModelBehavior for super::_entities::users::ActiveModel {
         if insert {
             let mut this = self;
             this.pid = ActiveValue::Set(Uuid::new_v4());
-            this.api_key = ActiveValue::Set(format!("lo-{}", Uuid::new_v4()));
             Ok(this)
         } else {
             Ok(self)
@@ -60,15 +61,12 @@ impl ActiveModelBehavior for super::_entities::users::ActiveModel {
 
 #[async_trait]
 impl Authenticable for Model {
+    /// Keys live in `api_keys`, see [`super::api_keys`].
     async fn find_by_api_key(db: &DatabaseConnection, api_key: &str) -> ModelResult<Self> {
-        let user = users::Entity::find()
-            .filter(
-                model::query::condition()
-                    .eq(users::Column::ApiKey, api_key)
-                    .build(),
-            )
-            .one(db)
-            .await?;
+        let key = super::api_keys::Entity::authenticate(db, api_key)
+            .await?
+            .ok_or_else(|| ModelError::EntityNotFound)?;
+        let user = users::Entity::find_by_id(key.user_id).one(db).await?;
         user.ok_or_else(|| ModelError::EntityNotFound)
     }
 
@@ -186,23 +184,6 @@ impl Model {
         user.ok_or_else(|| ModelError::EntityNotFound)
     }
 
-    /// finds a user by the provided api key
-    ///
-    /// # Errors
-    ///
-    /// When could not find user by the given token or DB query error
-    pub async fn find_by_api_key(db: &DatabaseConnection, api_key: &str) -> ModelResult<Self> {
-        let user = users::Entity::find()
-            .filter(
-                model::query::condition()
-                    .eq(users::Column::ApiKey, api_key)
-                    .build(),
-            )
-            .one(db)
-            .await?;
-        user.ok_or_else(|| ModelError::EntityNotFound)
-    }
-
     /// Verifies whether the provided plain password matches the hashed password
     ///
     /// # Errors
@@ -264,6 +245,24 @@ impl Model {
             .generate_token(expiration, self.pid.to_string(), Map::new())
             .map_err(ModelError::from)
     }
+
+    /// Creates a JWT for the session with `session_id`
+    ///
+    /// # Errors
+    ///
+    /// when could not convert user claims to jwt token
+    pub fn generate_session_jwt(
+        &self,
+        secret: &str,
+        expiration: u64,
+        session_id: i32,
+    ) -> ModelResult<String> {
+        let mut claims = Map::new();
+        claims.insert(SESSION_CLAIM.to_string(), session_id.into());
+        jwt::JWT::new(secret)
+            .generate_token(expiration, self.pid.to_string(), claims)
+            .map_err(ModelError::from)
+    }
 }
 
 impl ActiveModel {
@@ -366,4 +365,17 @@ impl ActiveModel {
         self.magic_link_expiration = ActiveValue::set(None);
         self.update(db).await.map_err(ModelError::from)
     }
+
+    /// Grants or takes the admin role.
+    ///
+    /// # Errors
+    /// - Returns an error if database update fails
+    pub async fn set_admin(
+        mut self,
+        db: &DatabaseConnection,
+        is_admin: bool,
+    ) -> ModelResult<Model> {
+        self.is_admin = ActiveValue::set(is_admin);
+        self.update(db).await.map_err(ModelError::from)
+    }
 }
@@ -0,0 +1,61 @@
+pub use super::_entities::webhook_deliveries::{ActiveModel, Column, Entity, Model};
+use sea_orm::{entity::prelude::*, ActiveValue::Set, SqlErr};
+pub type WebhookDeliveries = Entity;
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {}
+
+// implement your write-oriented logic here
+impl ActiveModel {}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {
+    /// Record a webhook delivery. Returns `false` when the delivery was
+    /// already recorded, so redelivered events are only applied once.
+    ///
+    /// # Errors
+    ///
+    /// DB Error.
+    pub async fn record<C>(
+        db: &C,
+        forge: &str,
+        delivery_id: &str,
+        event: &str,
+    ) -> Result<bool, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let res = ActiveModel {
+            forge: Set(forge.to_string()),
+            delivery_id: Set(delivery_id.to_string()),
+            event: Set(event.to_string()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await;
+
+        match res {
+            Ok(_) => Ok(true),
+            Err(err) if matches!(err.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+                Ok(false)
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use reqwest::Url;
+use serde::Deserialize;
+
+use super::{Account, Error, Result};
+use crate::forges;
+
+const NAME: &str = "GitHub";
+
+/// An OAuth app registered on GitHub. The endpoints default to github.com and
+/// only change to point at a stand-in.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Settings {
+    #[serde(default)]
+    pub client_id: Option<String>,
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    /// Where GitHub sends users back to, the app's
+    /// `/api/auth/github/callback`. GitHub uses the app's callback URL when
+    /// unset.
+    #[serde(default)]
+    pub redirect_url: Option<String>,
+    #[serde(default = "default_authorize_url")]
+    pub authorize_url: String,
+    #[serde(default = "default_token_url")]
+    pub token_url: String,
+    #[serde(default = "default_api_url")]
+    pub api_url: String,
+}
+
+fn default_authorize_url() -> String {
+    "https://github.com/login/oauth/authorize".to_string()
+}
+
+fn default_token_url() -> String {
+    "https://github.com/login/oauth/access_token".to_string()
+}
+
+fn default_api_url() -> String {
+    "https://api.github.com".to_string()
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            client_id: None,
+            client_secret: None,
+            redirect_url: None,
+            authorize_url: default_authorize_url(),
+            token_url: default_token_url(),
+            api_url: default_api_url(),
+        }
+    }
+}
+
+/// The web application flow of a GitHub OAuth app.
+pub struct GitHubOAuth {
+    client: reqwest::Client,
+    client_id: String,
+    client_secret: String,
+    settings: Settings,
+}
+
+impl GitHubOAuth {
+    /// # Errors
+    ///
+    /// [`Error::NotConfigured`] without a client id and secret.
+    pub fn new(settings: &Settings) -> Result<Self> {
+        let non_empty = |value: &Option<String>| value.clone().filter(|v| !v.is_empty());
+        let (Some(client_id), Some(client_secret)) = (
+            non_empty(&settings.client_id),
+            non_empty(&settings.client_secret),
+        ) else {
+            return Err(Error::NotConfigured(NAME));
+        };
+        Ok(Self {
+            client: forges::http_client()?,
+            client_id,
+            client_secret,
+            settings: settings.clone(),
+        })
+    }
+
+    /// Where to send the user to approve the sign in.
+    ///
+    /// # Errors
+    ///
+    /// When the configured authorize URL is invalid.
+    pub fn authorize_url(&self, state: &str) -> Result<Url> {
+        let mut params = vec![
+            ("client_id", self.client_id.as_str()),
+            ("scope", "read:user user:email"),
+            ("state", state),
+        ];
+        if let Some(redirect_url) = &self.settings.redirect_url {
+            params.push(("redirect_uri", redirect_url));
+        }
+        Url::parse_with_params(&self.settings.authorize_url, &params)
+            .map_err(|_| Error::InvalidUrl(NAME, self.settings.authorize_url.clone()))
+    }
+
+    /// Trade the `code` GitHub sent back for the account that approved it.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Refused`] when GitHub does not accept the code, or on API
+    /// errors.
+    pub async fn account(&self, code: &str) -> Result<Account> {
+        let token = self.access_token(code).await?;
+        let api_url = self.settings.api_url.trim_end_matches('/');
+
+        #[derive(Deserialize)]
+        struct User {
+            id: i64,
+            login: String,
+            name: Option<String>,
+        }
+        let user: User = self
+            .client
+            .get(format!("{api_url}/user"))
+            .bearer_auth(&token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        #[derive(Deserialize)]
+        struct Email {
+            email: String,
+            primary: bool,
+            verified: bool,
+        }
+        let emails: Vec<Email> = self
+            .client
+            .get(format!("{api_url}/user/emails"))
+            .bearer_auth(&token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(Account {
+            id: user.id.to_string(),
+            login: user.login,
+            name: user.name.filter(|name| !name.is_empty()),
+            email: emails
+                .into_iter()
+                .find(|email| email.primary && email.verified)
+                .map(|email| email.email),
+        })
+    }
+
+    async fn access_token(&self, code: &str) -> Result<String> {
+        // GitHub answers 200 with an `error` when it refuses the code
+        #[derive(Deserialize)]
+        struct Token {
+            access_token: Option<String>,
+            error: Option<String>,
+            error_description: Option<String>,
+        }
+        let token: Token = self
+            .client
+            .post(&self.settings.token_url)
+            .header("Accept", "application/json")
+            .form(&[
+                ("client_id", self.client_id.as_str()),
+                ("client_secret", self.client_secret.as_str()),
+                ("code", code),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        token.access_token.ok_or_else(|| {
+            Error::Refused(
+                NAME,
+                token
+                    .error_description
+                    .or(token.error)
+                    .unwrap_or_else(|| "no access token".to_string()),
+            )
+        })
+    }
+}
@@ -0,0 +1,97 @@
+//! Signing in with an account on another site. The account is linked to a
+//! user in `user_identities`, which is also how activity on a forge is
+//! attributed to users.
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::signed;
+
+pub mod github;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Settings {
+    #[serde(default)]
+    pub github: github::Settings,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0} sign in is not configured")]
+    NotConfigured(&'static str),
+    #[error("{0} refused the sign in: {1}")]
+    Refused(&'static str, String),
+    #[error("the {0} account has no verified email")]
+    NoVerifiedEmail(&'static str),
+    #[error("invalid {0} url `{1}`")]
+    InvalidUrl(&'static str, String),
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// An account on a provider, as far as signing in cares.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    pub id: String,
+    pub login: String,
+    pub name: Option<String>,
+    /// The primary email, when the provider verified it.
+    pub email: Option<String>,
+}
+
+/// How long a sign in may take from redirect to callback.
+pub const STATE_TTL_SECS: i64 = 600;
+
+/// What states are signed for, see [`signed`].
+const STATE_PURPOSE: &str = "oauth-state";
+
+/// What a sign in is for, signed into the `state` that round-trips through
+/// the provider.
+///
+/// The state also carries a nonce the browser that started the sign in
+/// keeps in a cookie, so a callback only completes in that browser.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Purpose {
+    /// Sign in, or sign up, with the account.
+    SignIn,
+    /// Link the account to the user with this pid.
+    Link(String),
+}
+
+impl Purpose {
+    /// A new nonce to sign into a state and keep in the browser.
+    #[must_use]
+    pub fn nonce() -> String {
+        Uuid::new_v4().simple().to_string()
+    }
+
+    /// A signed `state` with `nonce` for a sign in starting at `now`.
+    #[must_use]
+    pub fn sign(&self, secret: &str, nonce: &str, now: DateTime<Utc>) -> String {
+        let pid = match self {
+            Self::SignIn => "",
+            Self::Link(pid) => pid,
+        };
+        signed::sign(secret, STATE_PURPOSE, &[nonce, pid], now, STATE_TTL_SECS)
+    }
+
+    /// The purpose signed into `state`, `None` when it was tampered with,
+    /// has expired or was not signed with the browser's `nonce`.
+    #[must_use]
+    pub fn verify(secret: &str, state: &str, nonce: &str, now: DateTime<Utc>) -> Option<Self> {
+        let fields = signed::verify(secret, STATE_PURPOSE, state, now)?;
+        let [signed_nonce, pid] = fields.as_slice() else {
+            return None;
+        };
+        if signed_nonce != nonce {
+            return None;
+        }
+        Some(if pid.is_empty() {
+            Self::SignIn
+        } else {
+            Self::Link(pid.clone())
+        })
+    }
+}
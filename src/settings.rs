@@ -0,0 +1,44 @@
+//! App specific settings, read from the `settings` section of the config file.
+use loco_rs::{config::Config, Result};
+use serde::Deserialize;
+
+use crate::{
+    email_domains, forges,
+    models::{auth_throttles, sessions},
+    oauth, webhooks,
+};
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Settings {
+    #[serde(default)]
+    pub forges: forges::Settings,
+    /// Default rules for magic link emails, see [`email_domains`].
+    #[serde(default)]
+    pub magic_link: email_domains::Rules,
+    #[serde(default)]
+    pub oauth: oauth::Settings,
+    #[serde(default)]
+    pub sessions: sessions::Settings,
+    /// Brute-force protection of the auth endpoints.
+    #[serde(default)]
+    pub throttle: auth_throttles::Settings,
+    #[serde(default)]
+    pub webhooks: webhooks::Settings,
+}
+
+impl Settings {
+    /// Read the settings from the loaded config, defaulting every section
+    /// that is not present.
+    ///
+    /// # Errors
+    ///
+    /// When the `settings` section does not match [`Settings`].
+    pub fn from_config(config: &Config) -> Result<Self> {
+        Ok(config
+            .settings
+            .clone()
+            .map(serde_json::from_value)
+            .transpose()?
+            .unwrap_or_default())
+    }
+}
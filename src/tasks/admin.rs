@@ -0,0 +1,52 @@
+use loco_rs::prelude::*;
+
+use crate::models::{
+    audit_events::{self, NewEvent},
+    users,
+};
+
+/// Grant or take the admin role, to set up the first admin who can then
+/// manage the others from the admin console.
+///
+/// ```sh
+/// cargo loco task admin email:someone@example.com
+/// cargo loco task admin email:someone@example.com revoke:true
+/// ```
+pub struct Admin;
+
+#[async_trait]
+impl Task for Admin {
+    fn task(&self) -> TaskInfo {
+        TaskInfo {
+            name: "admin".to_string(),
+            detail: "Make a user an admin. args: email, [revoke=true]".to_string(),
+        }
+    }
+
+    async fn run(&self, ctx: &AppContext, vars: &task::Vars) -> Result<()> {
+        let user = users::Model::find_by_email(&ctx.db, vars.cli_arg("email")?).await?;
+        let is_admin = vars.cli.get("revoke").is_none_or(|revoke| revoke != "true");
+
+        let user = user
+            .into_active_model()
+            .set_admin(&ctx.db, is_admin)
+            .await?;
+        NewEvent {
+            target: Some(user.pid.to_string()),
+            details: Some(serde_json::json!({ "via": "task" })),
+            ..NewEvent::new(if is_admin {
+                audit_events::ADMIN_GRANTED
+            } else {
+                audit_events::ADMIN_REVOKED
+            })
+        }
+        .record(&ctx.db)
+        .await?;
+        println!(
+            "{} is {}an admin",
+            user.email,
+            if is_admin { "now " } else { "no longer " }
+        );
+        Ok(())
+    }
+}
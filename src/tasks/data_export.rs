@@ -0,0 +1,47 @@
+use loco_rs::prelude::*;
+
+use crate::{
+    models::{data_exports, users},
+    workers::data_export::{DataExportWorker, DataExportWorkerArgs},
+};
+
+/// Export the data of a user, for a data-access request that did not come
+/// through the app. The user gets the link by email, like when they ask
+/// themselves.
+///
+/// ```sh
+/// cargo loco task data_export email=someone@example.com
+/// cargo loco task data_export pid=0b8c3c02-6a3b-4f49-9b9e-4d8a2f7e1c55
+/// ```
+pub struct DataExport;
+
+#[async_trait]
+impl Task for DataExport {
+    fn task(&self) -> TaskInfo {
+        TaskInfo {
+            name: "data_export".to_string(),
+            detail: "Export a user's data and mail them the link. args: email or pid".to_string(),
+        }
+    }
+
+    async fn run(&self, ctx: &AppContext, vars: &task::Vars) -> Result<()> {
+        let user = if let Ok(email) = vars.cli_arg("email") {
+            users::Model::find_by_email(&ctx.db, email).await?
+        } else if let Ok(pid) = vars.cli_arg("pid") {
+            users::Model::find_by_pid(&ctx.db, pid).await?
+        } else {
+            return Err(Error::string("give the user as email=... or pid=..."));
+        };
+
+        let export = data_exports::Entity::request(&ctx.db, user.id).await?;
+        DataExportWorker::perform_later(
+            ctx,
+            DataExportWorkerArgs {
+                export_id: export.id,
+            },
+        )
+        .await?;
+        println!("export {} of {} queued", export.id, user.email);
+        Ok(())
+    }
+}
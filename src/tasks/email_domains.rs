@@ -0,0 +1,71 @@
+use loco_rs::prelude::*;
+
+use crate::{email_domains::Rules, models::email_domain_rules, settings::Settings};
+
+/// Show or override which email domains may sign in with a magic link.
+///
+/// ```sh
+/// cargo loco task email_domains
+/// cargo loco task email_domains allow=example.com,*.corp.example deny=gmail.com
+/// cargo loco task email_domains allow=*
+/// cargo loco task email_domains reset=true
+/// ```
+///
+/// An override replaces the `magic_link` settings until it is reset.
+pub struct EmailDomains;
+
+fn patterns(vars: &task::Vars, key: &str) -> Vec<String> {
+    vars.cli_arg(key)
+        .map(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn print(rules: &Rules) {
+    println!("allow: {}", rules.allow.join(", "));
+    println!("deny: {}", rules.deny.join(", "));
+}
+
+#[async_trait]
+impl Task for EmailDomains {
+    fn task(&self) -> TaskInfo {
+        TaskInfo {
+            name: "email_domains".to_string(),
+            detail: "Show or override the email domains allowed to use magic links".to_string(),
+        }
+    }
+
+    async fn run(&self, ctx: &AppContext, vars: &task::Vars) -> Result<()> {
+        if vars.cli_arg("reset").is_ok_and(|v| v == "true") {
+            let none = Rules {
+                allow: vec![],
+                deny: vec![],
+            };
+            email_domain_rules::Entity::replace(&ctx.db, &none).await?;
+        } else if vars.cli_arg("allow").is_ok() || vars.cli_arg("deny").is_ok() {
+            let rules = Rules {
+                allow: patterns(vars, "allow"),
+                deny: patterns(vars, "deny"),
+            };
+            email_domain_rules::Entity::replace(&ctx.db, &rules).await?;
+        }
+
+        match email_domain_rules::Entity::overrides(&ctx.db).await? {
+            Some(rules) => {
+                println!("overridden in the database:");
+                print(&rules);
+            }
+            None => {
+                println!("from the settings:");
+                print(&Settings::from_config(&ctx.config)?.magic_link);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+use loco_rs::prelude::*;
+
+use crate::{
+    forges::{Forge, LocalGit, RepoRef, RepoSource},
+    models::repos,
+    settings::Settings,
+};
+
+/// Fetch a single repo from its forge and store it.
+///
+/// ```sh
+/// cargo loco task fetch_repo url:https://codeberg.org/forgejo/forgejo
+/// cargo loco task fetch_repo forge:gitlab owner:gitlab-org name:gitlab host:gitlab.com
+/// cargo loco task fetch_repo path:/srv/mirrors/acme/tool.git
+/// cargo loco task fetch_repo id:42
+/// ```
+///
+/// Local repos take owner and name from the last two path segments unless
+/// `owner` or `name` are given. `id` refreshes a tracked repo in place,
+/// following renames and transfers upstream.
+pub struct FetchRepo;
+
+#[async_trait]
+impl Task for FetchRepo {
+    fn task(&self) -> TaskInfo {
+        TaskInfo {
+            name: "fetch_repo".to_string(),
+            detail:
+                "Fetch a repo from its forge. args: url, or path, or id, or owner, name, [forge=github], [host]"
+                    .to_string(),
+        }
+    }
+
+    async fn run(&self, ctx: &AppContext, vars: &task::Vars) -> Result<()> {
+        let settings = Settings::from_config(&ctx.config)?;
+
+        if let Some(id) = vars.cli.get("id") {
+            let id: i32 = id
+                .parse()
+                .map_err(|_| Error::string(&format!("id must be a number, got `{id}`")))?;
+            let repo = repos::Entity::find_by_id(id)
+                .one(&ctx.db)
+                .await?
+                .ok_or(Error::NotFound)?;
+            let source = repo.source(&settings.forges).map_err(Error::wrap)?;
+            let refreshed = repo
+                .refresh(source.as_ref(), &ctx.db)
+                .await
+                .map_err(Error::wrap)?;
+            println!(
+                "refreshed {}/{} from {} (was {}/{})",
+                refreshed.owner, refreshed.name, refreshed.host, repo.owner, repo.name
+            );
+            return Ok(());
+        }
+
+        let (source, owner, name): (Box<dyn RepoSource>, String, String) =
+            if let Some(path) = vars.cli.get("path") {
+                let local = LocalGit::new(path);
+                let (owner, name) = local.owner_and_name();
+                (
+                    Box::new(local),
+                    vars.cli.get("owner").cloned().unwrap_or(owner),
+                    vars.cli.get("name").cloned().unwrap_or(name),
+                )
+            } else {
+                let repo_ref = if let Some(url) = vars.cli.get("url") {
+                    RepoRef::parse(url, &settings.forges).map_err(Error::wrap)?
+                } else {
+                    let forge: Forge = vars
+                        .cli
+                        .get("forge")
+                        .map_or(Ok(Forge::Github), |f| f.parse())
+                        .map_err(Error::wrap)?;
+                    RepoRef {
+                        forge,
+                        host: vars.cli.get("host").cloned().unwrap_or_default(),
+                        owner: vars.cli_arg("owner")?.clone(),
+                        name: vars.cli_arg("name")?.clone(),
+                    }
+                };
+                let host = Some(repo_ref.host.as_str()).filter(|h| !h.is_empty());
+                let source = settings
+                    .forges
+                    .source(repo_ref.forge, host)
+                    .map_err(Error::wrap)?;
+                (source, repo_ref.owner, repo_ref.name)
+            };
+
+        let repo = repos::Entity::fetch(source.as_ref(), &owner, &name, &ctx.db)
+            .await
+            .map_err(Error::wrap)?;
+
+        println!(
+            "fetched {}/{} from {} (health {:.1})",
+            repo.owner,
+            repo.name,
+            repo.host,
+            repo.health()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use loco_rs::prelude::*;
+
+use crate::{
+    forges::{listing, GitHub, ImportFilter},
+    models::repos,
+};
+
+/// Import all public repos of a GitHub user or organization into one
+/// project.
+///
+/// ```sh
+/// cargo loco task import_repos account:rust-lang
+/// cargo loco task import_repos account:rust-lang project_id:3 topic:compiler min_stars:50
+/// cargo loco task import_repos account:rust-lang archived:true forks:true
+/// ```
+///
+/// Archived repos and forks are left out unless asked for. Without
+/// `project_id` a project named after the account is created.
+pub struct ImportRepos;
+
+fn flag(vars: &task::Vars, name: &str) -> bool {
+    vars.cli
+        .get(name)
+        .is_some_and(|v| matches!(v.as_str(), "true" | "1" | "yes"))
+}
+
+fn number(vars: &task::Vars, name: &str) -> Result<Option<i32>> {
+    vars.cli
+        .get(name)
+        .map(|v| {
+            v.parse()
+                .map_err(|_| Error::string(&format!("{name} must be a number, got `{v}`")))
+        })
+        .transpose()
+}
+
+#[async_trait]
+impl Task for ImportRepos {
+    fn task(&self) -> TaskInfo {
+        TaskInfo {
+            name: "import_repos".to_string(),
+            detail: "Import all repos of a GitHub user or org. args: account, [project_id], [topic], [min_stars], [archived=false], [forks=false]"
+                .to_string(),
+        }
+    }
+
+    async fn run(&self, ctx: &AppContext, vars: &task::Vars) -> Result<()> {
+        let account = listing::parse_account(vars.cli_arg("account")?).map_err(Error::wrap)?;
+        let filter = ImportFilter {
+            include_archived: flag(vars, "archived"),
+            include_forks: flag(vars, "forks"),
+            topic: vars.cli.get("topic").cloned(),
+            min_stars: number(vars, "min_stars")?.unwrap_or_default(),
+        };
+        let project_id = number(vars, "project_id")?;
+
+        let github = GitHub::from_env().map_err(Error::wrap)?;
+        let import =
+            repos::Entity::import_account(&github, &account, &filter, project_id, None, &ctx.db)
+                .await
+                .map_err(Error::wrap)?;
+
+        let Some(project_id) = import.project_id else {
+            println!("no repos of {account} match");
+            return Ok(());
+        };
+        for repo in &import.imported {
+            println!("imported {}/{}", repo.owner, repo.name);
+        }
+        for repo in &import.skipped {
+            println!("skipped {repo}, already tracked");
+        }
+        for (repo, err) in &import.failed {
+            println!("failed {repo}: {err}");
+        }
+        println!(
+            "{} imported, {} skipped, {} failed into project {project_id}",
+            import.imported.len(),
+            import.skipped.len(),
+            import.failed.len()
+        );
+        Ok(())
+    }
+}
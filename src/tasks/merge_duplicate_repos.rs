@@ -0,0 +1,39 @@
+use loco_rs::prelude::*;
+
+use crate::models::repos;
+
+/// Merge repo rows that track the same repo, left over from fetches that
+/// inserted a new row every time.
+///
+/// ```sh
+/// cargo loco task merge_duplicate_repos
+/// ```
+///
+/// Safe to run more than once, a second run finds nothing to merge.
+pub struct MergeDuplicateRepos;
+
+#[async_trait]
+impl Task for MergeDuplicateRepos {
+    fn task(&self) -> TaskInfo {
+        TaskInfo {
+            name: "merge_duplicate_repos".to_string(),
+            detail: "Merge duplicate repo rows into the one fetched last".to_string(),
+        }
+    }
+
+    async fn run(&self, ctx: &AppContext, _vars: &task::Vars) -> Result<()> {
+        let merges = repos::Entity::merge_duplicates(&ctx.db).await?;
+        for merge in &merges {
+            println!(
+                "kept {}/{} (#{}), removed repos {:?} and projects {:?}",
+                merge.kept.owner,
+                merge.kept.name,
+                merge.kept.id,
+                merge.removed,
+                merge.removed_projects
+            );
+        }
+        println!("{} repos merged", merges.len());
+        Ok(())
+    }
+}
@@ -1 +1,7 @@
-
+pub mod admin;
+pub mod data_export;
+pub mod email_domains;
+pub mod fetch_repo;
+pub mod import_repos;
+pub mod merge_duplicate_repos;
+pub mod recalculate_health;
@@ -0,0 +1,43 @@
+use loco_rs::prelude::*;
+
+use crate::models::projects;
+
+/// Recalculate the health of every project in one pass, for when repos
+/// were changed outside the app. This also rebuilds the percentiles repos
+/// are scored against, so the scheduler runs it regularly.
+///
+/// ```sh
+/// cargo loco task recalculate_health
+/// cargo loco task recalculate_health concurrency:16
+/// ```
+pub struct RecalculateHealth;
+
+#[async_trait]
+impl Task for RecalculateHealth {
+    fn task(&self) -> TaskInfo {
+        TaskInfo {
+            name: "recalculate_health".to_string(),
+            detail: format!(
+                "Recalculate the health of every project. args: [concurrency:{}]",
+                projects::RECALCULATE_CONCURRENCY
+            ),
+        }
+    }
+
+    async fn run(&self, ctx: &AppContext, vars: &task::Vars) -> Result<()> {
+        let concurrency = match vars.cli.get("concurrency") {
+            Some(concurrency) => match concurrency.parse::<usize>() {
+                Ok(concurrency) if concurrency > 0 => concurrency,
+                _ => {
+                    return Err(Error::string(&format!(
+                        "concurrency must be a number above zero, got `{concurrency}`"
+                    )))
+                }
+            },
+            None => projects::RECALCULATE_CONCURRENCY,
+        };
+        let count = projects::Entity::recalculate_all_health(&ctx.db, concurrency).await?;
+        println!("{count} projects recalculated");
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+//! Time-based one-time passwords (RFC 6238) as shown by authenticator apps:
+//! HMAC-SHA1, 6 digits, a new code every 30 seconds.
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+
+/// Seconds each code is valid for.
+pub const PERIOD: u64 = 30;
+/// Codes of this many periods before or after now are accepted too, for
+/// clocks that are a little off and codes typed in at the last second.
+pub const DRIFT: u64 = 1;
+const DIGITS: u32 = 6;
+/// Bytes in a new secret, the size of the HMAC-SHA1 key RFC 4226 suggests.
+pub const SECRET_LEN: usize = 20;
+
+const BASE32: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Base32 without padding, how authenticator apps take secrets.
+#[must_use]
+pub fn base32_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    for &byte in bytes {
+        buffer = (buffer << 8) | u32::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    out
+}
+
+/// Decode base32, ignoring case, spaces and padding. `None` on other
+/// characters.
+#[must_use]
+pub fn base32_decode(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() * 5 / 8);
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    for c in text.bytes().filter(|c| !matches!(c, b' ' | b'=')) {
+        let value = BASE32.iter().position(|&b| b == c.to_ascii_uppercase())?;
+        buffer = (buffer << 5) | u32::try_from(value).ok()?;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push(u8::try_from((buffer >> bits) & 0xff).ok()?);
+        }
+    }
+    Some(out)
+}
+
+/// The time step `unix_secs` falls in.
+#[must_use]
+pub const fn step_at(unix_secs: u64) -> u64 {
+    unix_secs / PERIOD
+}
+
+/// The code for `step`, zero padded.
+#[must_use]
+pub fn code_at(secret: &[u8], step: u64) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC takes keys of any size");
+    mac.update(&step.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+    let offset = usize::from(digest[digest.len() - 1] & 0x0f);
+    let truncated = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    format!(
+        "{:0width$}",
+        truncated % 10u32.pow(DIGITS),
+        width = DIGITS as usize
+    )
+}
+
+/// The step `code` is valid for at `unix_secs`, allowing [`DRIFT`] steps
+/// either way. Steps up to `used` are refused, so a code works only once.
+#[must_use]
+pub fn verify(secret: &[u8], code: &str, unix_secs: u64, used: Option<u64>) -> Option<u64> {
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    if code.len() != DIGITS as usize {
+        return None;
+    }
+    let now = step_at(unix_secs);
+    (now.saturating_sub(DRIFT)..=now + DRIFT)
+        .filter(|step| used.is_none_or(|used| *step > used))
+        .find(|step| constant_time_eq(code_at(secret, *step).as_bytes(), code.as_bytes()))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// The `otpauth://` URI authenticator apps read from a QR code.
+#[must_use]
+pub fn provisioning_uri(secret: &[u8], issuer: &str, account: &str) -> String {
+    let label = format!("{issuer}:{account}");
+    let mut uri = reqwest::Url::parse("otpauth://totp/").expect("static url");
+    uri.path_segments_mut()
+        .expect("otpauth urls have paths")
+        .pop()
+        .push(&label);
+    uri.query_pairs_mut()
+        .append_pair("secret", &base32_encode(secret))
+        .append_pair("issuer", issuer)
+        .append_pair("algorithm", "SHA1")
+        .append_pair("digits", &DIGITS.to_string())
+        .append_pair("period", &PERIOD.to_string());
+    uri.to_string()
+}
@@ -0,0 +1,125 @@
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    health,
+    models::{
+        _entities::{audit_events, repos, sync_runs, users},
+        audit_events::Filter,
+    },
+};
+
+/// A user as admins see them, without secrets.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UserResponse {
+    pub pid: String,
+    pub name: String,
+    pub email: String,
+    pub is_admin: bool,
+    pub email_verified_at: Option<DateTimeWithTimeZone>,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+impl UserResponse {
+    #[must_use]
+    pub fn new(user: &users::Model) -> Self {
+        Self {
+            pid: user.pid.to_string(),
+            name: user.name.clone(),
+            email: user.email.clone(),
+            is_admin: user.is_admin,
+            email_verified_at: user.email_verified_at,
+            created_at: user.created_at,
+        }
+    }
+}
+
+/// Render failed sync runs and the repos marked because of them, with
+/// forms sent with `csrf_token`.
+///
+/// # Errors
+///
+/// When there is an issue with rendering the view.
+pub fn sync_failures(
+    v: &impl ViewRenderer,
+    runs: &[sync_runs::Model],
+    marked: &[repos::Model],
+    csrf_token: Option<&str>,
+) -> Result<Response> {
+    format::render().view(
+        v,
+        "admin/sync_failures.html",
+        data!({
+            "runs": runs,
+            "marked": marked,
+            "csrf_token": csrf_token.unwrap_or_default(),
+        }),
+    )
+}
+
+/// Render a list of `users` with what admins can do to them, in forms sent
+/// with `csrf_token`.
+///
+/// # Errors
+///
+/// When there is an issue with rendering the view.
+pub fn users(
+    v: &impl ViewRenderer,
+    items: &[users::Model],
+    csrf_token: Option<&str>,
+) -> Result<Response> {
+    let items: Vec<_> = items.iter().map(UserResponse::new).collect();
+    format::render().view(
+        v,
+        "admin/users.html",
+        data!({"items": items, "csrf_token": csrf_token.unwrap_or_default()}),
+    )
+}
+
+/// Render the health config form, sent with `csrf_token`, with why it was
+/// rejected when re-rendered.
+///
+/// # Errors
+///
+/// When there is an issue with rendering the view.
+pub fn health(
+    v: &impl ViewRenderer,
+    config: &health::Config,
+    error: Option<&str>,
+    csrf_token: Option<&str>,
+) -> Result<Response> {
+    format::render().view(
+        v,
+        "admin/health.html",
+        data!({
+            "config": config,
+            "error": error,
+            "csrf_token": csrf_token.unwrap_or_default(),
+        }),
+    )
+}
+
+/// Render a page of audit events and the filter they match.
+///
+/// # Errors
+///
+/// When there is an issue with rendering the view.
+pub fn audit_log(
+    v: &impl ViewRenderer,
+    filter: &Filter,
+    pagination: &query::PaginationQuery,
+    res: &query::PageResponse<audit_events::Model>,
+) -> Result<Response> {
+    format::render().view(
+        v,
+        "admin/audit.html",
+        data!({
+            "filter": filter,
+            "items": res.page,
+            "page": pagination.page,
+            "page_size": pagination.page_size,
+            "total_pages": res.total_pages,
+            "total_items": res.total_items,
+        }),
+    )
+}
@@ -1,10 +1,13 @@
+use sea_orm::prelude::DateTimeWithTimeZone;
 use serde::{Deserialize, Serialize};
 
-use crate::models::_entities::users;
+use crate::models::_entities::{sessions, users};
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct LoginResponse {
     pub token: String,
+    /// Trade this at `/api/auth/refresh` for a new `token` before it expires.
+    pub refresh_token: String,
     pub pid: String,
     pub name: String,
     pub is_verified: bool,
@@ -12,9 +15,10 @@ pub struct LoginResponse {
 
 impl LoginResponse {
     #[must_use]
-    pub fn new(user: &users::Model, token: &str) -> Self {
+    pub fn new(user: &users::Model, token: &str, refresh_token: &str) -> Self {
         Self {
             token: token.to_owned(),
+            refresh_token: refresh_token.to_owned(),
             pid: user.pid.to_string(),
             name: user.name.clone(),
             is_verified: user.email_verified_at.is_some(),
@@ -22,6 +26,64 @@ impl LoginResponse {
     }
 }
 
+/// The answer to a password, magic link or GitHub sign in of a user with
+/// two-factor authentication on.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TwoFactorResponse {
+    pub two_factor_required: bool,
+    /// Send this with a code to `/api/auth/2fa/verify` to finish signing in.
+    pub challenge: String,
+}
+
+impl TwoFactorResponse {
+    #[must_use]
+    pub fn new(challenge: &str) -> Self {
+        Self {
+            two_factor_required: true,
+            challenge: challenge.to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EnrollResponse {
+    /// The base32 secret, for typing into an authenticator app.
+    pub secret: String,
+    /// The `otpauth://` URI to show as a QR code.
+    pub uri: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RecoveryCodesResponse {
+    /// Each works once in place of a code, they are not shown again.
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SessionResponse {
+    pub id: i32,
+    pub user_agent: Option<String>,
+    pub created_at: DateTimeWithTimeZone,
+    pub last_used_at: Option<DateTimeWithTimeZone>,
+    pub expires_at: DateTimeWithTimeZone,
+    /// Whether this is the session of the token asking.
+    pub current: bool,
+}
+
+impl SessionResponse {
+    #[must_use]
+    pub fn new(session: &sessions::Model, current: Option<i32>) -> Self {
+        Self {
+            id: session.id,
+            user_agent: session.user_agent.clone(),
+            created_at: session.created_at,
+            last_used_at: session.last_used_at,
+            expires_at: session.expires_at,
+            current: current == Some(session.id),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct CurrentResponse {
     pub pid: String,
@@ -1,4 +1,5 @@
 use loco_rs::prelude::*;
+use serde::Serialize;
 
 use crate::models::_entities::projects;
 
@@ -20,20 +21,56 @@ pub fn show(v: &impl ViewRenderer, item: &projects::Model) -> Result<Response> {
     format::render().view(v, "project/show.html", data!({"item": item}))
 }
 
-/// Render a `project` create form.
+/// Render a `project` create form, sent with `csrf_token`.
 ///
 /// # Errors
 ///
 /// When there is an issue with rendering the view.
-pub fn create(v: &impl ViewRenderer) -> Result<Response> {
-    format::render().view(v, "project/create.html", data!({}))
+pub fn create(v: &impl ViewRenderer, csrf_token: Option<&str>) -> Result<Response> {
+    format::render().view(
+        v,
+        "project/create.html",
+        data!({"csrf_token": csrf_token.unwrap_or_default()}),
+    )
 }
 
-/// Render a `project` edit form.
+/// Render a `project` edit form, sent with `csrf_token`.
 ///
 /// # Errors
 ///
 /// When there is an issue with rendering the view.
-pub fn edit(v: &impl ViewRenderer, item: &projects::Model) -> Result<Response> {
-    format::render().view(v, "project/edit.html", data!({"item": item}))
+pub fn edit(
+    v: &impl ViewRenderer,
+    item: &projects::Model,
+    csrf_token: Option<&str>,
+) -> Result<Response> {
+    format::render().view(
+        v,
+        "project/edit.html",
+        data!({"item": item, "csrf_token": csrf_token.unwrap_or_default()}),
+    )
+}
+
+/// Render the form to import the repos of a GitHub account, sent with
+/// `csrf_token`, with the previous input and why it was rejected when
+/// re-rendered.
+///
+/// # Errors
+///
+/// When there is an issue with rendering the view.
+pub fn import(
+    v: &impl ViewRenderer,
+    form: &impl Serialize,
+    error: Option<&str>,
+    csrf_token: Option<&str>,
+) -> Result<Response> {
+    format::render().view(
+        v,
+        "project/import.html",
+        data!({
+            "form": form,
+            "error": error,
+            "csrf_token": csrf_token.unwrap_or_default(),
+        }),
+    )
 }
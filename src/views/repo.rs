@@ -20,20 +20,44 @@ pub fn show(v: &impl ViewRenderer, item: &repos::Model) -> Result<Response> {
     format::render().view(v, "repo/show.html", data!({"item": item}))
 }
 
-/// Render a `repo` create form.
+/// Render the `repo` import form, sent with `csrf_token`, with the previous
+/// input and why it was rejected when re-rendered after a failed import.
 ///
 /// # Errors
 ///
 /// When there is an issue with rendering the view.
-pub fn create(v: &impl ViewRenderer) -> Result<Response> {
-    format::render().view(v, "repo/create.html", data!({}))
+pub fn create(
+    v: &impl ViewRenderer,
+    repo: &str,
+    project_id: Option<i32>,
+    error: Option<&str>,
+    csrf_token: Option<&str>,
+) -> Result<Response> {
+    format::render().view(
+        v,
+        "repo/create.html",
+        data!({
+            "repo": repo,
+            "project_id": project_id,
+            "error": error,
+            "csrf_token": csrf_token.unwrap_or_default(),
+        }),
+    )
 }
 
-/// Render a `repo` edit form.
+/// Render a `repo` edit form, sent with `csrf_token`.
 ///
 /// # Errors
 ///
 /// When there is an issue with rendering the view.
-pub fn edit(v: &impl ViewRenderer, item: &repos::Model) -> Result<Response> {
-    format::render().view(v, "repo/edit.html", data!({"item": item}))
+pub fn edit(
+    v: &impl ViewRenderer,
+    item: &repos::Model,
+    csrf_token: Option<&str>,
+) -> Result<Response> {
+    format::render().view(
+        v,
+        "repo/edit.html",
+        data!({"item": item, "csrf_token": csrf_token.unwrap_or_default()}),
+    )
 }
@@ -0,0 +1,318 @@
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use loco_rs::prelude::*;
+use serde::Deserialize;
+use sha2::Sha256;
+
+use super::Outcome;
+use crate::{
+    forges::{github::HOST, Forge},
+    models::{
+        _entities::activity_events, projects, repos, sync_runs::SyncStatus, webhook_deliveries,
+    },
+};
+
+/// Events that change repo stats or show up in the activity feed.
+pub const EVENTS: &[&str] = &[
+    "push",
+    "pull_request",
+    "issues",
+    "release",
+    "star",
+    "fork",
+    "repository",
+];
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Check `X-Hub-Signature-256` (`sha256=<hex hmac of the body>`) in constant
+/// time.
+#[must_use]
+pub fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let Some(expected) = signature
+        .strip_prefix("sha256=")
+        .and_then(|hex_sig| hex::decode(hex_sig).ok())
+    else {
+        return false;
+    };
+    let Ok(mut mac) = HmacSha256::new_from_slice(secret.as_bytes()) else {
+        return false;
+    };
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+#[derive(Debug, Deserialize)]
+struct Payload {
+    action: Option<String>,
+    repository: Option<Repository>,
+    sender: Option<Account>,
+    #[serde(rename = "ref")]
+    git_ref: Option<String>,
+    #[serde(default)]
+    commits: Vec<serde_json::Value>,
+    compare: Option<String>,
+    pull_request: Option<Item>,
+    issue: Option<Item>,
+    release: Option<Release>,
+    forkee: Option<Forkee>,
+    changes: Option<Changes>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Repository {
+    id: Option<i64>,
+    name: String,
+    owner: Account,
+    html_url: Option<String>,
+    archived: Option<bool>,
+    default_branch: Option<String>,
+    stargazers_count: Option<i32>,
+    forks_count: Option<i32>,
+    open_issues_count: Option<i32>,
+    watchers_count: Option<i32>,
+}
+
+/// What a `repository` `renamed` or `transferred` event changed.
+#[derive(Debug, Default, Deserialize)]
+struct Changes {
+    repository: Option<RepositoryChanges>,
+    owner: Option<OwnerChange>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepositoryChanges {
+    name: Option<Previous<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OwnerChange {
+    from: PreviousOwner,
+}
+
+#[derive(Debug, Deserialize)]
+struct PreviousOwner {
+    user: Option<Account>,
+    organization: Option<Account>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Previous<T> {
+    from: T,
+}
+
+#[derive(Debug, Deserialize)]
+struct Account {
+    login: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Item {
+    title: Option<String>,
+    html_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    name: Option<String>,
+    tag_name: String,
+    html_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Forkee {
+    full_name: String,
+    html_url: Option<String>,
+}
+
+/// Apply a verified GitHub event to the matching repo and append it to the
+/// activity log.
+///
+/// Recording the delivery and applying it happen in one transaction, so a
+/// failed delivery is applied when GitHub redelivers it, and a successful one
+/// is never applied twice.
+///
+/// # Errors
+///
+/// When the payload is not valid JSON for the event, or on DB errors.
+pub async fn handle(
+    db: &DatabaseConnection,
+    delivery_id: &str,
+    event: &str,
+    body: &[u8],
+) -> Result<Outcome> {
+    if !EVENTS.contains(&event) {
+        return Ok(Outcome::Ignored);
+    }
+    let payload: Payload = serde_json::from_slice(body)?;
+    let Some(gh_repo) = &payload.repository else {
+        return Ok(Outcome::Ignored);
+    };
+
+    let txn = db.begin().await?;
+    if !webhook_deliveries::Entity::record(&txn, Forge::Github.as_str(), delivery_id, event).await?
+    {
+        return Ok(Outcome::Duplicate);
+    }
+
+    let Some(repo) = find_repo(&txn, gh_repo, payload.changes.as_ref()).await? else {
+        txn.commit().await?;
+        return Ok(Outcome::Ignored);
+    };
+    let repo_id = repo.id;
+
+    let action = payload.action.as_deref();
+    let mut item = repo.clone().into_active_model();
+
+    // the repo as it is now, after a rename or transfer
+    item.owner = Set(gh_repo.owner.login.clone());
+    item.name = Set(gh_repo.name.clone());
+    if gh_repo.id.is_some() {
+        item.forge_id = Set(gh_repo.id);
+    }
+    if let Some(archived) = gh_repo.archived {
+        item.archived = Set(archived);
+    }
+
+    // absolute counts GitHub sends along with every event
+    if let Some(stars) = gh_repo.stargazers_count {
+        item.stars = Set(stars);
+    }
+    if let Some(forks) = gh_repo.forks_count {
+        item.forks = Set(forks);
+    }
+    if let Some(issues) = gh_repo.open_issues_count {
+        item.issues = Set(issues);
+    }
+    if let Some(watchers) = gh_repo.watchers_count {
+        item.watchers = Set(watchers);
+    }
+
+    // everything else is counted incrementally until the next full fetch
+    let (title, url) = match event {
+        "push" => {
+            let branch = payload
+                .git_ref
+                .as_deref()
+                .and_then(|r| r.strip_prefix("refs/heads/"))
+                .unwrap_or_default();
+            let commits = i32::try_from(payload.commits.len()).unwrap_or(i32::MAX);
+            if gh_repo.default_branch.as_deref() == Some(branch) {
+                item.commits_last_30d = Set(repo.commits_last_30d.saturating_add(commits));
+            }
+            (
+                Some(format!("{commits} commit(s) to {branch}")),
+                payload.compare.clone(),
+            )
+        }
+        "pull_request" => {
+            match action {
+                Some("opened" | "reopened") => item.prs = Set(repo.prs.saturating_add(1)),
+                Some("closed") => item.prs = Set((repo.prs - 1).max(0)),
+                _ => {}
+            }
+            item_details(payload.pull_request.as_ref())
+        }
+        "issues" => item_details(payload.issue.as_ref()),
+        "release" => {
+            match action {
+                Some("published") => item.releases = Set(repo.releases.saturating_add(1)),
+                Some("deleted") => item.releases = Set((repo.releases - 1).max(0)),
+                _ => {}
+            }
+            payload.release.as_ref().map_or((None, None), |r| {
+                (
+                    Some(r.name.clone().unwrap_or_else(|| r.tag_name.clone())),
+                    r.html_url.clone(),
+                )
+            })
+        }
+        "repository" => {
+            if action == Some("deleted") {
+                item.sync_status = Set(SyncStatus::Gone.as_str().to_string());
+            }
+            let previous = payload
+                .changes
+                .as_ref()
+                .and_then(|c| previous_name(c, &gh_repo.owner.login, &gh_repo.name));
+            let title = match (action, previous) {
+                (Some("renamed" | "transferred"), Some((owner, name))) => {
+                    Some(format!("moved from {owner}/{name}"))
+                }
+                _ => None,
+            };
+            (title, gh_repo.html_url.clone())
+        }
+        "fork" => payload.forkee.as_ref().map_or((None, None), |f| {
+            (Some(f.full_name.clone()), f.html_url.clone())
+        }),
+        _ => (None, None),
+    };
+
+    item.update(&txn).await?;
+
+    activity_events::ActiveModel {
+        repo_id: Set(repo_id),
+        kind: Set(event.to_string()),
+        action: Set(payload.action.clone()),
+        actor: Set(payload.sender.as_ref().map(|s| s.login.clone())),
+        title: Set(title),
+        url: Set(url),
+        occurred_at: Set(Utc::now().into()),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+
+    txn.commit().await?;
+    projects::Entity::recalculate_health_of(db, repo.project_id).await?;
+    Ok(Outcome::Applied(repo_id))
+}
+
+/// The tracked repo an event is about: by GitHub's repo id, which survives
+/// renames and transfers, then by its current and its previous name.
+async fn find_repo<C>(
+    db: &C,
+    gh_repo: &Repository,
+    changes: Option<&Changes>,
+) -> Result<Option<repos::Model>>
+where
+    C: ConnectionTrait,
+{
+    let owner = &gh_repo.owner.login;
+    let name = &gh_repo.name;
+    if let Some(repo) =
+        repos::Entity::find_tracked(db, Forge::Github, HOST, gh_repo.id, owner, name).await?
+    {
+        return Ok(Some(repo));
+    }
+    let Some((owner, name)) = changes.and_then(|c| previous_name(c, owner, name)) else {
+        return Ok(None);
+    };
+    Ok(repos::Entity::find_by_ref(db, Forge::Github, HOST, &owner, &name).await?)
+}
+
+/// Owner and name before a `renamed` or `transferred` event.
+fn previous_name(changes: &Changes, owner: &str, name: &str) -> Option<(String, String)> {
+    let old_name = changes
+        .repository
+        .as_ref()
+        .and_then(|r| r.name.as_ref())
+        .map(|n| n.from.clone());
+    let old_owner = changes
+        .owner
+        .as_ref()
+        .and_then(|o| o.from.user.as_ref().or(o.from.organization.as_ref()))
+        .map(|a| a.login.clone());
+    if old_name.is_none() && old_owner.is_none() {
+        return None;
+    }
+    Some((
+        old_owner.unwrap_or_else(|| owner.to_string()),
+        old_name.unwrap_or_else(|| name.to_string()),
+    ))
+}
+
+fn item_details(item: Option<&Item>) -> (Option<String>, Option<String>) {
+    item.map_or((None, None), |i| (i.title.clone(), i.html_url.clone()))
+}
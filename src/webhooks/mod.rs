@@ -0,0 +1,49 @@
+//! Incoming forge webhooks. They keep `repos` up to date between fetches and
+//! feed the activity log with what happens in a repo.
+use serde::Deserialize;
+
+pub mod github;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Settings {
+    #[serde(default)]
+    pub github: GitHubSettings,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct GitHubSettings {
+    /// The secret configured on the GitHub webhook, used to verify
+    /// `X-Hub-Signature-256`.
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+impl GitHubSettings {
+    /// The configured secret, `None` when unset or empty.
+    #[must_use]
+    pub fn secret(&self) -> Option<&str> {
+        self.secret.as_deref().filter(|s| !s.is_empty())
+    }
+}
+
+/// What happened to a delivered event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The event updated the repo with this id.
+    Applied(i32),
+    /// The event was delivered before and skipped.
+    Duplicate,
+    /// Not a repo we track, or an event we do not handle.
+    Ignored,
+}
+
+impl Outcome {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Applied(_) => "applied",
+            Self::Duplicate => "duplicate",
+            Self::Ignored => "ignored",
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    mailers::auth::AuthMailer,
+    models::{data_exports, users},
+};
+
+/// Assembles an export of a user's data and mails them the link to it.
+pub struct DataExportWorker {
+    pub ctx: AppContext,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct DataExportWorkerArgs {
+    pub export_id: i32,
+}
+
+#[async_trait]
+impl BackgroundWorker<DataExportWorkerArgs> for DataExportWorker {
+    fn build(ctx: &AppContext) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    async fn perform(&self, args: DataExportWorkerArgs) -> Result<()> {
+        let export = data_exports::Entity::find_by_id(args.export_id)
+            .one(&self.ctx.db)
+            .await?
+            .ok_or(Error::NotFound)?;
+        let user = users::Entity::find_by_id(export.user_id)
+            .one(&self.ctx.db)
+            .await?
+            .ok_or(Error::NotFound)?;
+
+        let archive = data_exports::assemble(&self.ctx.db, &user).await?;
+        let export = export.complete(&self.ctx.db, archive).await?;
+        AuthMailer::send_data_export(&self.ctx, &user, &export).await?;
+        let expired = data_exports::Entity::delete_expired(&self.ctx.db).await?;
+
+        tracing::info!(
+            export_id = export.id,
+            pid = user.pid.to_string(),
+            expired,
+            "data export ready"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{forges::Forge, models::repos, settings::Settings};
+
+/// Fetches a repo from its forge in the background, e.g. after it was
+/// imported through the web UI.
+pub struct FetchRepoWorker {
+    pub ctx: AppContext,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct FetchRepoWorkerArgs {
+    pub forge: Forge,
+    pub host: String,
+    pub owner: String,
+    pub name: String,
+    /// Project to add the repo to, a new one is created when `None`.
+    pub project_id: Option<i32>,
+    /// Who imported the repo, they own the project created for it.
+    #[serde(default)]
+    pub user_id: Option<i32>,
+}
+
+#[async_trait]
+impl BackgroundWorker<FetchRepoWorkerArgs> for FetchRepoWorker {
+    fn build(ctx: &AppContext) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    async fn perform(&self, args: FetchRepoWorkerArgs) -> Result<()> {
+        let settings = Settings::from_config(&self.ctx.config)?;
+        let source = settings
+            .forges
+            .source(args.forge, Some(&args.host))
+            .map_err(Error::wrap)?;
+        let repo = repos::Entity::fetch_into(
+            source.as_ref(),
+            &args.owner,
+            &args.name,
+            args.project_id,
+            args.user_id,
+            &self.ctx.db,
+        )
+        .await
+        .map_err(Error::wrap)?;
+
+        tracing::info!(
+            repo_id = repo.id,
+            forge = repo.forge,
+            owner = repo.owner,
+            name = repo.name,
+            "repo fetched"
+        );
+        Ok(())
+    }
+}
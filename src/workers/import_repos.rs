@@ -0,0 +1,55 @@
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    forges::{GitHub, ImportFilter},
+    models::repos,
+};
+
+/// Imports the repos of a GitHub account in the background, started from
+/// the project import form.
+pub struct ImportReposWorker {
+    pub ctx: AppContext,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct ImportReposWorkerArgs {
+    pub account: String,
+    pub filter: ImportFilter,
+    /// Project to add the repos to, a new one is created when `None`.
+    pub project_id: Option<i32>,
+    /// Who started the import, they own the project created for it.
+    #[serde(default)]
+    pub user_id: Option<i32>,
+}
+
+#[async_trait]
+impl BackgroundWorker<ImportReposWorkerArgs> for ImportReposWorker {
+    fn build(ctx: &AppContext) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    async fn perform(&self, args: ImportReposWorkerArgs) -> Result<()> {
+        let github = GitHub::from_env().map_err(Error::wrap)?;
+        let import = repos::Entity::import_account(
+            &github,
+            &args.account,
+            &args.filter,
+            args.project_id,
+            args.user_id,
+            &self.ctx.db,
+        )
+        .await
+        .map_err(Error::wrap)?;
+
+        tracing::info!(
+            account = args.account,
+            project_id = import.project_id,
+            imported = import.imported.len(),
+            skipped = import.skipped.len(),
+            failed = import.failed.len(),
+            "repos imported"
+        );
+        Ok(())
+    }
+}
@@ -1 +1,5 @@
+pub mod data_export;
 pub mod downloader;
+pub mod fetch_repo;
+pub mod import_repos;
+pub mod sync_repo;
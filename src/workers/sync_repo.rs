@@ -0,0 +1,47 @@
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{models::repos, settings::Settings};
+
+/// Fetches a tracked repo again in the background, e.g. when an admin asks
+/// for it after a failed sync.
+pub struct SyncRepoWorker {
+    pub ctx: AppContext,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct SyncRepoWorkerArgs {
+    pub repo_id: i32,
+}
+
+#[async_trait]
+impl BackgroundWorker<SyncRepoWorkerArgs> for SyncRepoWorker {
+    fn build(ctx: &AppContext) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    async fn perform(&self, args: SyncRepoWorkerArgs) -> Result<()> {
+        let Some(repo) = repos::Entity::find_by_id(args.repo_id)
+            .one(&self.ctx.db)
+            .await?
+        else {
+            tracing::warn!(repo_id = args.repo_id, "repo to sync is gone");
+            return Ok(());
+        };
+        let settings = Settings::from_config(&self.ctx.config)?;
+        let source = repo.source(&settings.forges).map_err(Error::wrap)?;
+        let repo = repo
+            .refresh(source.as_ref(), &self.ctx.db)
+            .await
+            .map_err(Error::wrap)?;
+
+        tracing::info!(
+            repo_id = repo.id,
+            forge = repo.forge,
+            owner = repo.owner,
+            name = repo.name,
+            "repo synced"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+use std::{path::PathBuf, process::Command};
+
+use chrono::{Duration, Utc};
+use tempfile::TempDir;
+
+/// A generated git repo at `<tmp>/acme/tool`.
+///
+/// Four commits by three authors (one of them with a differently cased
+/// email), three of them in the last 30 days, and two tags.
+pub struct FixtureRepo {
+    _dir: TempDir,
+    pub path: PathBuf,
+}
+
+fn git(path: &PathBuf, args: &[&str], envs: &[(&str, String)]) {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .args(args)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .envs(envs.iter().map(|(k, v)| (*k, v.as_str())))
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {args:?} failed");
+}
+
+fn commit(path: &PathBuf, author: &str, email: &str, days_ago: i64) {
+    let date = (Utc::now() - Duration::days(days_ago)).to_rfc2822();
+    let envs = [
+        ("GIT_AUTHOR_NAME", author.to_string()),
+        ("GIT_AUTHOR_EMAIL", email.to_string()),
+        ("GIT_AUTHOR_DATE", date.clone()),
+        ("GIT_COMMITTER_NAME", author.to_string()),
+        ("GIT_COMMITTER_EMAIL", email.to_string()),
+        ("GIT_COMMITTER_DATE", date),
+    ];
+    let message = format!("work by {author}");
+    git(
+        path,
+        &["commit", "--quiet", "--allow-empty", "-m", &message],
+        &envs,
+    );
+}
+
+impl FixtureRepo {
+    pub fn create() -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("acme").join("tool");
+        std::fs::create_dir_all(&path).unwrap();
+
+        git(&path, &["init", "--quiet", "--initial-branch=main"], &[]);
+        commit(&path, "alice", "alice@example.com", 60);
+        git(&path, &["tag", "v0.1.0"], &[]);
+        commit(&path, "bob", "bob@example.com", 10);
+        commit(&path, "alice", "Alice@Example.com", 5);
+        git(&path, &["tag", "v0.2.0"], &[]);
+        commit(&path, "carol", "carol@example.com", 1);
+
+        Self { _dir: dir, path }
+    }
+}
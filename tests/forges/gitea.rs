@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, Query},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use gooncityhub::{
+    app::App,
+    forges::{Error, Gitea, RepoSource},
+    models::repos::Entity,
+};
+use loco_rs::testing::prelude::*;
+use serde_json::json;
+use serial_test::serial;
+
+use super::stand_in;
+
+async fn repo(Path((owner, name)): Path<(String, String)>) -> impl IntoResponse {
+    if (owner.as_str(), name.as_str()) != ("forgejo", "forgejo") {
+        return (StatusCode::NOT_FOUND, Json(json!({"message": "not found"})));
+    }
+    (
+        StatusCode::OK,
+        Json(json!({
+            "id": 7,
+            "name": "forgejo",
+            "owner": { "login": "forgejo" },
+            "stars_count": 300,
+            "forks_count": 40,
+            "watchers_count": 25,
+            "open_issues_count": 9,
+            "open_pr_counter": 6,
+            "release_counter": 11,
+            "licenses": ["GPL-3.0-or-later"]
+        })),
+    )
+}
+
+async fn commits(
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    assert_eq!(
+        headers.get("authorization").and_then(|v| v.to_str().ok()),
+        Some("token secret")
+    );
+    let author = |email: &str| json!({ "commit": { "author": { "email": email } } });
+    if query.contains_key("since") {
+        (
+            [("x-total-count", "17")],
+            Json(vec![author("a@example.com")]),
+        )
+    } else {
+        (
+            [("x-total-count", "4")],
+            Json(vec![
+                author("a@example.com"),
+                author("B@example.com"),
+                author("b@example.com"),
+                author("c@example.com"),
+            ]),
+        )
+    }
+}
+
+async fn repo_by_id(Path(id): Path<i64>) -> impl IntoResponse {
+    if id == 7 {
+        repo(Path(("forgejo".to_string(), "forgejo".to_string())))
+            .await
+            .into_response()
+    } else {
+        StatusCode::NOT_FOUND.into_response()
+    }
+}
+
+fn gitea_stand_in() -> Router {
+    Router::new()
+        .route("/api/v1/repos/{owner}/{name}", get(repo))
+        .route("/api/v1/repositories/{id}", get(repo_by_id))
+        .route("/api/v1/repos/{owner}/{name}/commits", get(commits))
+}
+
+#[tokio::test]
+async fn can_fetch_gitea_stats() {
+    let base_url = stand_in::serve(gitea_stand_in()).await;
+    let gitea = Gitea::new(&base_url, Some("secret")).unwrap();
+
+    let stats = gitea.fetch("forgejo", "forgejo").await.unwrap();
+
+    assert_eq!(stats.owner, "forgejo");
+    assert_eq!(stats.stars, 300);
+    assert_eq!(stats.forks, 40);
+    assert_eq!(stats.watchers, 25);
+    assert_eq!(stats.issues, 9);
+    assert_eq!(stats.prs, 6);
+    assert_eq!(stats.releases, 11);
+    assert_eq!(stats.commits_last_30d, 17);
+    assert_eq!(stats.contributors, 3);
+    assert_eq!(stats.license.as_deref(), Some("GPL-3.0-or-later"));
+}
+
+#[tokio::test]
+async fn can_fetch_gitea_repo_by_id() {
+    let base_url = stand_in::serve(gitea_stand_in()).await;
+    let gitea = Gitea::new(&base_url, Some("secret")).unwrap();
+
+    let stats = gitea.fetch_by_id(7).await.unwrap();
+
+    assert_eq!(stats.forge_id, Some(7));
+    assert_eq!(stats.owner, "forgejo");
+    assert_eq!(stats.name, "forgejo");
+    assert!(matches!(
+        gitea.fetch_by_id(8).await,
+        Err(Error::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn gitea_reports_missing_repo() {
+    let base_url = stand_in::serve(gitea_stand_in()).await;
+    let gitea = Gitea::new(&base_url, None).unwrap();
+
+    let res = gitea.fetch("forgejo", "missing").await;
+
+    assert!(matches!(res, Err(Error::NotFound(_))), "{res:?}");
+}
+
+#[tokio::test]
+#[serial]
+async fn can_store_gitea_repo() {
+    let boot = boot_test::<App>().await.unwrap();
+    let base_url = stand_in::serve(gitea_stand_in()).await;
+    let gitea = Gitea::new(&base_url, Some("secret")).unwrap();
+
+    let repo = Entity::fetch(&gitea, "forgejo", "forgejo", &boot.app_context.db)
+        .await
+        .unwrap();
+
+    assert_eq!(repo.forge, "gitea");
+    assert_eq!(repo.host, "127.0.0.1");
+    assert_eq!(repo.releases, 11);
+}
@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use gooncityhub::{
+    app::App,
+    forges::{listing, Error, GitHub, ImportFilter, ListedRepo},
+    models::{_entities::projects, repos},
+};
+use loco_rs::prelude::*;
+use serde_json::{json, Value};
+use serial_test::serial;
+
+use super::stand_in;
+
+/// The id GitHub gives the repo `name` of `acme`.
+fn repo_id(name: &str) -> i64 {
+    match name {
+        "tool" => 1,
+        "site" => 2,
+        _ => 3,
+    }
+}
+
+fn listed(name: &str, archived: bool, fork: bool, topics: &[&str], stars: i32) -> Value {
+    json!({
+        "id": repo_id(name),
+        "name": name,
+        "owner": { "login": "acme" },
+        "archived": archived,
+        "fork": fork,
+        "topics": topics,
+        "stargazers_count": stars,
+    })
+}
+
+/// Lists the repos of `acme` on two pages, linking the second page from the
+/// first like GitHub does.
+async fn account_repos(
+    State(base_url): State<String>,
+    Path(account): Path<String>,
+    Query(query): Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    if account != "acme" {
+        return (
+            StatusCode::NOT_FOUND,
+            [("link", String::new())],
+            Json(json!({ "message": "Not Found" })),
+        );
+    }
+    if query.get("page").map(String::as_str) == Some("2") {
+        return (
+            StatusCode::OK,
+            [("link", String::new())],
+            Json(json!([listed("old", true, false, &[], 50)])),
+        );
+    }
+    (
+        StatusCode::OK,
+        [(
+            "link",
+            format!("<{base_url}/users/acme/repos?page=2>; rel=\"next\""),
+        )],
+        Json(json!([
+            listed("tool", false, false, &["cli"], 120),
+            listed("tool-fork", false, true, &["cli"], 3),
+            listed("site", false, false, &["web"], 8),
+        ])),
+    )
+}
+
+/// The full account object octocrab expects as repo owner.
+fn owner_json(login: &str) -> Value {
+    let mut owner = json!({
+        "login": login,
+        "id": 2,
+        "node_id": "",
+        "gravatar_id": "",
+        "type": "Organization",
+        "site_admin": false,
+    });
+    for field in [
+        "avatar_url",
+        "url",
+        "html_url",
+        "followers_url",
+        "following_url",
+        "gists_url",
+        "starred_url",
+        "subscriptions_url",
+        "organizations_url",
+        "repos_url",
+        "events_url",
+        "received_events_url",
+    ] {
+        owner[field] = json!("https://example.com");
+    }
+    owner
+}
+
+async fn repo(Path((owner, name)): Path<(String, String)>) -> impl IntoResponse {
+    Json(json!({
+        "id": repo_id(&name),
+        "name": name,
+        "url": format!("https://api.github.com/repos/{owner}/{name}"),
+        "owner": owner_json(&owner),
+        "stargazers_count": 120,
+        "forks_count": 7,
+        "open_issues_count": 4,
+        "watchers_count": 120,
+    }))
+}
+
+async fn empty_list() -> impl IntoResponse {
+    Json(json!([]))
+}
+
+async fn github_stand_in() -> String {
+    stand_in::serve_with(|base_url| {
+        Router::new()
+            .route("/users/{account}/repos", get(account_repos))
+            .route("/repos/{owner}/{name}", get(repo))
+            .route("/repos/{owner}/{name}/pulls", get(empty_list))
+            .route("/repos/{owner}/{name}/contributors", get(empty_list))
+            .route("/repos/{owner}/{name}/commits", get(empty_list))
+            .route("/repos/{owner}/{name}/releases", get(empty_list))
+            .with_state(base_url.to_string())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn can_list_all_pages_of_account_repos() {
+    let base_url = github_stand_in().await;
+    let github = GitHub::new(Some(&base_url), None).unwrap();
+
+    let repos = github.list_repos("acme").await.unwrap();
+
+    let names: Vec<_> = repos.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, ["tool", "tool-fork", "site", "old"]);
+    assert!(repos[1].fork);
+    assert!(repos[3].archived);
+    assert_eq!(repos[0].topics, ["cli"]);
+    assert_eq!(repos[0].stars, 120);
+}
+
+#[tokio::test]
+async fn list_reports_missing_account() {
+    let base_url = github_stand_in().await;
+    let github = GitHub::new(Some(&base_url), None).unwrap();
+
+    let res = github.list_repos("nobody").await;
+
+    assert!(matches!(res, Err(Error::AccountNotFound(_))), "{res:?}");
+}
+
+#[test]
+fn filter_skips_archived_and_forks_by_default() {
+    let repo = |archived, fork| ListedRepo {
+        forge_id: None,
+        owner: "acme".to_string(),
+        name: "tool".to_string(),
+        archived,
+        fork,
+        topics: vec!["CLI".to_string()],
+        stars: 10,
+    };
+    let default = ImportFilter::default();
+    assert!(default.matches(&repo(false, false)));
+    assert!(!default.matches(&repo(true, false)));
+    assert!(!default.matches(&repo(false, true)));
+
+    let all = ImportFilter {
+        include_archived: true,
+        include_forks: true,
+        ..Default::default()
+    };
+    assert!(all.matches(&repo(true, true)));
+
+    let topic = ImportFilter {
+        topic: Some("cli".to_string()),
+        ..Default::default()
+    };
+    assert!(topic.matches(&repo(false, false)));
+    let other_topic = ImportFilter {
+        topic: Some("web".to_string()),
+        ..Default::default()
+    };
+    assert!(!other_topic.matches(&repo(false, false)));
+
+    let popular = ImportFilter {
+        min_stars: 11,
+        ..Default::default()
+    };
+    assert!(!popular.matches(&repo(false, false)));
+}
+
+#[test]
+fn can_parse_account() {
+    assert_eq!(listing::parse_account("rust-lang").unwrap(), "rust-lang");
+    assert_eq!(listing::parse_account(" @rust-lang ").unwrap(), "rust-lang");
+    assert_eq!(
+        listing::parse_account("https://github.com/rust-lang/").unwrap(),
+        "rust-lang"
+    );
+    assert!(matches!(
+        listing::parse_account("rust-lang/rust"),
+        Err(Error::InvalidAccount(_))
+    ));
+    assert!(listing::parse_account("").is_err());
+}
+
+#[tokio::test]
+#[serial]
+async fn can_import_account_into_new_project() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let base_url = github_stand_in().await;
+    let github = GitHub::new(Some(&base_url), None).unwrap();
+
+    let import =
+        repos::Entity::import_account(&github, "acme", &ImportFilter::default(), None, None, db)
+            .await
+            .unwrap();
+
+    let names: Vec<_> = import.imported.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, ["tool", "site"]);
+    let project_id = import.project_id.unwrap();
+    let project = projects::Entity::find_by_id(project_id)
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(project.name, "acme");
+    let stored = repos::Entity::find().all(db).await.unwrap();
+    assert_eq!(stored.len(), 2);
+    assert!(stored.iter().all(|r| r.project_id == project_id));
+    assert!(stored.iter().all(|r| r.stars == 120));
+}
+
+#[tokio::test]
+#[serial]
+async fn import_skips_tracked_repos() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let base_url = github_stand_in().await;
+    let github = GitHub::new(Some(&base_url), None).unwrap();
+    let filter = ImportFilter {
+        topic: Some("cli".to_string()),
+        ..Default::default()
+    };
+
+    let first = repos::Entity::import_account(&github, "acme", &filter, None, None, db)
+        .await
+        .unwrap();
+    let second =
+        repos::Entity::import_account(&github, "acme", &filter, first.project_id, None, db)
+            .await
+            .unwrap();
+
+    assert_eq!(first.imported.len(), 1);
+    assert!(second.imported.is_empty());
+    assert_eq!(second.skipped, ["acme/tool"]);
+    assert_eq!(repos::Entity::find().all(db).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+#[serial]
+async fn import_skips_tracked_repos_that_were_renamed() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let base_url = github_stand_in().await;
+    let github = GitHub::new(Some(&base_url), None).unwrap();
+    let filter = ImportFilter {
+        topic: Some("cli".to_string()),
+        ..Default::default()
+    };
+    let first = repos::Entity::import_account(&github, "acme", &filter, None, None, db)
+        .await
+        .unwrap();
+    // tracked under the name it had before it was renamed on GitHub
+    let mut tracked: repos::ActiveModel = first.imported[0].clone().into();
+    tracked.name = Set("old-tool".to_string());
+    tracked.update(db).await.unwrap();
+
+    let second = repos::Entity::import_account(&github, "acme", &filter, None, None, db)
+        .await
+        .unwrap();
+
+    assert!(second.imported.is_empty());
+    assert_eq!(second.skipped, ["acme/tool"]);
+    let stored = repos::Entity::find().all(db).await.unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(Some(stored[0].project_id), first.project_id);
+}
+
+#[tokio::test]
+#[serial]
+async fn import_without_matches_creates_no_project() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let base_url = github_stand_in().await;
+    let github = GitHub::new(Some(&base_url), None).unwrap();
+    let filter = ImportFilter {
+        min_stars: 1000,
+        ..Default::default()
+    };
+
+    let import = repos::Entity::import_account(&github, "acme", &filter, None, None, db)
+        .await
+        .unwrap();
+
+    assert!(import.project_id.is_none());
+    assert!(projects::Entity::find().all(db).await.unwrap().is_empty());
+}
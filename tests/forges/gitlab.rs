@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, Query},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use gooncityhub::{
+    app::App,
+    forges::{Error, GitLab, RepoSource},
+    models::repos::Entity,
+};
+use loco_rs::testing::prelude::*;
+use serde_json::json;
+use serial_test::serial;
+
+use super::stand_in;
+
+const TOKEN: &str = "glpat-test";
+
+async fn project(Path(id): Path<String>, headers: HeaderMap) -> impl IntoResponse {
+    if headers.get("private-token").and_then(|v| v.to_str().ok()) != Some(TOKEN) {
+        return (StatusCode::UNAUTHORIZED, Json(json!({"message": "401"})));
+    }
+    // by path, or by the stable id that survives renames
+    if id != "group/subgroup/project" && id != "42" {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"message": "404 Project Not Found"})),
+        );
+    }
+    (
+        StatusCode::OK,
+        Json(json!({
+            "id": 42,
+            "path": "project",
+            "namespace": { "full_path": "group/subgroup" },
+            "star_count": 120,
+            "forks_count": 7,
+            "open_issues_count": 4,
+            "license": { "name": "MIT License" },
+            "archived": true
+        })),
+    )
+}
+
+fn items(n: usize) -> Json<Vec<serde_json::Value>> {
+    Json((0..n).map(|i| json!({ "id": i })).collect())
+}
+
+async fn commits(Query(query): Query<HashMap<String, String>>) -> impl IntoResponse {
+    assert!(query.contains_key("since"), "commits must be limited");
+    items(5)
+}
+
+fn gitlab_stand_in() -> Router {
+    Router::new()
+        .route("/api/v4/projects/{id}", get(project))
+        .route(
+            "/api/v4/projects/{id}/merge_requests",
+            get(|Query(query): Query<HashMap<String, String>>| async move {
+                assert_eq!(query.get("state").map(String::as_str), Some("opened"));
+                items(3)
+            }),
+        )
+        .route(
+            "/api/v4/projects/{id}/repository/contributors",
+            // a single page, but the instance reports the real total
+            get(|| async { ([("x-total", "12")], items(2)) }),
+        )
+        .route("/api/v4/projects/{id}/repository/commits", get(commits))
+        .route("/api/v4/projects/{id}/releases", get(|| async { items(2) }))
+}
+
+#[tokio::test]
+async fn can_fetch_gitlab_stats() {
+    let base_url = stand_in::serve(gitlab_stand_in()).await;
+    let gitlab = GitLab::new(&base_url, Some(TOKEN)).unwrap();
+
+    let stats = gitlab.fetch("group/subgroup", "project").await.unwrap();
+
+    assert_eq!(stats.owner, "group/subgroup");
+    assert_eq!(stats.name, "project");
+    assert_eq!(stats.stars, 120);
+    assert_eq!(stats.forks, 7);
+    assert_eq!(stats.issues, 4);
+    assert_eq!(stats.prs, 3);
+    assert_eq!(stats.contributors, 12);
+    assert_eq!(stats.commits_last_30d, 5);
+    assert_eq!(stats.releases, 2);
+    assert_eq!(stats.license.as_deref(), Some("MIT License"));
+}
+
+#[tokio::test]
+async fn can_fetch_gitlab_project_by_id() {
+    let base_url = stand_in::serve(gitlab_stand_in()).await;
+    let gitlab = GitLab::new(&base_url, Some(TOKEN)).unwrap();
+
+    let stats = gitlab.fetch_by_id(42).await.unwrap();
+
+    assert_eq!(stats.forge_id, Some(42));
+    assert_eq!(stats.owner, "group/subgroup");
+    assert_eq!(stats.name, "project");
+    assert!(stats.archived);
+    assert!(matches!(
+        gitlab.fetch_by_id(43).await,
+        Err(Error::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn gitlab_reports_missing_project() {
+    let base_url = stand_in::serve(gitlab_stand_in()).await;
+    let gitlab = GitLab::new(&base_url, Some(TOKEN)).unwrap();
+
+    let res = gitlab.fetch("group", "missing").await;
+
+    assert!(matches!(res, Err(Error::NotFound(_))), "{res:?}");
+}
+
+#[tokio::test]
+#[serial]
+async fn can_store_gitlab_repo() {
+    let boot = boot_test::<App>().await.unwrap();
+    let base_url = stand_in::serve(gitlab_stand_in()).await;
+    let gitlab = GitLab::new(&base_url, Some(TOKEN)).unwrap();
+
+    let repo = Entity::fetch(&gitlab, "group/subgroup", "project", &boot.app_context.db)
+        .await
+        .unwrap();
+
+    assert_eq!(repo.forge, "gitlab");
+    assert_eq!(repo.owner, "group/subgroup");
+    assert_eq!(repo.releases, 2);
+    assert!(repo.health() > 0.);
+}
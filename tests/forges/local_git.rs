@@ -0,0 +1,61 @@
+use gooncityhub::{
+    app::App,
+    forges::{Error, LocalGit, RepoSource},
+    models::repos::Entity,
+};
+use loco_rs::testing::prelude::*;
+use serial_test::serial;
+
+use super::fixture_repo::FixtureRepo;
+
+#[tokio::test]
+async fn can_compute_local_git_stats() {
+    let fixture = FixtureRepo::create();
+    let local = LocalGit::new(&fixture.path);
+
+    let stats = local.fetch("acme", "tool").await.unwrap();
+
+    assert_eq!(stats.commits_last_30d, 3);
+    assert_eq!(stats.contributors, 3);
+    assert_eq!(stats.releases, 2);
+    assert_eq!(stats.stars, 0);
+    assert_eq!(stats.prs, 0);
+}
+
+#[tokio::test]
+async fn local_git_owner_and_name_from_path() {
+    let fixture = FixtureRepo::create();
+
+    let (owner, name) = LocalGit::new(&fixture.path).owner_and_name();
+
+    assert_eq!((owner.as_str(), name.as_str()), ("acme", "tool"));
+}
+
+#[tokio::test]
+async fn local_git_reports_missing_repo() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let res = LocalGit::new(dir.path()).fetch("acme", "tool").await;
+
+    assert!(matches!(res, Err(Error::NotFound(_))), "{res:?}");
+}
+
+#[tokio::test]
+#[serial]
+async fn can_store_local_git_repo() {
+    let boot = boot_test::<App>().await.unwrap();
+    let fixture = FixtureRepo::create();
+
+    let repo = Entity::fetch(
+        &LocalGit::new(&fixture.path),
+        "acme",
+        "tool",
+        &boot.app_context.db,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(repo.forge, "local");
+    assert_eq!(repo.host, "localhost");
+    assert_eq!(repo.commits_last_30d, 3);
+}
@@ -0,0 +1,7 @@
+pub mod fixture_repo;
+mod gitea;
+mod github;
+mod gitlab;
+mod local_git;
+mod repo_ref;
+pub mod stand_in;
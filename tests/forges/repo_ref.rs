@@ -0,0 +1,132 @@
+use gooncityhub::forges::{Error, Forge, InstanceSettings, RepoRef, Settings};
+use rstest::rstest;
+
+fn settings() -> Settings {
+    Settings {
+        gitlab: vec![InstanceSettings {
+            base_url: "https://gitlab.example.com".to_string(),
+            token: None,
+        }],
+        gitea: vec![InstanceSettings {
+            base_url: "https://git.example.org/".to_string(),
+            token: None,
+        }],
+    }
+}
+
+#[rstest]
+#[case(
+    "octocat/hello-world",
+    Forge::Github,
+    "github.com",
+    "octocat",
+    "hello-world"
+)]
+#[case(
+    "https://github.com/octocat/hello-world",
+    Forge::Github,
+    "github.com",
+    "octocat",
+    "hello-world"
+)]
+#[case(
+    "https://www.github.com/octocat/hello-world/tree/main/src",
+    Forge::Github,
+    "github.com",
+    "octocat",
+    "hello-world"
+)]
+#[case(
+    "git@github.com:octocat/hello-world.git",
+    Forge::Github,
+    "github.com",
+    "octocat",
+    "hello-world"
+)]
+#[case(
+    "codeberg.org/forgejo/forgejo",
+    Forge::Gitea,
+    "codeberg.org",
+    "forgejo",
+    "forgejo"
+)]
+#[case(
+    "https://Codeberg.org/forgejo/forgejo.git/",
+    Forge::Gitea,
+    "codeberg.org",
+    "forgejo",
+    "forgejo"
+)]
+#[case(
+    "https://git.example.org/team/tool/issues",
+    Forge::Gitea,
+    "git.example.org",
+    "team",
+    "tool"
+)]
+#[case(
+    "https://gitlab.com/gitlab-org/gitlab",
+    Forge::Gitlab,
+    "gitlab.com",
+    "gitlab-org",
+    "gitlab"
+)]
+#[case(
+    "https://gitlab.com/group/sub/project/-/merge_requests?state=opened",
+    Forge::Gitlab,
+    "gitlab.com",
+    "group/sub",
+    "project"
+)]
+#[case(
+    "gitlab.example.com:8443/ops/infra",
+    Forge::Gitlab,
+    "gitlab.example.com",
+    "ops",
+    "infra"
+)]
+fn can_parse_repo_ref(
+    #[case] input: &str,
+    #[case] forge: Forge,
+    #[case] host: &str,
+    #[case] owner: &str,
+    #[case] name: &str,
+) {
+    let repo_ref = RepoRef::parse(input, &settings()).unwrap();
+
+    assert_eq!(
+        repo_ref,
+        RepoRef {
+            forge,
+            host: host.to_string(),
+            owner: owner.to_string(),
+            name: name.to_string(),
+        }
+    );
+}
+
+#[rstest]
+#[case("")]
+#[case("octocat")]
+#[case("https://github.com/octocat")]
+#[case("octocat/hello world")]
+#[case("../etc/passwd")]
+fn rejects_invalid_repo_ref(#[case] input: &str) {
+    let res = RepoRef::parse(input, &settings());
+
+    assert!(matches!(res, Err(Error::InvalidRepoRef(_))), "{res:?}");
+}
+
+#[test]
+fn rejects_unknown_host() {
+    let res = RepoRef::parse("https://example.net/owner/name", &settings());
+
+    assert!(matches!(res, Err(Error::UnknownHost(host)) if host == "example.net"));
+}
+
+#[test]
+fn repo_ref_has_web_url() {
+    let repo_ref = RepoRef::parse("codeberg.org/forgejo/forgejo", &settings()).unwrap();
+
+    assert_eq!(repo_ref.url(), "https://codeberg.org/forgejo/forgejo");
+}
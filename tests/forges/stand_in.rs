@@ -0,0 +1,19 @@
+use axum::Router;
+use tokio::net::TcpListener;
+
+/// Serve `router` on a random local port and return its base URL.
+pub async fn serve(router: Router) -> String {
+    serve_with(|_| router).await
+}
+
+/// Like [`serve`], for stand-ins that need their own base URL, e.g. to send
+/// pagination links.
+pub async fn serve_with(router: impl FnOnce(&str) -> Router) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let router = router(&base_url);
+    tokio::spawn(async move {
+        axum::serve(listener, router).await.unwrap();
+    });
+    base_url
+}
@@ -1,4 +1,6 @@
+mod forges;
 mod models;
 mod requests;
+mod signed;
 mod tasks;
 mod workers;
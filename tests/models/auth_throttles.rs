@@ -0,0 +1,131 @@
+use chrono::{Duration, Utc};
+use gooncityhub::{
+    app::App,
+    models::auth_throttles::{self, Settings},
+};
+use loco_rs::prelude::*;
+use serial_test::serial;
+
+const KEY: &str = "login:account:someone@example.com";
+
+#[test]
+fn lockouts_double_up_to_the_max() {
+    let settings = Settings {
+        lockout: 60,
+        max_lockout: 600,
+        ..Default::default()
+    };
+    assert_eq!(settings.lockout_after(0), Duration::seconds(60));
+    assert_eq!(settings.lockout_after(1), Duration::seconds(120));
+    assert_eq!(settings.lockout_after(3), Duration::seconds(480));
+    assert_eq!(settings.lockout_after(4), Duration::seconds(600));
+    assert_eq!(settings.lockout_after(100), Duration::seconds(600));
+}
+
+#[tokio::test]
+#[serial]
+async fn locks_out_after_the_limit() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let settings = Settings::default();
+    let keys = [KEY.to_string()];
+
+    for _ in 0..2 {
+        assert!(auth_throttles::Entity::hit(db, &settings, KEY, 3)
+            .await
+            .unwrap()
+            .is_none());
+    }
+    assert!(auth_throttles::Entity::locked_until(db, &keys)
+        .await
+        .unwrap()
+        .is_none());
+    let locked = auth_throttles::Entity::hit(db, &settings, KEY, 3)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(locked.is_locked());
+    assert_eq!(locked.lockouts, 1);
+    let until = auth_throttles::Entity::locked_until(db, &keys)
+        .await
+        .unwrap()
+        .unwrap();
+    let first = until.with_timezone(&Utc) - Utc::now();
+    assert!(first > Duration::seconds(55) && first <= Duration::seconds(60));
+
+    // the next lockout is twice as long
+    for _ in 0..2 {
+        auth_throttles::Entity::hit(db, &settings, KEY, 3)
+            .await
+            .unwrap();
+    }
+    let locked = auth_throttles::Entity::hit(db, &settings, KEY, 3)
+        .await
+        .unwrap()
+        .unwrap();
+    let second = locked.locked_until.unwrap().with_timezone(&Utc) - Utc::now();
+    assert!(second > Duration::seconds(115) && second <= Duration::seconds(120));
+
+    auth_throttles::Entity::clear(db, KEY).await.unwrap();
+    assert!(auth_throttles::Entity::locked_until(db, &keys)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+#[serial]
+async fn attempts_expire_with_the_window() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let settings = Settings::default();
+
+    for _ in 0..2 {
+        auth_throttles::Entity::hit(db, &settings, KEY, 3)
+            .await
+            .unwrap();
+    }
+    let throttle = auth_throttles::Entity::find()
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut throttle = throttle.into_active_model();
+    throttle.window_started_at = Set((Utc::now() - Duration::hours(1)).into());
+    throttle.update(db).await.unwrap();
+
+    assert!(auth_throttles::Entity::hit(db, &settings, KEY, 3)
+        .await
+        .unwrap()
+        .is_none());
+    let throttle = auth_throttles::Entity::find()
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(throttle.attempts, 1);
+}
+
+#[tokio::test]
+#[serial]
+async fn concurrent_attempts_all_count() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let settings = Settings::default();
+
+    let mut hits = tokio::task::JoinSet::new();
+    for _ in 0..10 {
+        let (db, settings) = (db.clone(), settings.clone());
+        hits.spawn(async move { auth_throttles::Entity::hit(&db, &settings, KEY, 100).await });
+    }
+    while let Some(hit) = hits.join_next().await {
+        assert!(hit.unwrap().unwrap().is_none());
+    }
+
+    let throttle = auth_throttles::Entity::find()
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(throttle.attempts, 10);
+}
@@ -0,0 +1,74 @@
+use gooncityhub::{
+    app::App,
+    email_domains::{Rejected, Rules},
+    models::email_domain_rules,
+};
+use loco_rs::testing::prelude::*;
+use serial_test::serial;
+
+fn rules(allow: &[&str], deny: &[&str]) -> Rules {
+    Rules {
+        allow: allow.iter().map(ToString::to_string).collect(),
+        deny: deny.iter().map(ToString::to_string).collect(),
+    }
+}
+
+#[test]
+fn checks_email_domains() {
+    let rules = rules(&["corp.example", "*.corp.example"], &["lab.corp.example"]);
+    assert_eq!(rules.check("a@corp.example"), Ok(()));
+    assert_eq!(rules.check("a@Eng.Corp.Example"), Ok(()));
+    assert_eq!(
+        rules.check("a@lab.corp.example"),
+        Err(Rejected::Denied("lab.corp.example".to_string()))
+    );
+    assert_eq!(
+        rules.check("a@notcorp.example"),
+        Err(Rejected::NotAllowed("notcorp.example".to_string()))
+    );
+    assert_eq!(
+        rules.check("corp.example"),
+        Err(Rejected::NoDomain("corp.example".to_string()))
+    );
+
+    let all = self::rules(&["*"], &["gmail.com"]);
+    assert_eq!(all.check("a@anything.test"), Ok(()));
+    assert!(all.check("a@gmail.com").is_err());
+}
+
+#[tokio::test]
+#[serial]
+async fn overrides_configured_rules() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let configured = Rules::default();
+
+    assert_eq!(
+        email_domain_rules::Entity::overrides(db).await.unwrap(),
+        None
+    );
+    assert_eq!(
+        email_domain_rules::Entity::effective(db, &configured)
+            .await
+            .unwrap(),
+        configured
+    );
+
+    email_domain_rules::Entity::replace(db, &rules(&["*", " Corp.Example"], &["corp.example"]))
+        .await
+        .unwrap();
+    assert_eq!(
+        email_domain_rules::Entity::effective(db, &configured)
+            .await
+            .unwrap(),
+        rules(&["*"], &["corp.example"])
+    );
+
+    email_domain_rules::Entity::replace(db, &rules(&[], &[]))
+        .await
+        .unwrap();
+    assert_eq!(
+        email_domain_rules::Entity::overrides(db).await.unwrap(),
+        None
+    );
+}
@@ -1,4 +1,8 @@
 mod users;
 
+mod auth_throttles;
+mod email_domain_rules;
 mod projects;
 mod repos;
+mod sync_runs;
+mod totp_credentials;
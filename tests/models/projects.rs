@@ -1,45 +1,225 @@
-use gooncityhub::app::App;
-use loco_rs::testing::prelude::*;
-use sea_orm::EntityTrait;
+use gooncityhub::{
+    app::App,
+    health::{self, Bucket, Normalization, Strategy},
+    models::{_entities::repos, health_configs, health_distributions, projects},
+};
+use loco_rs::{app::AppContext, testing::prelude::*};
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, EntityTrait};
 use serial_test::serial;
 
-macro_rules! configure_insta {
-    ($($expr:expr),*) => {
-        let mut settings = insta::Settings::clone_current();
-        settings.set_prepend_module_to_snapshot(false);
-        let _guard = settings.bind_to_scope();
-    };
+/// A repo with stats scaled from the default caps, so it scores `size`.
+async fn create_repo(ctx: &AppContext, project_id: i32, name: &str, size: f64) -> repos::Model {
+    #[allow(clippy::cast_possible_truncation)]
+    let scaled = |cap: f64| (cap * size / 100.0) as i32;
+    repos::ActiveModel {
+        project_id: Set(project_id),
+        forge: Set("github".to_string()),
+        host: Set("github.com".to_string()),
+        owner: Set("acme".to_string()),
+        name: Set(name.to_string()),
+        stars: Set(scaled(100.0)),
+        forks: Set(0),
+        issues: Set(0),
+        prs: Set(scaled(10.0)),
+        contributors: Set(scaled(10.0)),
+        commits_last_30d: Set(scaled(30.0)),
+        watchers: Set(0),
+        releases: Set(0),
+        last_fetch: Set(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .insert(&ctx.db)
+    .await
+    .unwrap()
 }
 
 #[tokio::test]
 #[serial]
-async fn test_calc_health() {
-    configure_insta!();
+async fn combines_repo_health_by_strategy() {
+    let boot = boot_test::<App>().await.unwrap();
+    let ctx = &boot.app_context;
+    let project = projects::ActiveModel {
+        name: Set("acme".to_string()),
+        owner: Set("acme".to_string()),
+        health: Set(0.),
+        last_fetch: Set(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .insert(&ctx.db)
+    .await
+    .unwrap();
+    let flagship = create_repo(ctx, project.id, "flagship", 100.0).await;
+    create_repo(ctx, project.id, "helper", 0.0).await;
+    create_repo(ctx, project.id, "tool", 50.0).await;
+
+    for (strategy, primary_repo_id, health, used) in [
+        ("average", None, 50.0, "average"),
+        ("stars", None, 83.33, "stars"),
+        ("activity", None, 83.33, "activity"),
+        ("primary", Some(flagship.id), 100.0, "primary"),
+        ("primary", None, 50.0, "average"),
+        ("max", None, 100.0, "max"),
+        ("median", None, 50.0, "median"),
+    ] {
+        let project = projects::ActiveModel {
+            id: Set(project.id),
+            health_strategy: Set(strategy.to_string()),
+            primary_repo_id: Set(primary_repo_id),
+            ..Default::default()
+        }
+        .update(&ctx.db)
+        .await
+        .unwrap();
+        let got = project.recalculate_health(&ctx.db).await.unwrap();
+        assert!((got - health).abs() < 0.01, "{strategy}: {got}");
 
+        let project = projects::Entity::find_by_id(project.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!((project.health - got).abs() < f32::EPSILON);
+        assert_eq!(
+            project.health_strategy_used.as_deref(),
+            Some(used),
+            "{strategy}"
+        );
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_calc_health() {
     let boot = boot_test::<App>().await.unwrap();
-    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let project = create_project(ctx, "acme", &[100.0, 0.0, 50.0]).await;
+    let repos = repos::Entity::find().all(&ctx.db).await.unwrap();
+    let flagship = repos.iter().find(|r| r.name == "acme-0").unwrap();
+
+    // with the caps the repos score 100, 0 and 50, as percentiles of the
+    // three of them 83.33, 0 and 50
+    for (normalization, expected) in [
+        (
+            Normalization::Caps,
+            [50.0, 83.33, 83.33, 100.0, 100.0, 50.0],
+        ),
+        (
+            Normalization::Percentile,
+            [44.44, 72.22, 72.22, 83.33, 83.33, 50.0],
+        ),
+    ] {
+        let config = health::Config {
+            normalization,
+            ..Default::default()
+        };
+        health_configs::Entity::replace(&ctx.db, &config)
+            .await
+            .unwrap();
+        // builds the distribution percentiles are measured against
+        projects::Entity::recalculate_all_health(&ctx.db, 2)
+            .await
+            .unwrap();
+
+        for (strategy, health) in [
+            Strategy::Average,
+            Strategy::Stars,
+            Strategy::Activity,
+            Strategy::Primary,
+            Strategy::Max,
+            Strategy::Median,
+        ]
+        .into_iter()
+        .zip(expected)
+        {
+            let project = projects::ActiveModel {
+                id: Set(project.id),
+                health_strategy: Set(strategy.as_str().to_string()),
+                primary_repo_id: Set(Some(flagship.id)),
+                ..Default::default()
+            }
+            .update(&ctx.db)
+            .await
+            .unwrap();
+            let got = project.recalculate_health(&ctx.db).await.unwrap();
+            assert!(
+                (got - health).abs() < 0.01,
+                "{normalization:?} {strategy:?}: {got}"
+            );
+        }
+    }
+}
 
-    let repo = gooncityhub::models::repos::Entity::fetch_from_github(
-        "XAMPPRocky",
-        "octocrab",
-        &boot.app_context.db,
-    )
+async fn create_project(ctx: &AppContext, name: &str, sizes: &[f64]) -> projects::Model {
+    let project = projects::ActiveModel {
+        name: Set(name.to_string()),
+        owner: Set("acme".to_string()),
+        health: Set(0.),
+        last_fetch: Set(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .insert(&ctx.db)
     .await
-    .expect("Should fetch repo successfully");
+    .unwrap();
+    for (i, size) in sizes.iter().enumerate() {
+        create_repo(ctx, project.id, &format!("{name}-{i}"), *size).await;
+    }
+    project
+}
 
-    let project = gooncityhub::models::projects::Entity::find_by_id(repo.project_id)
-        .one(&boot.app_context.db)
+async fn health_of(ctx: &AppContext, project: &projects::Model) -> f32 {
+    projects::Entity::find_by_id(project.id)
+        .one(&ctx.db)
         .await
         .unwrap()
-        .expect("Project should exist");
+        .unwrap()
+        .health
+}
+
+#[tokio::test]
+#[serial]
+async fn percentiles_separate_popular_repos() {
+    let boot = boot_test::<App>().await.unwrap();
+    let ctx = &boot.app_context;
+    let larger = create_project(ctx, "larger", &[400.0]).await;
+    let large = create_project(ctx, "large", &[200.0]).await;
+    let small = create_project(ctx, "small", &[10.0, 20.0]).await;
 
-    let health = project
-        .recalculate_health(&boot.app_context.db)
+    // both are past every cap
+    projects::Entity::recalculate_all_health(&ctx.db, 2)
         .await
-        .expect("Health calculation should succeed");
+        .unwrap();
+    assert!((health_of(ctx, &larger).await - 100.0).abs() < f32::EPSILON);
+    assert!((health_of(ctx, &large).await - 100.0).abs() < f32::EPSILON);
 
-    println!("Repo Health: {:#?}", repo.health());
-    println!("Project Health: {:#?}", health);
-    assert!(health > 10.);
-    assert_eq!(repo.health(), health);
+    let mut config = health::Config {
+        normalization: Normalization::Percentile,
+        ..Default::default()
+    };
+    health_configs::Entity::replace(&ctx.db, &config)
+        .await
+        .unwrap();
+    projects::Entity::recalculate_all_health(&ctx.db, 2)
+        .await
+        .unwrap();
+    let distribution = health_distributions::Entity::current(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        distribution.buckets.keys().collect::<Vec<_>>(),
+        vec!["all", "size:medium", "size:small"]
+    );
+    let against_all = health_of(ctx, &small).await;
+    assert!(health_of(ctx, &larger).await > health_of(ctx, &large).await);
+    assert!(health_of(ctx, &large).await > against_all);
+
+    // the small repos are only measured against each other
+    config.bucket = Bucket::Size;
+    health_configs::Entity::replace(&ctx.db, &config)
+        .await
+        .unwrap();
+    projects::Entity::recalculate_all_health(&ctx.db, 2)
+        .await
+        .unwrap();
+    assert!(health_of(ctx, &small).await > against_all);
 }
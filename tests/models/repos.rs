@@ -1,40 +1,354 @@
+use async_trait::async_trait;
 use gooncityhub::app::App;
-use gooncityhub::models::repos::Entity;
+use gooncityhub::forges::{self, Forge, RepoSource, RepoStats};
+use gooncityhub::models::_entities::activity_events;
+use gooncityhub::models::projects;
+use gooncityhub::models::repos::{ActiveModel, Entity};
 use loco_rs::testing::prelude::*;
+use sea_orm::ActiveModelTrait;
+use sea_orm::ActiveValue::Set;
 use sea_orm::ColumnTrait;
 use sea_orm::EntityTrait;
 use sea_orm::QueryFilter;
+use sea_orm::SqlErr;
 use serial_test::serial;
 
-macro_rules! configure_insta {
-    ($($expr:expr),*) => {
-        let mut settings = insta::Settings::clone_current();
-        settings.set_prepend_module_to_snapshot(false);
-        let _guard = settings.bind_to_scope();
-    };
+/// GitHub as it reports octocrab, without asking it.
+struct GitHubStub;
+
+#[async_trait]
+impl RepoSource for GitHubStub {
+    fn forge(&self) -> Forge {
+        Forge::Github
+    }
+
+    fn host(&self) -> String {
+        forges::github::HOST.to_string()
+    }
+
+    async fn fetch(&self, owner: &str, name: &str) -> forges::Result<RepoStats> {
+        Ok(RepoStats {
+            forge_id: Some(1),
+            owner: owner.to_string(),
+            name: name.to_string(),
+            stars: 120,
+            forks: 7,
+            issues: 4,
+            prs: 6,
+            contributors: 8,
+            commits_last_30d: 15,
+            watchers: 120,
+            releases: 2,
+            license: Some("MIT".to_string()),
+            archived: false,
+        })
+    }
 }
 
 #[tokio::test]
 #[serial]
 async fn test_fetch_github_repo() {
-    configure_insta!();
     let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
 
-    let repo = Entity::fetch_from_github("XAMPPRocky", "octocrab", &boot.app_context.db)
+    let repo = Entity::fetch(&GitHubStub, "XAMPPRocky", "octocrab", db)
         .await
         .expect("Should fetch repo successfully");
 
-    // Verify saved to DB
-    Entity::find()
+    let stored = Entity::find()
         .filter(gooncityhub::models::_entities::repos::Column::Owner.eq("XAMPPRocky"))
-        .one(&boot.app_context.db)
+        .one(db)
         .await
         .unwrap()
         .unwrap();
+    assert_eq!(stored, repo);
+    assert_eq!(repo.forge, "github");
+    assert_eq!(repo.host, "github.com");
+    assert_eq!(repo.forge_id, Some(1));
+    assert_eq!(repo.stars, 120);
+    assert_eq!(repo.prs, 6);
+    assert_eq!(repo.license.as_deref(), Some("MIT"));
+    // activity 0.5, community 0.8, adoption 1.0, maintenance 0.6 * 0.92
+    assert!((repo.health() - 66.3).abs() < 0.01, "{}", repo.health());
+    let project = projects::Entity::find_by_id(repo.project_id)
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!((project.health - repo.health()).abs() < f32::EPSILON);
+}
+
+/// A forge where the repo with id 99 currently lives at `owner/name`, or
+/// was deleted when `gone`.
+struct Upstream {
+    owner: &'static str,
+    name: &'static str,
+    gone: bool,
+}
+
+impl Upstream {
+    fn stats(&self) -> forges::Result<RepoStats> {
+        if self.gone {
+            return Err(forges::Error::NotFound("#99".to_string()));
+        }
+        Ok(RepoStats {
+            forge_id: Some(99),
+            owner: self.owner.to_string(),
+            name: self.name.to_string(),
+            stars: 10,
+            forks: 1,
+            issues: 0,
+            prs: 0,
+            contributors: 2,
+            commits_last_30d: 3,
+            watchers: 10,
+            releases: 0,
+            license: None,
+            archived: self.owner == "archive",
+        })
+    }
+}
+
+#[async_trait]
+impl RepoSource for Upstream {
+    fn forge(&self) -> Forge {
+        Forge::Gitea
+    }
+
+    fn host(&self) -> String {
+        "git.example.org".to_string()
+    }
+
+    /// Old names redirect to the current one, like forges do.
+    async fn fetch(&self, _owner: &str, _name: &str) -> forges::Result<RepoStats> {
+        self.stats()
+    }
+
+    async fn fetch_by_id(&self, id: i64) -> forges::Result<RepoStats> {
+        assert_eq!(id, 99);
+        self.stats()
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn fetch_updates_renamed_repo_in_place() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let before = Upstream {
+        owner: "acme",
+        name: "tool",
+        gone: false,
+    };
+    let repo = Entity::fetch(&before, "acme", "tool", db).await.unwrap();
+
+    let after = Upstream {
+        owner: "acme-labs",
+        name: "tool-ng",
+        gone: false,
+    };
+    let renamed = Entity::fetch(&after, "acme", "tool", db).await.unwrap();
+
+    assert_eq!(renamed.id, repo.id);
+    assert_eq!(renamed.project_id, repo.project_id);
+    assert_eq!(renamed.owner, "acme-labs");
+    assert_eq!(renamed.name, "tool-ng");
+    assert_eq!(renamed.forge_id, Some(99));
+    assert_eq!(Entity::find().all(db).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+#[serial]
+async fn refresh_follows_id_and_marks_deleted_repo_gone() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let repo = Entity::fetch(
+        &Upstream {
+            owner: "acme",
+            name: "tool",
+            gone: false,
+        },
+        "acme",
+        "tool",
+        db,
+    )
+    .await
+    .unwrap();
+
+    let archived = repo
+        .refresh(
+            &Upstream {
+                owner: "archive",
+                name: "tool",
+                gone: false,
+            },
+            db,
+        )
+        .await
+        .unwrap();
+    assert_eq!(archived.id, repo.id);
+    assert_eq!(archived.owner, "archive");
+    assert!(archived.archived);
+    assert_eq!(archived.sync_status, "ok");
+
+    let res = archived
+        .refresh(
+            &Upstream {
+                owner: "archive",
+                name: "tool",
+                gone: true,
+            },
+            db,
+        )
+        .await;
+    assert!(res.is_err());
+    let gone = Entity::find_by_id(repo.id).one(db).await.unwrap().unwrap();
+    assert_eq!(gone.sync_status, "gone");
+}
+
+/// A forge without stable ids that reports the repo under `owner`.
+struct NoIds {
+    owner: &'static str,
+}
+
+#[async_trait]
+impl RepoSource for NoIds {
+    fn forge(&self) -> Forge {
+        Forge::Gitea
+    }
+
+    fn host(&self) -> String {
+        "git.example.org".to_string()
+    }
+
+    async fn fetch(&self, _owner: &str, name: &str) -> forges::Result<RepoStats> {
+        Ok(RepoStats {
+            forge_id: None,
+            owner: self.owner.to_string(),
+            name: name.to_string(),
+            stars: 1,
+            forks: 0,
+            issues: 0,
+            prs: 0,
+            contributors: 1,
+            commits_last_30d: 0,
+            watchers: 1,
+            releases: 0,
+            license: None,
+            archived: false,
+        })
+    }
+}
+
+fn gitea_repo(owner: &str, name: &str, hours_ago: i64) -> ActiveModel {
+    ActiveModel {
+        forge: Set("gitea".to_string()),
+        host: Set("git.example.org".to_string()),
+        owner: Set(owner.to_string()),
+        name: Set(name.to_string()),
+        stars: Set(0),
+        forks: Set(0),
+        issues: Set(0),
+        prs: Set(0),
+        contributors: Set(0),
+        commits_last_30d: Set(0),
+        watchers: Set(0),
+        releases: Set(0),
+        last_fetch: Set((chrono::Utc::now() - chrono::Duration::hours(hours_ago)).naive_utc()),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn refetch_updates_existing_row() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+
+    let repo = Entity::fetch(&NoIds { owner: "acme" }, "acme", "tool", db)
+        .await
+        .unwrap();
+    let again = Entity::fetch(&NoIds { owner: "Acme" }, "Acme", "tool", db)
+        .await
+        .unwrap();
+
+    assert_eq!(again.id, repo.id);
+    assert_eq!(again.project_id, repo.project_id);
+    assert_eq!(again.owner, "Acme");
+    assert_eq!(Entity::find().all(db).await.unwrap().len(), 1);
+    assert_eq!(projects::Entity::find().all(db).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+#[serial]
+async fn rejects_duplicate_rows() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+
+    gitea_repo("acme", "tool", 0).insert(db).await.unwrap();
+    for (owner, name) in [("acme", "tool"), ("ACME", "Tool")] {
+        let err = gitea_repo(owner, name, 0).insert(db).await.unwrap_err();
+        assert!(matches!(
+            err.sql_err(),
+            Some(SqlErr::UniqueConstraintViolation(_))
+        ));
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn merges_duplicate_rows() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+
+    // the same repo before and after a rename
+    let old = ActiveModel {
+        forge_id: Set(Some(7)),
+        ..gitea_repo("acme", "tool-old", 2)
+    }
+    .insert(db)
+    .await
+    .unwrap();
+    let kept = ActiveModel {
+        forge_id: Set(Some(7)),
+        ..gitea_repo("acme", "tool", 1)
+    }
+    .insert(db)
+    .await
+    .unwrap();
+    let other = gitea_repo("acme", "other", 3).insert(db).await.unwrap();
+    activity_events::ActiveModel {
+        repo_id: Set(old.id),
+        kind: Set("star".to_string()),
+        occurred_at: Set(chrono::Utc::now().into()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap();
+
+    let merges = Entity::merge_duplicates(db).await.unwrap();
+    assert_eq!(merges.len(), 1);
+    assert_eq!(merges[0].kept.id, kept.id);
+    assert_eq!(merges[0].removed, vec![old.id]);
+    assert_eq!(merges[0].removed_projects, vec![old.project_id]);
+
+    let ids: Vec<i32> = Entity::find()
+        .all(db)
+        .await
+        .unwrap()
+        .iter()
+        .map(|r| r.id)
+        .collect();
+    assert_eq!(ids.len(), 2);
+    assert!(ids.contains(&kept.id) && ids.contains(&other.id));
+    assert!(projects::Entity::find_by_id(old.project_id)
+        .one(db)
+        .await
+        .unwrap()
+        .is_none());
+    let events = activity_events::Entity::find().all(db).await.unwrap();
+    assert_eq!(events[0].repo_id, kept.id);
 
-    println!("{:#?}", repo);
-    assert!(repo.stars > 0);
-    assert!(repo.prs > 0);
-    assert_eq!(repo.name, "octocrab");
-    assert_eq!(repo.owner, "XAMPPRocky");
+    assert!(Entity::merge_duplicates(db).await.unwrap().is_empty());
 }
@@ -0,0 +1,197 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use gooncityhub::{
+    app::App,
+    forges::{self, Error, Forge, RepoSource, RepoStats},
+    models::{
+        _entities::repos,
+        sync_runs::{self, FAILURES_BEFORE_MARKING},
+    },
+};
+use loco_rs::prelude::*;
+use serial_test::serial;
+
+/// A source that answers every fetch with a canned result.
+struct Canned {
+    result: fn(&str, &str) -> forges::Result<RepoStats>,
+}
+
+#[async_trait]
+impl RepoSource for Canned {
+    fn forge(&self) -> Forge {
+        Forge::Gitea
+    }
+
+    fn host(&self) -> String {
+        "git.example.org".to_string()
+    }
+
+    async fn fetch(&self, owner: &str, name: &str) -> forges::Result<RepoStats> {
+        (self.result)(owner, name)
+    }
+
+    fn api_calls(&self) -> u32 {
+        3
+    }
+}
+
+fn stats(owner: &str, name: &str) -> forges::Result<RepoStats> {
+    Ok(RepoStats {
+        forge_id: None,
+        owner: owner.to_string(),
+        name: name.to_string(),
+        stars: 5,
+        forks: 0,
+        issues: 0,
+        prs: 0,
+        contributors: 1,
+        commits_last_30d: 1,
+        watchers: 0,
+        releases: 0,
+        license: None,
+        archived: false,
+    })
+}
+
+fn not_found(owner: &str, name: &str) -> forges::Result<RepoStats> {
+    Err(Error::NotFound(format!("{owner}/{name}")))
+}
+
+fn broken(_: &str, _: &str) -> forges::Result<RepoStats> {
+    Err(Error::Git("boom".to_string()))
+}
+
+#[tokio::test]
+#[serial]
+async fn records_successful_fetch() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+
+    let repo = repos::Entity::fetch(&Canned { result: stats }, "acme", "tool", db)
+        .await
+        .unwrap();
+
+    let runs = sync_runs::Entity::find().all(db).await.unwrap();
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0].outcome, "ok");
+    assert_eq!(runs[0].repo_id, Some(repo.id));
+    assert_eq!(runs[0].forge, "gitea");
+    assert_eq!(runs[0].host, "git.example.org");
+    assert!(runs[0].error.is_none());
+    assert!(runs[0].finished_at >= runs[0].started_at);
+}
+
+#[tokio::test]
+#[serial]
+async fn records_failed_fetch_of_untracked_repo() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+
+    let res = repos::Entity::fetch(&Canned { result: broken }, "acme", "tool", db).await;
+
+    assert!(res.is_err());
+    let failures = sync_runs::Entity::recent_failures(db, 10).await.unwrap();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].outcome, "failed");
+    assert_eq!(failures[0].error.as_deref(), Some("git: boom"));
+    assert_eq!(failures[0].repo_id, None);
+}
+
+#[tokio::test]
+#[serial]
+async fn marks_repo_not_found_after_repeated_404s() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let repo = repos::Entity::fetch(&Canned { result: stats }, "acme", "tool", db)
+        .await
+        .unwrap();
+
+    for _ in 1..FAILURES_BEFORE_MARKING {
+        let _ = repos::Entity::fetch(&Canned { result: not_found }, "acme", "tool", db).await;
+    }
+    let repo_now = repos::Entity::find_by_id(repo.id)
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(repo_now.sync_status, "ok");
+    assert_eq!(repo_now.failed_syncs, FAILURES_BEFORE_MARKING - 1);
+
+    let _ = repos::Entity::fetch(&Canned { result: not_found }, "acme", "tool", db).await;
+    let repo_now = repos::Entity::find_by_id(repo.id)
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(repo_now.sync_status, "not_found");
+
+    let runs = sync_runs::Entity::recent_failures(db, 10).await.unwrap();
+    assert_eq!(
+        runs.len(),
+        usize::try_from(FAILURES_BEFORE_MARKING).unwrap()
+    );
+    assert!(runs.iter().all(|r| r.repo_id == Some(repo.id)));
+}
+
+#[tokio::test]
+#[serial]
+async fn counts_failures_of_a_repo_named_in_other_case() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let repo = repos::Entity::fetch(&Canned { result: stats }, "Acme", "Tool", db)
+        .await
+        .unwrap();
+
+    let _ = repos::Entity::fetch(&Canned { result: broken }, "acme", "tool", db).await;
+
+    let failed = repos::Entity::find_by_id(repo.id)
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(failed.failed_syncs, 1);
+    let failures = sync_runs::Entity::recent_failures(db, 10).await.unwrap();
+    assert_eq!(failures[0].repo_id, Some(repo.id));
+}
+
+#[tokio::test]
+#[serial]
+async fn marks_repo_stale_and_resets_on_success() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let repo = repos::Entity::fetch(&Canned { result: stats }, "acme", "tool", db)
+        .await
+        .unwrap();
+
+    for _ in 0..FAILURES_BEFORE_MARKING {
+        let _ = repos::Entity::fetch(&Canned { result: broken }, "acme", "tool", db).await;
+    }
+    let stale = repos::Entity::find_by_id(repo.id)
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stale.sync_status, "stale");
+
+    let attempt = sync_runs::Attempt {
+        forge: Forge::Gitea,
+        host: stale.host.clone(),
+        owner: stale.owner.clone(),
+        name: stale.name.clone(),
+        started_at: Utc::now(),
+        api_calls: 3,
+        repo_id: Some(stale.id),
+        by_id: false,
+    };
+    let run = sync_runs::Entity::record(db, attempt, Ok(&stale))
+        .await
+        .unwrap();
+    assert_eq!(run.api_calls, 3);
+    let synced = repos::Entity::find_by_id(repo.id)
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(synced.sync_status, "ok");
+    assert_eq!(synced.failed_syncs, 0);
+}
@@ -0,0 +1,196 @@
+use gooncityhub::{
+    app::App,
+    models::{recovery_codes, totp_credentials, users},
+    totp,
+};
+use loco_rs::testing::prelude::*;
+use serial_test::serial;
+
+/// The SHA1 secret of the RFC 6238 test vectors.
+const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+#[test]
+fn matches_rfc_6238_vectors() {
+    for (time, code) in [
+        (59, "287082"),
+        (1_111_111_109, "081804"),
+        (1_234_567_890, "005924"),
+        (2_000_000_000, "279037"),
+    ] {
+        assert_eq!(totp::code_at(RFC_SECRET, totp::step_at(time)), code);
+    }
+}
+
+#[test]
+fn base32_round_trips() {
+    assert_eq!(totp::base32_encode(b"foobar"), "MZXW6YTBOI");
+    assert_eq!(
+        totp::base32_decode("mzxw 6ytb oi=="),
+        Some(b"foobar".to_vec())
+    );
+    assert_eq!(totp::base32_decode("not base32!"), None);
+    let secret: Vec<u8> = (0..20).collect();
+    assert_eq!(
+        totp::base32_decode(&totp::base32_encode(&secret)),
+        Some(secret)
+    );
+}
+
+#[test]
+fn accepts_codes_within_the_drift_window() {
+    let now = 1_111_111_109;
+    let step = totp::step_at(now);
+    let code = |step| totp::code_at(RFC_SECRET, step);
+
+    assert_eq!(totp::verify(RFC_SECRET, &code(step), now, None), Some(step));
+    // a slow clock or a code typed in at the last second
+    assert_eq!(
+        totp::verify(RFC_SECRET, &code(step - 1), now, None),
+        Some(step - 1)
+    );
+    // a fast clock
+    assert_eq!(
+        totp::verify(RFC_SECRET, &code(step + 1), now, None),
+        Some(step + 1)
+    );
+    assert_eq!(totp::verify(RFC_SECRET, &code(step - 2), now, None), None);
+    assert_eq!(totp::verify(RFC_SECRET, &code(step + 2), now, None), None);
+    // still good at the last second of the next period
+    let later = (step + 2) * totp::PERIOD - 1;
+    assert_eq!(
+        totp::verify(RFC_SECRET, &code(step), later, None),
+        Some(step)
+    );
+    assert_eq!(totp::verify(RFC_SECRET, &code(step), later + 1, None), None);
+
+    assert_eq!(totp::verify(RFC_SECRET, "12345", now, None), None);
+    assert_eq!(totp::verify(RFC_SECRET, "abcdef", now, None), None);
+}
+
+#[test]
+fn refuses_used_codes() {
+    let now = 1_111_111_109;
+    let step = totp::step_at(now);
+    let code = |step| totp::code_at(RFC_SECRET, step);
+
+    assert_eq!(totp::verify(RFC_SECRET, &code(step), now, Some(step)), None);
+    assert_eq!(
+        totp::verify(RFC_SECRET, &code(step - 1), now, Some(step)),
+        None
+    );
+    assert_eq!(
+        totp::verify(RFC_SECRET, &code(step + 1), now, Some(step)),
+        Some(step + 1)
+    );
+}
+
+#[test]
+fn builds_provisioning_uris() {
+    assert_eq!(
+        totp::provisioning_uri(b"foobar", "gooncityhub", "a@example.com"),
+        "otpauth://totp/gooncityhub:a@example.com?secret=MZXW6YTBOI&issuer=gooncityhub\
+         &algorithm=SHA1&digits=6&period=30"
+    );
+}
+
+async fn create_user(db: &sea_orm::DatabaseConnection) -> users::Model {
+    users::Model::create_with_password(
+        db,
+        &users::RegisterParams {
+            email: "totp@example.com".to_string(),
+            password: "12341234".to_string(),
+            name: "totp".to_string(),
+        },
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+#[serial]
+async fn can_enroll_confirm_and_disable() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let user = create_user(db).await;
+
+    let first = totp_credentials::Entity::enroll(db, user.id).await.unwrap();
+    let credential = totp_credentials::Entity::enroll(db, user.id).await.unwrap();
+    assert_ne!(first.secret, credential.secret);
+    assert!(totp_credentials::Entity::enabled_for(db, user.id)
+        .await
+        .unwrap()
+        .is_none());
+
+    let now = 1_111_111_109;
+    let step = totp::step_at(now);
+    let code = |step| totp::code_at(&credential.secret_bytes(), step);
+    assert!(credential.check_at(db, &code(step), now).await.unwrap());
+    let reload = || async {
+        totp_credentials::Entity::for_user(db, user.id)
+            .await
+            .unwrap()
+            .unwrap()
+    };
+    // the same code does not work twice, also not in the next step
+    let credential = reload().await;
+    assert!(!credential
+        .check_at(db, &code(step), now + totp::PERIOD)
+        .await
+        .unwrap());
+    assert!(credential
+        .check_at(db, &code(step + 1), now + totp::PERIOD)
+        .await
+        .unwrap());
+    let credential = reload().await;
+    assert_eq!(
+        credential.last_used_step,
+        Some(i64::try_from(step + 1).unwrap())
+    );
+    credential.confirm(db).await.unwrap();
+    assert!(totp_credentials::Entity::enabled_for(db, user.id)
+        .await
+        .unwrap()
+        .is_some());
+
+    totp_credentials::Entity::disable(db, user.id)
+        .await
+        .unwrap();
+    assert!(totp_credentials::Entity::for_user(db, user.id)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+#[serial]
+async fn recovery_codes_work_once() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let user = create_user(db).await;
+
+    let codes = recovery_codes::Entity::regenerate(db, user.id)
+        .await
+        .unwrap();
+    assert_eq!(codes.len(), recovery_codes::COUNT);
+    assert!(
+        recovery_codes::Entity::redeem(db, user.id, &codes[0].to_uppercase())
+            .await
+            .unwrap()
+    );
+    assert!(!recovery_codes::Entity::redeem(db, user.id, &codes[0])
+        .await
+        .unwrap());
+    assert!(!recovery_codes::Entity::redeem(db, user.id, "aaaaa-bbbbb")
+        .await
+        .unwrap());
+
+    let fresh = recovery_codes::Entity::regenerate(db, user.id)
+        .await
+        .unwrap();
+    assert!(!recovery_codes::Entity::redeem(db, user.id, &codes[1])
+        .await
+        .unwrap());
+    assert!(recovery_codes::Entity::redeem(db, user.id, &fresh[1])
+        .await
+        .unwrap());
+}
@@ -0,0 +1,375 @@
+use gooncityhub::{
+    app::App,
+    models::{audit_events, data_exports, email_changes, project_members, projects, users},
+};
+use loco_rs::{prelude::*, TestServer};
+use serde_json::json;
+use serial_test::serial;
+
+use super::prepare_data;
+
+async fn send(
+    request: &TestServer,
+    token: &str,
+    method: &str,
+    path: &str,
+    body: serde_json::Value,
+) -> (u16, serde_json::Value) {
+    let (auth_key, auth_value) = prepare_data::auth_header(token);
+    let path = format!("/api/account{path}");
+    let request = match method {
+        "PATCH" => request.patch(&path),
+        "DELETE" => request.delete(&path),
+        _ => request.post(&path),
+    };
+    let response = request.add_header(auth_key, auth_value).json(&body).await;
+    let status = response.status_code().as_u16();
+    let body = if status == 200 {
+        response.json()
+    } else {
+        json!(null)
+    };
+    (status, body)
+}
+
+async fn login(request: &TestServer, email: &str, password: &str) -> u16 {
+    request
+        .post("/api/auth/login")
+        .json(&json!({ "email": email, "password": password }))
+        .await
+        .status_code()
+        .as_u16()
+}
+
+#[tokio::test]
+#[serial]
+async fn can_update_profile() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+
+        let (status, updated) = send(
+            &request,
+            &user.token,
+            "PATCH",
+            "",
+            json!({ "name": " Ada " }),
+        )
+        .await;
+        assert_eq!(status, 200);
+        assert_eq!(updated["name"], "Ada");
+        let (status, _) = send(&request, &user.token, "PATCH", "", json!({ "name": "A" })).await;
+        assert_eq!(status, 400);
+
+        let response = request
+            .patch("/api/account")
+            .json(&json!({ "name": "Eve" }))
+            .await;
+        assert_eq!(response.status_code(), 401);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_change_email_after_confirming_it() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+
+        let (status, _) = send(
+            &request,
+            &user.token,
+            "POST",
+            "/email",
+            json!({ "email": "not an email" }),
+        )
+        .await;
+        assert_eq!(status, 400);
+        let (status, _) = send(
+            &request,
+            &user.token,
+            "POST",
+            "/email",
+            json!({ "email": prepare_data::USER_EMAIL }),
+        )
+        .await;
+        assert_eq!(status, 400);
+
+        let (status, _) = send(
+            &request,
+            &user.token,
+            "POST",
+            "/email",
+            json!({ "email": "new@loco.com" }),
+        )
+        .await;
+        assert_eq!(status, 200);
+        let deliveries = ctx.mailer.as_ref().unwrap().deliveries();
+        assert!(deliveries
+            .messages
+            .last()
+            .unwrap()
+            .contains("To: new@loco.com"));
+        // nothing changes until the new address is confirmed
+        assert_eq!(
+            login(
+                &request,
+                prepare_data::USER_EMAIL,
+                prepare_data::USER_PASSWORD
+            )
+            .await,
+            200
+        );
+
+        let change = email_changes::Entity::find()
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        let response = request.get("/api/account/email/made-up").await;
+        assert_eq!(response.status_code(), 401);
+        let response = request
+            .get(&format!("/api/account/email/{}", change.token))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(
+            login(&request, "new@loco.com", prepare_data::USER_PASSWORD).await,
+            200
+        );
+        assert_eq!(
+            login(
+                &request,
+                prepare_data::USER_EMAIL,
+                prepare_data::USER_PASSWORD
+            )
+            .await,
+            401
+        );
+        // links work once
+        let response = request
+            .get(&format!("/api/account/email/{}", change.token))
+            .await;
+        assert_eq!(response.status_code(), 401);
+
+        let changed = audit_events::Entity::find()
+            .filter(audit_events::Column::Action.eq(audit_events::EMAIL_CHANGED))
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(changed.actor_id, Some(user.user.id));
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn changing_password_takes_the_current_one() {
+    request::<App, _, _>(|request, ctx| async move {
+        let first = prepare_data::init_user_login(&request, &ctx).await;
+        let second = prepare_data::init_user_login(&request, &ctx).await;
+
+        let (status, _) = send(
+            &request,
+            &second.token,
+            "POST",
+            "/password",
+            json!({ "current_password": "wrong", "password": "new-password" }),
+        )
+        .await;
+        assert_eq!(status, 401);
+        let (status, _) = send(
+            &request,
+            &second.token,
+            "POST",
+            "/password",
+            json!({ "current_password": prepare_data::USER_PASSWORD, "password": "new-password" }),
+        )
+        .await;
+        assert_eq!(status, 200);
+
+        assert_eq!(
+            login(&request, prepare_data::USER_EMAIL, "new-password").await,
+            200
+        );
+        assert_eq!(
+            login(
+                &request,
+                prepare_data::USER_EMAIL,
+                prepare_data::USER_PASSWORD
+            )
+            .await,
+            401
+        );
+        // the other session ended, this one goes on
+        let refresh = |token: String| {
+            let request = &request;
+            async move {
+                request
+                    .post("/api/auth/refresh")
+                    .json(&json!({ "refresh_token": token }))
+                    .await
+                    .status_code()
+            }
+        };
+        assert_eq!(refresh(first.refresh_token).await, 401);
+        assert_eq!(refresh(second.refresh_token).await, 200);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_delete_account() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+
+        let (status, _) = send(
+            &request,
+            &user.token,
+            "DELETE",
+            "",
+            json!({ "password": "wrong" }),
+        )
+        .await;
+        assert_eq!(status, 401);
+        let (status, _) = send(
+            &request,
+            &user.token,
+            "DELETE",
+            "",
+            json!({ "password": prepare_data::USER_PASSWORD }),
+        )
+        .await;
+        assert_eq!(status, 200);
+
+        assert!(
+            users::Model::find_by_email(&ctx.db, prepare_data::USER_EMAIL)
+                .await
+                .is_err()
+        );
+        let deleted = audit_events::Entity::find()
+            .filter(audit_events::Column::Action.eq(audit_events::ACCOUNT_DELETED))
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(deleted.target, Some(user.user.pid.to_string()));
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn sole_owners_can_not_leave_members_behind() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let other = users::Model::create_with_password(
+            &ctx.db,
+            &users::RegisterParams {
+                email: "other@loco.com".to_string(),
+                password: "12341234".to_string(),
+                name: "other".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+        let project = projects::ActiveModel {
+            name: Set("Forges".to_string()),
+            owner: Set("forgejo".to_string()),
+            health: Set(0.0),
+            last_fetch: Set(chrono::Utc::now().naive_utc()),
+            ..Default::default()
+        }
+        .insert(&ctx.db)
+        .await
+        .unwrap();
+        project_members::Entity::grant(
+            &ctx.db,
+            project.id,
+            user.user.id,
+            project_members::Role::Owner,
+        )
+        .await
+        .unwrap();
+        project_members::Entity::grant(
+            &ctx.db,
+            project.id,
+            other.id,
+            project_members::Role::Viewer,
+        )
+        .await
+        .unwrap();
+
+        let delete = || {
+            send(
+                &request,
+                &user.token,
+                "DELETE",
+                "",
+                json!({ "password": prepare_data::USER_PASSWORD }),
+            )
+        };
+        assert_eq!(delete().await.0, 400);
+        project_members::Entity::grant(&ctx.db, project.id, other.id, project_members::Role::Owner)
+            .await
+            .unwrap();
+        assert_eq!(delete().await.0, 200);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_export_data() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let response = request
+            .post("/api/account/export")
+            .add_header(auth_key, auth_value)
+            .await;
+        assert_eq!(response.status_code(), 202);
+
+        // workers run in the foreground in tests, the export is ready
+        let export = data_exports::Entity::find()
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        let deliveries = ctx.mailer.as_ref().unwrap().deliveries();
+        assert!(deliveries
+            .messages
+            .last()
+            .unwrap()
+            .contains(&format!("/api/account/export/{}", export.token)));
+
+        let response = request
+            .get(&format!("/api/account/export/{}", export.token))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        assert!(response
+            .header("content-disposition")
+            .to_str()
+            .unwrap()
+            .starts_with("attachment"));
+        let archive: serde_json::Value = response.json();
+        assert_eq!(archive["profile"]["email"], prepare_data::USER_EMAIL);
+        assert_eq!(archive["sessions"].as_array().unwrap().len(), 1);
+        assert!(archive["api_keys"].as_array().unwrap().is_empty());
+        assert!(archive["profile"].get("password").is_none());
+
+        let response = request.get("/api/account/export/made-up").await;
+        assert_eq!(response.status_code(), 404);
+        let mut expired = export.into_active_model();
+        expired.expires_at = Set(Some(
+            (chrono::Utc::now() - chrono::Duration::minutes(1)).into(),
+        ));
+        let expired = expired.update(&ctx.db).await.unwrap();
+        let response = request
+            .get(&format!("/api/account/export/{}", expired.token))
+            .await;
+        assert_eq!(response.status_code(), 404);
+    })
+    .await;
+}
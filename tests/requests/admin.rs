@@ -0,0 +1,472 @@
+use gooncityhub::{
+    app::App,
+    forges::{Error, Forge},
+    models::{
+        _entities::repos,
+        api_keys::{self, Scope},
+        audit_events, projects, sessions, sync_runs, users,
+    },
+};
+use loco_rs::{app::AppContext, prelude::*};
+use sea_orm::QueryOrder;
+use serde_json::json;
+use serial_test::serial;
+
+use super::prepare_data;
+
+async fn create_user(ctx: &AppContext, email: &str) -> users::Model {
+    users::Model::create_with_password(
+        &ctx.db,
+        &users::RegisterParams {
+            email: email.to_string(),
+            password: "12341234".to_string(),
+            name: "other".to_string(),
+        },
+    )
+    .await
+    .unwrap()
+}
+
+async fn last_event(ctx: &AppContext) -> audit_events::Model {
+    audit_events::Entity::find()
+        .order_by_desc(audit_events::Column::Id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+#[serial]
+async fn can_list_sync_failures() {
+    request::<App, _, _>(|request, ctx| async move {
+        let admin = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&admin.token);
+        let attempt = sync_runs::Attempt {
+            forge: Forge::Gitlab,
+            host: "gitlab.com".to_string(),
+            owner: "acme".to_string(),
+            name: "gone".to_string(),
+            started_at: chrono::Utc::now(),
+            api_calls: 1,
+            repo_id: None,
+            by_id: false,
+        };
+        sync_runs::Entity::record(&ctx.db, attempt, Err(&Error::Git("timed out".to_string())))
+            .await
+            .unwrap();
+
+        let response = request
+            .get("/admin/sync_failures")
+            .add_header(auth_key, auth_value)
+            .await;
+
+        assert_eq!(response.status_code(), 200);
+        let body = response.text();
+        assert!(body.contains("gitlab.com/acme/gone"), "{body}");
+        assert!(body.contains("git: timed out"));
+        assert!(body.contains(r#"id="no-marked""#));
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn admin_pages_need_an_admin() {
+    request::<App, _, _>(|request, ctx| async move {
+        let response = request.get("/admin/users").await;
+        assert_eq!(response.status_code(), 401);
+
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        for path in ["/admin/users", "/admin/health", "/api/v1/admin/users"] {
+            let response = request
+                .get(path)
+                .add_header(auth_key.clone(), auth_value.clone())
+                .await;
+            assert_eq!(response.status_code(), 403, "{path}");
+        }
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn admin_actions_need_a_session() {
+    request::<App, _, _>(|request, ctx| async move {
+        let admin = prepare_data::init_admin_login(&request, &ctx).await;
+        let key = api_keys::Entity::issue(&ctx.db, admin.user.id, "ci", &Scope::ALL)
+            .await
+            .unwrap();
+        let (auth_key, auth_value) = prepare_data::auth_header(&key.secret);
+
+        let response = request
+            .get("/api/v1/admin/users")
+            .add_header(auth_key, auth_value)
+            .await;
+
+        assert_eq!(response.status_code(), 403);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_grant_and_revoke_admin() {
+    request::<App, _, _>(|request, ctx| async move {
+        let admin = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&admin.token);
+        let other = create_user(&ctx, "other@example.com").await;
+
+        let response = request
+            .post(&format!("/admin/users/{}/admin", other.pid))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .form(&[("is_admin", "true")])
+            .await;
+        assert_eq!(response.status_code(), 303);
+        let other = users::Model::find_by_pid(&ctx.db, &other.pid.to_string())
+            .await
+            .unwrap();
+        assert!(other.is_admin);
+        let event = last_event(&ctx).await;
+        assert_eq!(event.action, audit_events::ADMIN_GRANTED);
+        assert_eq!(event.actor_id, Some(admin.user.id));
+        assert_eq!(event.target, Some(other.pid.to_string()));
+
+        let response = request
+            .patch(&format!("/api/v1/admin/users/{}", other.pid))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&json!({ "is_admin": false }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(response.json::<serde_json::Value>()["is_admin"], false);
+        assert_eq!(last_event(&ctx).await.action, audit_events::ADMIN_REVOKED);
+
+        let response = request
+            .patch(&format!("/api/v1/admin/users/{}", admin.user.pid))
+            .add_header(auth_key, auth_value)
+            .json(&json!({ "is_admin": false }))
+            .await;
+        assert_eq!(response.status_code(), 422);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn admin_console_works_from_a_browser() {
+    request::<App, _, _>(|request, ctx| async move {
+        let admin = prepare_data::init_admin_login(&request, &ctx).await;
+        let other = create_user(&ctx, "other@example.com").await;
+        let session =
+            sessions::Entity::start(&ctx.db, &sessions::Settings::default(), other.id, None)
+                .await
+                .unwrap()
+                .session;
+
+        let response = request
+            .get("/admin/users")
+            .add_cookie(admin.cookie.clone())
+            .await;
+        assert_eq!(response.status_code(), 200);
+        assert!(response.text().contains("other@example.com"));
+        let csrf_token = prepare_data::csrf_token(&response.text());
+
+        // a form another site sends along with the cookie
+        let response = request
+            .post(&format!("/admin/users/{}/admin", other.pid))
+            .add_cookie(admin.cookie.clone())
+            .form(&[("is_admin", "true")])
+            .await;
+        assert_eq!(response.status_code(), 403);
+
+        let response = request
+            .post(&format!("/admin/users/{}/admin", other.pid))
+            .add_cookie(admin.cookie.clone())
+            .form(&[("is_admin", "true"), ("csrf_token", &csrf_token)])
+            .await;
+        assert_eq!(response.status_code(), 303);
+        assert!(
+            users::Model::find_by_pid(&ctx.db, &other.pid.to_string())
+                .await
+                .unwrap()
+                .is_admin
+        );
+
+        let response = request
+            .post(&format!("/admin/users/{}/sessions/revoke", other.pid))
+            .add_cookie(admin.cookie.clone())
+            .form(&[("csrf_token", &csrf_token)])
+            .await;
+        assert_eq!(response.status_code(), 303);
+        let session = sessions::Entity::find_by_id(session.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(session.revoked_at.is_some());
+
+        let response = request
+            .post("/admin/repos/4242/sync")
+            .add_cookie(admin.cookie.clone())
+            .form(&[("csrf_token", &csrf_token)])
+            .await;
+        assert_eq!(response.status_code(), 404);
+
+        let response = request.get("/admin/health").add_cookie(admin.cookie).await;
+        assert_eq!(response.status_code(), 200);
+        assert!(!prepare_data::csrf_token(&response.text()).is_empty());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_list_users() {
+    request::<App, _, _>(|request, ctx| async move {
+        let admin = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&admin.token);
+        create_user(&ctx, "other@example.com").await;
+
+        let response = request
+            .get("/api/v1/admin/users?is_admin=true")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["pagination"]["total_items"], 1);
+        assert_eq!(body["results"][0]["email"], prepare_data::USER_EMAIL);
+        assert!(body["results"][0].get("password").is_none());
+
+        let response = request
+            .get("/admin/users")
+            .add_header(auth_key, auth_value)
+            .await;
+        assert_eq!(response.status_code(), 200);
+        assert!(response.text().contains("other@example.com"));
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_sign_a_user_out_everywhere() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let admin = create_user(&ctx, "admin@example.com")
+            .await
+            .into_active_model()
+            .set_admin(&ctx.db, true)
+            .await
+            .unwrap();
+        let login = request
+            .post("/api/auth/login")
+            .json(&json!({ "email": admin.email, "password": "12341234" }))
+            .await;
+        let token = login.json::<serde_json::Value>()["token"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let (auth_key, auth_value) = prepare_data::auth_header(&token);
+
+        let response = request
+            .delete(&format!("/api/v1/admin/users/{}/sessions", user.user.pid))
+            .add_header(auth_key, auth_value)
+            .await;
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(response.json::<serde_json::Value>()["revoked"], 1);
+        assert_eq!(
+            last_event(&ctx).await.action,
+            audit_events::SESSIONS_REVOKED
+        );
+
+        let response = request
+            .post("/api/auth/refresh")
+            .json(&json!({ "refresh_token": user.refresh_token }))
+            .await;
+        assert_eq!(response.status_code(), 401);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_not_sync_a_missing_repo() {
+    request::<App, _, _>(|request, ctx| async move {
+        let admin = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&admin.token);
+
+        let response = request
+            .post("/api/v1/admin/repos/4242/sync")
+            .add_header(auth_key, auth_value)
+            .await;
+
+        assert_eq!(response.status_code(), 404);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_change_how_health_is_scored() {
+    request::<App, _, _>(|request, ctx| async move {
+        let admin = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&admin.token);
+        let project = projects::ActiveModel {
+            name: Set("acme".to_string()),
+            owner: Set("acme".to_string()),
+            health: Set(0.),
+            last_fetch: Set(chrono::Utc::now().naive_utc()),
+            ..Default::default()
+        }
+        .insert(&ctx.db)
+        .await
+        .unwrap();
+        repos::ActiveModel {
+            project_id: Set(project.id),
+            forge: Set("github".to_string()),
+            host: Set("github.com".to_string()),
+            owner: Set("acme".to_string()),
+            name: Set("tool".to_string()),
+            stars: Set(10),
+            forks: Set(0),
+            issues: Set(0),
+            prs: Set(0),
+            contributors: Set(0),
+            commits_last_30d: Set(0),
+            watchers: Set(0),
+            releases: Set(0),
+            last_fetch: Set(chrono::Utc::now().naive_utc()),
+            ..Default::default()
+        }
+        .insert(&ctx.db)
+        .await
+        .unwrap();
+
+        let response = request
+            .get("/api/v1/admin/health")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let mut config: serde_json::Value = response.json();
+        assert_eq!(config["caps"]["stars"], 100.0);
+
+        // only stars count, and 10 of them are enough
+        config["weights"] = json!({
+            "activity": 0.0,
+            "community": 0.0,
+            "adoption": 1.0,
+            "maintenance": 0.0
+        });
+        config["caps"]["stars"] = json!(10.0);
+        let response = request
+            .put("/api/v1/admin/health")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&config)
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let project = projects::Entity::find_by_id(project.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!((project.health - 100.0).abs() < f32::EPSILON);
+        // the config change, then every rating it changed
+        let rescored = last_event(&ctx).await;
+        assert_eq!(rescored.action, audit_events::PROJECT_HEALTH_CHANGED);
+        assert_eq!(rescored.target, Some(format!("project:{}", project.id)));
+        assert_eq!(rescored.changes.unwrap()["health"]["after"], 100.0);
+        let event = audit_events::Entity::find()
+            .filter(audit_events::Column::Action.eq(audit_events::HEALTH_CONFIG_CHANGED))
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        let changes = event.changes.unwrap();
+        assert_eq!(changes["caps"]["after"], config["caps"]);
+        assert_eq!(changes["caps"]["before"]["stars"], 100.0);
+
+        config["caps"]["stars"] = json!(0.0);
+        let response = request
+            .put("/api/v1/admin/health")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&config)
+            .await;
+        assert_eq!(response.status_code(), 422);
+
+        let response = request
+            .post("/admin/health")
+            .add_header(auth_key, auth_value)
+            .form(&[
+                ("activity", "1"),
+                ("community", "0"),
+                ("adoption", "0"),
+                ("maintenance", "0"),
+                ("commits_last_30d", "30"),
+                ("contributors", "10"),
+                ("stars", "-1"),
+                ("prs", "10"),
+                ("issues", "50"),
+            ])
+            .await;
+        assert_eq!(response.status_code(), 200);
+        assert!(response.text().contains(r#"id="error""#));
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_score_health_by_percentile() {
+    request::<App, _, _>(|request, ctx| async move {
+        let admin = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&admin.token);
+        let response = request
+            .post("/admin/health")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .form(&[
+                ("activity", "1"),
+                ("community", "0"),
+                ("adoption", "0"),
+                ("maintenance", "0"),
+                ("commits_last_30d", "30"),
+                ("contributors", "10"),
+                ("stars", "100"),
+                ("prs", "10"),
+                ("issues", "50"),
+                ("normalization", "percentile"),
+                ("bucket", "size"),
+            ])
+            .await;
+        assert_eq!(response.status_code(), 303, "{}", response.text());
+
+        let config: serde_json::Value = request
+            .get("/api/v1/admin/health")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await
+            .json();
+        assert_eq!(config["normalization"], "percentile");
+        assert_eq!(config["bucket"], "size");
+
+        let response = request
+            .get("/admin/health")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert!(response
+            .text()
+            .contains(r#"<option value="percentile" selected>"#));
+
+        // configs saved before there was a bucket still load
+        let mut config = config;
+        config.as_object_mut().unwrap().remove("bucket");
+        let response = request
+            .put("/api/v1/admin/health")
+            .add_header(auth_key, auth_value)
+            .json(&config)
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let saved: serde_json::Value = response.json();
+        assert_eq!(saved["bucket"], "all");
+    })
+    .await;
+}
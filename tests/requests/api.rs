@@ -0,0 +1,511 @@
+use gooncityhub::{
+    app::App,
+    models::{
+        _entities::repos,
+        api_keys::{self, Scope},
+        project_members::{self, Role},
+        projects, users,
+    },
+};
+use loco_rs::{app::AppContext, prelude::*};
+use serde_json::json;
+use serial_test::serial;
+
+use super::prepare_data;
+
+async fn create_project(ctx: &AppContext, name: &str, owner: &str) -> projects::Model {
+    projects::ActiveModel {
+        name: Set(name.to_string()),
+        owner: Set(owner.to_string()),
+        health: Set(100.),
+        last_fetch: Set(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .insert(&ctx.db)
+    .await
+    .unwrap()
+}
+
+async fn create_repo(ctx: &AppContext, project_id: i32, name: &str, stars: i32) -> repos::Model {
+    repos::ActiveModel {
+        project_id: Set(project_id),
+        forge: Set("github".to_string()),
+        host: Set("github.com".to_string()),
+        owner: Set("acme".to_string()),
+        name: Set(name.to_string()),
+        stars: Set(stars),
+        forks: Set(0),
+        issues: Set(0),
+        prs: Set(0),
+        contributors: Set(0),
+        commits_last_30d: Set(0),
+        watchers: Set(0),
+        releases: Set(0),
+        last_fetch: Set(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .insert(&ctx.db)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+#[serial]
+async fn can_crud_projects() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let response = request
+            .post("/api/v1/projects")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&json!({ "name": "tool", "owner": "acme", "description": "A tool" }))
+            .await;
+        assert_eq!(response.status_code(), 201, "{}", response.text());
+        let created: serde_json::Value = response.json();
+        let id = created["id"].as_i64().unwrap();
+        assert_eq!(created["health"], 100.0);
+
+        let response = request
+            .patch(&format!("/api/v1/projects/{id}"))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&json!({ "description": "" , "name": "tool-ng" }))
+            .await;
+        assert_eq!(response.status_code(), 200, "{}", response.text());
+
+        let shown: serde_json::Value = request.get(&format!("/api/v1/projects/{id}")).await.json();
+        assert_eq!(shown["name"], "tool-ng");
+        assert_eq!(shown["owner"], "acme");
+        assert!(shown["description"].is_null());
+
+        let response = request
+            .delete(&format!("/api/v1/projects/{id}"))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(response.status_code(), 204);
+
+        let response = request.get(&format!("/api/v1/projects/{id}")).await;
+        assert_eq!(response.status_code(), 404);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["error"], "not_found");
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn rejects_invalid_project_params() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let response = request
+            .post("/api/v1/projects")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&json!({ "name": "tool", "owner": "acme", "health": 100 }))
+            .await;
+        assert_eq!(response.status_code(), 400);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["error"], "bad_request");
+        assert!(body["description"]
+            .as_str()
+            .unwrap()
+            .contains("unknown field `health`"));
+
+        let response = request
+            .post("/api/v1/projects")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&json!({ "name": " ", "owner": "acme" }))
+            .await;
+        assert_eq!(response.status_code(), 422);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["error"], "invalid_params");
+        assert_eq!(body["description"], "name must not be empty");
+
+        assert!(projects::Entity::find()
+            .all(&ctx.db)
+            .await
+            .unwrap()
+            .is_empty());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_page_sort_and_filter_projects() {
+    request::<App, _, _>(|request, ctx| async move {
+        for name in ["bravo", "alpha", "charlie"] {
+            create_project(&ctx, name, "acme").await;
+        }
+        create_project(&ctx, "delta", "other").await;
+
+        let res: serde_json::Value = request
+            .get("/api/v1/projects?owner=acme&sort=name&page=1&page_size=2")
+            .await
+            .json();
+        let names: Vec<_> = res["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["alpha", "bravo"]);
+        assert_eq!(res["pagination"]["total_items"], 3);
+        assert_eq!(res["pagination"]["total_pages"], 2);
+
+        let res: serde_json::Value = request.get("/api/v1/projects?sort=-name&q=a").await.json();
+        assert_eq!(res["results"][0]["name"], "delta");
+
+        let response = request.get("/api/v1/projects?sort=secret").await;
+        assert_eq!(response.status_code(), 422);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["error"], "invalid_params");
+
+        let response = request.get("/api/v1/projects?page=first").await;
+        assert_eq!(response.status_code(), 400);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["error"], "bad_request");
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_list_move_and_delete_repos() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let project = create_project(&ctx, "tools", "acme").await;
+        let other = create_project(&ctx, "other", "acme").await;
+        let small = create_repo(&ctx, project.id, "small", 1).await;
+        let big = create_repo(&ctx, project.id, "big", 50).await;
+        create_repo(&ctx, other.id, "elsewhere", 100).await;
+        for project_id in [project.id, other.id] {
+            project_members::Entity::grant(&ctx.db, project_id, user.user.id, Role::Maintainer)
+                .await
+                .unwrap();
+        }
+
+        let res: serde_json::Value = request
+            .get(&format!(
+                "/api/v1/repos?project_id={}&sort=-stars",
+                project.id
+            ))
+            .await
+            .json();
+        let ids: Vec<_> = res["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["id"].as_i64().unwrap())
+            .collect();
+        assert_eq!(ids, [i64::from(big.id), i64::from(small.id)]);
+
+        let shown: serde_json::Value = request
+            .get(&format!("/api/v1/repos/{}", big.id))
+            .await
+            .json();
+        assert_eq!(shown["name"], "big");
+
+        let response = request
+            .patch(&format!("/api/v1/repos/{}", big.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&json!({ "project_id": other.id }))
+            .await;
+        assert_eq!(response.status_code(), 200, "{}", response.text());
+        let moved: serde_json::Value = response.json();
+        assert_eq!(moved["project_id"], other.id);
+
+        let response = request
+            .patch(&format!("/api/v1/repos/{}", big.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&json!({ "project_id": other.id, "stars": 100_000 }))
+            .await;
+        assert_eq!(response.status_code(), 400);
+
+        let response = request
+            .patch(&format!("/api/v1/repos/{}", big.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&json!({ "project_id": 999_999 }))
+            .await;
+        assert_eq!(response.status_code(), 422);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["description"], "project 999999 does not exist");
+
+        let response = request
+            .delete(&format!("/api/v1/repos/{}", small.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(response.status_code(), 204);
+        assert!(repos::Entity::find_by_id(small.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .is_none());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn rejects_invalid_repo_import() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let response = request
+            .post("/api/v1/repos")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&json!({ "repo": "https://example.com/acme/tool" }))
+            .await;
+        assert_eq!(response.status_code(), 422);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["error"], "invalid_params");
+        assert!(body["description"]
+            .as_str()
+            .unwrap()
+            .contains("no forge configured for host"));
+
+        let response = request
+            .post("/api/v1/repos")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&json!({ "repo": "acme/tool", "project_id": 999_999 }))
+            .await;
+        assert_eq!(response.status_code(), 422);
+
+        assert!(repos::Entity::find().all(&ctx.db).await.unwrap().is_empty());
+    })
+    .await;
+}
+
+async fn create_user(ctx: &AppContext, email: &str) -> users::Model {
+    users::Model::create_with_password(
+        &ctx.db,
+        &users::RegisterParams {
+            email: email.to_string(),
+            password: "12341234".to_string(),
+            name: "other".to_string(),
+        },
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+#[serial]
+async fn requires_auth_for_mutations() {
+    request::<App, _, _>(|request, ctx| async move {
+        let project = create_project(&ctx, "tools", "acme").await;
+        let repo = create_repo(&ctx, project.id, "tool", 1).await;
+
+        for response in [
+            request
+                .post("/api/v1/projects")
+                .json(&json!({ "name": "tool", "owner": "acme" }))
+                .await,
+            request
+                .patch(&format!("/api/v1/projects/{}", project.id))
+                .json(&json!({ "name": "mine" }))
+                .await,
+            request
+                .delete(&format!("/api/v1/projects/{}", project.id))
+                .await,
+            request
+                .post("/api/v1/repos")
+                .json(&json!({ "repo": "acme/tool" }))
+                .await,
+            request.delete(&format!("/api/v1/repos/{}", repo.id)).await,
+            request.delete(&format!("/repos/{}", repo.id)).await,
+            request.delete(&format!("/projects/{}", project.id)).await,
+        ] {
+            assert_eq!(response.status_code(), 401, "{}", response.text());
+        }
+
+        // reads stay public
+        let response = request
+            .get(&format!("/api/v1/projects/{}", project.id))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(
+            projects::Entity::find().all(&ctx.db).await.unwrap().len(),
+            1
+        );
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn enforces_member_roles() {
+    request::<App, _, _>(|request, ctx| async move {
+        let owner = prepare_data::init_user_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&owner.token);
+        let other = create_user(&ctx, "other@example.com").await;
+        // API keys work wherever a JWT does
+        let key = api_keys::Entity::issue(&ctx.db, other.id, "ci", &Scope::ALL)
+            .await
+            .unwrap();
+        let (key_name, key_value) = prepare_data::auth_header(&key.secret);
+
+        let created: serde_json::Value = request
+            .post("/api/v1/projects")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&json!({ "name": "tool", "owner": "acme" }))
+            .await
+            .json();
+        let id = created["id"].as_i64().unwrap();
+
+        let response = request
+            .patch(&format!("/api/v1/projects/{id}"))
+            .add_header(key_name.clone(), key_value.clone())
+            .json(&json!({ "name": "mine" }))
+            .await;
+        assert_eq!(response.status_code(), 403);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["error"], "forbidden");
+
+        let response = request
+            .put(&format!("/api/v1/projects/{id}/members"))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&json!({ "email": "other@example.com", "role": "maintainer" }))
+            .await;
+        assert_eq!(response.status_code(), 200, "{}", response.text());
+
+        let response = request
+            .patch(&format!("/api/v1/projects/{id}"))
+            .add_header(key_name.clone(), key_value.clone())
+            .json(&json!({ "name": "tool-ng" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        // only owners delete and manage members
+        let response = request
+            .delete(&format!("/api/v1/projects/{id}"))
+            .add_header(key_name.clone(), key_value.clone())
+            .await;
+        assert_eq!(response.status_code(), 403);
+        let response = request
+            .put(&format!("/api/v1/projects/{id}/members"))
+            .add_header(key_name.clone(), key_value.clone())
+            .json(&json!({ "email": "other@example.com", "role": "owner" }))
+            .await;
+        assert_eq!(response.status_code(), 403);
+
+        let members: serde_json::Value = request
+            .get(&format!("/api/v1/projects/{id}/members"))
+            .add_header(key_name.clone(), key_value.clone())
+            .await
+            .json();
+        let roles: Vec<_> = members
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(roles, ["owner", "maintainer"]);
+
+        let response = request
+            .delete(&format!("/api/v1/projects/{id}/members/{}", other.pid))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(response.status_code(), 204);
+        let response = request
+            .patch(&format!("/api/v1/projects/{id}"))
+            .add_header(key_name, key_value)
+            .json(&json!({ "name": "again" }))
+            .await;
+        assert_eq!(response.status_code(), 403);
+
+        let response = request
+            .delete(&format!("/api/v1/projects/{id}"))
+            .add_header(auth_key, auth_value)
+            .await;
+        assert_eq!(response.status_code(), 204);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_choose_how_health_is_combined() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let response = request
+            .post("/api/v1/projects")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&json!({ "name": "tool", "owner": "acme", "health_strategy": "best" }))
+            .await;
+        assert_eq!(response.status_code(), 422);
+
+        let created: serde_json::Value = request
+            .post("/api/v1/projects")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&json!({ "name": "tool", "owner": "acme", "health_strategy": "max" }))
+            .await
+            .json();
+        assert_eq!(created["health_strategy"], "max");
+        let id = i32::try_from(created["id"].as_i64().unwrap()).unwrap();
+        let flagship = create_repo(&ctx, id, "flagship", 100).await;
+        create_repo(&ctx, id, "helper", 0).await;
+        let elsewhere = create_project(&ctx, "other", "acme").await;
+        let stray = create_repo(&ctx, elsewhere.id, "stray", 100).await;
+
+        let response = request
+            .patch(&format!("/api/v1/projects/{id}"))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&json!({ "health_strategy": "primary", "primary_repo_id": stray.id }))
+            .await;
+        assert_eq!(response.status_code(), 422);
+
+        let response = request
+            .patch(&format!("/api/v1/projects/{id}"))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&json!({ "health_strategy": "average" }))
+            .await;
+        let average: serde_json::Value = response.json();
+        assert_eq!(average["health_strategy_used"], "average");
+
+        let response = request
+            .patch(&format!("/api/v1/projects/{id}"))
+            .add_header(auth_key, auth_value)
+            .json(&json!({ "health_strategy": "primary", "primary_repo_id": flagship.id }))
+            .await;
+        assert_eq!(response.status_code(), 200, "{}", response.text());
+        let primary: serde_json::Value = response.json();
+        assert_eq!(primary["health_strategy"], "primary");
+        assert_eq!(primary["health_strategy_used"], "primary");
+        assert!(primary["health"].as_f64() > average["health"].as_f64());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_not_import_a_repo_away_from_its_project() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let mine: serde_json::Value = request
+            .post("/api/v1/projects")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&json!({ "name": "mine", "owner": "me" }))
+            .await
+            .json();
+        let theirs = create_project(&ctx, "theirs", "acme").await;
+        let repo = create_repo(&ctx, theirs.id, "tool", 1).await;
+
+        let response = request
+            .post("/api/v1/repos")
+            .add_header(auth_key, auth_value)
+            .json(&json!({ "repo": "ACME/tool", "project_id": mine["id"] }))
+            .await;
+        assert_eq!(response.status_code(), 403, "{}", response.text());
+
+        let repo = repos::Entity::find_by_id(repo.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(repo.project_id, theirs.id);
+    })
+    .await;
+}
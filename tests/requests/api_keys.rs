@@ -0,0 +1,116 @@
+use gooncityhub::{app::App, models::api_keys};
+use loco_rs::prelude::*;
+use serde_json::json;
+use serial_test::serial;
+
+use super::prepare_data;
+
+#[tokio::test]
+#[serial]
+async fn can_create_use_rotate_and_revoke_keys() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+
+        let response = request
+            .post("/api/v1/api_keys")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&json!({ "name": "ci" }))
+            .await;
+        assert_eq!(response.status_code(), 201, "{}", response.text());
+        let created: serde_json::Value = response.json();
+        let id = created["id"].as_i64().unwrap();
+        let secret = created["key"].as_str().unwrap().to_string();
+        assert!(secret.starts_with(api_keys::KEY_PREFIX));
+        assert!(secret.starts_with(created["prefix"].as_str().unwrap()));
+        assert_eq!(created["scopes"], json!(["projects", "repos", "members"]));
+
+        let (key_name, key_value) = prepare_data::auth_header(&secret);
+        let response = request
+            .post("/api/v1/projects")
+            .add_header(key_name.clone(), key_value.clone())
+            .json(&json!({ "name": "tool", "owner": "acme" }))
+            .await;
+        assert_eq!(response.status_code(), 201);
+
+        // the secret is never shown again
+        let keys: serde_json::Value = request
+            .get("/api/v1/api_keys")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await
+            .json();
+        assert_eq!(keys.as_array().unwrap().len(), 1);
+        assert!(keys[0].get("key").is_none());
+        assert!(!keys[0]["last_used_at"].is_null());
+
+        let rotated: serde_json::Value = request
+            .post(&format!("/api/v1/api_keys/{id}/rotate"))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await
+            .json();
+        let new_secret = rotated["key"].as_str().unwrap().to_string();
+        assert_ne!(new_secret, secret);
+        let response = request
+            .post("/api/v1/projects")
+            .add_header(key_name, key_value)
+            .json(&json!({ "name": "tool", "owner": "acme" }))
+            .await;
+        assert_eq!(response.status_code(), 401);
+
+        let response = request
+            .delete(&format!("/api/v1/api_keys/{id}"))
+            .add_header(auth_key, auth_value)
+            .await;
+        assert_eq!(response.status_code(), 204);
+        let (key_name, key_value) = prepare_data::auth_header(&new_secret);
+        let response = request
+            .post("/api/v1/projects")
+            .add_header(key_name, key_value)
+            .json(&json!({ "name": "tool", "owner": "acme" }))
+            .await;
+        assert_eq!(response.status_code(), 401);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn enforces_key_scopes() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+
+        let response = request
+            .post("/api/v1/api_keys")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&json!({ "name": "bad", "scopes": ["everything"] }))
+            .await;
+        assert_eq!(response.status_code(), 422);
+
+        let created: serde_json::Value = request
+            .post("/api/v1/api_keys")
+            .add_header(auth_key, auth_value)
+            .json(&json!({ "name": "importer", "scopes": ["repos"] }))
+            .await
+            .json();
+        let (key_name, key_value) = prepare_data::auth_header(created["key"].as_str().unwrap());
+
+        let response = request
+            .post("/api/v1/projects")
+            .add_header(key_name.clone(), key_value.clone())
+            .json(&json!({ "name": "tool", "owner": "acme" }))
+            .await;
+        assert_eq!(response.status_code(), 403);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["error"], "forbidden");
+
+        // keys can not manage keys
+        let response = request
+            .post("/api/v1/api_keys")
+            .add_header(key_name, key_value)
+            .json(&json!({ "name": "more" }))
+            .await;
+        assert_eq!(response.status_code(), 401);
+    })
+    .await;
+}
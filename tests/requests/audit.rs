@@ -0,0 +1,142 @@
+use axum::http::{HeaderName, HeaderValue};
+use gooncityhub::{app::App, models::audit_events};
+use loco_rs::prelude::*;
+use serde_json::json;
+use serial_test::serial;
+
+use super::prepare_data;
+
+#[tokio::test]
+#[serial]
+async fn records_what_changed_with_the_request_id() {
+    request::<App, _, _>(|request, ctx| async move {
+        let admin = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&admin.token);
+        let created: serde_json::Value = request
+            .post("/api/v1/projects")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&json!({ "name": "tool", "owner": "acme" }))
+            .await
+            .json();
+        let id = created["id"].as_i64().unwrap();
+
+        let response = request
+            .patch(&format!("/api/v1/projects/{id}"))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .add_header(
+                HeaderName::from_static("x-request-id"),
+                HeaderValue::from_static("rename-tool"),
+            )
+            .json(&json!({ "name": "tool-ng" }))
+            .await;
+        assert_eq!(response.status_code(), 200, "{}", response.text());
+
+        let event = audit_events::Entity::find()
+            .filter(audit_events::Column::Action.eq(audit_events::PROJECT_UPDATED))
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.actor_id, Some(admin.user.id));
+        assert_eq!(
+            event.target.as_deref(),
+            Some(format!("project:{id}").as_str())
+        );
+        assert_eq!(event.request_id.as_deref(), Some("rename-tool"));
+        assert_eq!(
+            event.changes,
+            Some(json!({ "name": { "before": "tool", "after": "tool-ng" } }))
+        );
+
+        let res: serde_json::Value = request
+            .get("/api/v1/admin/audit_events?request_id=rename-tool")
+            .add_header(auth_key, auth_value)
+            .await
+            .json();
+        assert_eq!(res["pagination"]["total_items"], 1, "{res}");
+        assert_eq!(res["results"][0]["action"], audit_events::PROJECT_UPDATED);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_filter_events_by_action_prefix() {
+    request::<App, _, _>(|request, ctx| async move {
+        let admin = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&admin.token);
+        request
+            .post("/api/auth/login")
+            .json(&json!({ "email": "nobody@example.com", "password": "wrong" }))
+            .await;
+        request
+            .post("/api/v1/projects")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&json!({ "name": "tool", "owner": "acme" }))
+            .await;
+
+        let res: serde_json::Value = request
+            .get("/api/v1/admin/audit_events?action=auth")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await
+            .json();
+        let actions: Vec<&str> = res["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|event| event["action"].as_str().unwrap())
+            .collect();
+        assert!(actions.iter().all(|action| action.starts_with("auth.")));
+        assert_eq!(actions[0], audit_events::AUTH_SIGN_IN_FAILED);
+        assert_eq!(res["results"][0]["target"], "nobody@example.com");
+        assert!(actions.contains(&audit_events::AUTH_SIGNED_IN));
+
+        let res: serde_json::Value = request
+            .get(&format!(
+                "/api/v1/admin/audit_events?action={}&actor_id={}",
+                audit_events::PROJECT_CREATED,
+                admin.user.id
+            ))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await
+            .json();
+        assert_eq!(res["pagination"]["total_items"], 1, "{res}");
+
+        let response = request
+            .get("/api/v1/admin/audit_events?since=yesterday")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(response.status_code(), 400);
+
+        let response = request
+            .get("/admin/audit?action=project")
+            .add_header(auth_key, auth_value)
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let body = response.text();
+        assert!(body.contains(audit_events::PROJECT_CREATED), "{body}");
+        assert!(!body.contains(audit_events::AUTH_SIGN_IN_FAILED));
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn audit_log_needs_an_admin() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+
+        let response = request
+            .get("/api/v1/admin/audit_events")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(response.status_code(), 403);
+        let response = request
+            .get("/admin/audit")
+            .add_header(auth_key, auth_value)
+            .await;
+        assert_eq!(response.status_code(), 403);
+    })
+    .await;
+}
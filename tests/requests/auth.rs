@@ -1,4 +1,8 @@
-use gooncityhub::{app::App, models::users};
+use gooncityhub::{
+    app::App,
+    email_domains::Rules,
+    models::{email_domain_rules, users},
+};
 use insta::{assert_debug_snapshot, with_settings};
 use loco_rs::testing::prelude::*;
 use rstest::rstest;
@@ -17,6 +21,13 @@ macro_rules! configure_insta {
     };
 }
 
+/// Like `cleanup_user_model`, also hiding refresh tokens.
+fn cleanup_login() -> Vec<(&'static str, &'static str)> {
+    let mut filters = cleanup_user_model();
+    filters.push((r"gcr_[0-9a-f]{32}", "REFRESH_TOKEN"));
+    filters
+}
+
 #[tokio::test]
 #[serial]
 async fn can_register() {
@@ -39,7 +50,7 @@ async fn can_register() {
         let saved_user = users::Model::find_by_email(&ctx.db, email).await;
 
         with_settings!({
-            filters => cleanup_user_model()
+            filters => cleanup_login()
         }, {
             assert_debug_snapshot!(saved_user);
         });
@@ -113,7 +124,7 @@ async fn can_login_with_verify(#[case] test_name: &str, #[case] password: &str)
         );
 
         with_settings!({
-            filters => cleanup_user_model()
+            filters => cleanup_login()
         }, {
             assert_debug_snapshot!(test_name, (response.status_code(), response.text()));
         });
@@ -183,7 +194,7 @@ async fn can_login_without_verify() {
         );
 
         with_settings!({
-            filters => cleanup_user_model()
+            filters => cleanup_login()
         }, {
             assert_debug_snapshot!(login_response.text());
         });
@@ -303,7 +314,7 @@ async fn can_get_current_user() {
         );
 
         with_settings!({
-            filters => cleanup_user_model()
+            filters => cleanup_login()
         }, {
             assert_debug_snapshot!((response.status_code(), response.text()));
         });
@@ -359,7 +370,7 @@ async fn can_auth_with_magic_link() {
         );
 
         with_settings!({
-            filters => cleanup_user_model()
+            filters => cleanup_login()
         }, {
             assert_debug_snapshot!(magic_link_response.text());
         });
@@ -386,6 +397,45 @@ async fn can_reject_invalid_email() {
     .await;
 }
 
+#[tokio::test]
+#[serial]
+async fn magic_link_domains_can_be_overridden() {
+    request::<App, _, _>(|request, ctx| async move {
+        let response = request
+            .post("/api/auth/magic-link")
+            .json(&serde_json::json!({ "email": "dev@eng.corp.example" }))
+            .await;
+        assert_eq!(response.status_code(), 400);
+        let body: serde_json::Value = response.json();
+        assert_eq!(
+            body["description"],
+            "magic links are not enabled for `eng.corp.example` addresses"
+        );
+
+        email_domain_rules::Entity::replace(
+            &ctx.db,
+            &Rules {
+                allow: vec!["*.corp.example".to_string()],
+                deny: vec!["gmail.com".to_string()],
+            },
+        )
+        .await
+        .unwrap();
+
+        let response = request
+            .post("/api/auth/magic-link")
+            .json(&serde_json::json!({ "email": "dev@eng.corp.example" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let response = request
+            .post("/api/auth/magic-link")
+            .json(&serde_json::json!({ "email": "user1@gmail.com" }))
+            .await;
+        assert_eq!(response.status_code(), 400);
+    })
+    .await;
+}
+
 #[tokio::test]
 #[serial]
 async fn can_reject_invalid_magic_link_token() {
@@ -448,7 +498,7 @@ async fn can_resend_verification_email() {
             .expect("User should exist");
 
         with_settings!({
-            filters => cleanup_user_model()
+            filters => cleanup_login()
         }, {
             assert_debug_snapshot!("resend_verification_user", user);
         });
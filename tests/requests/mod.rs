@@ -1,2 +1,14 @@
+mod account;
+mod admin;
+mod api;
+mod api_keys;
+mod audit;
 mod auth;
+mod oauth;
 mod prepare_data;
+mod project;
+mod repo;
+mod sessions;
+mod throttle;
+mod two_factor;
+mod webhooks;
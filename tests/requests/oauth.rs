@@ -0,0 +1,355 @@
+use axum::{
+    extract::Form,
+    http::HeaderMap,
+    routing::{get, post},
+    Json, Router,
+};
+use gooncityhub::{
+    app::App,
+    forges::Forge,
+    models::{
+        _entities::{activity_events, repos},
+        user_identities, users,
+    },
+};
+use loco_rs::{
+    app::{AppContext, Hooks},
+    boot::StartMode,
+    environment::Environment,
+    testing::prelude::*,
+    TestServer,
+};
+use reqwest::Url;
+use sea_orm::{ActiveModelTrait, ActiveValue::Set};
+use serde_json::json;
+use serial_test::serial;
+use std::collections::HashMap;
+
+use super::prepare_data;
+use crate::forges::stand_in;
+
+/// GitHub's OAuth endpoints and API. The code `octo` belongs to a verified
+/// account, `ghost` to one without a verified email.
+fn github() -> Router {
+    Router::new()
+        .route(
+            "/login/oauth/access_token",
+            post(|Form(form): Form<HashMap<String, String>>| async move {
+                assert_eq!(form["client_id"], "client-id");
+                assert_eq!(form["client_secret"], "client-secret");
+                match form["code"].as_str() {
+                    code @ ("octo" | "ghost") => Json(json!({ "access_token": code })),
+                    _ => Json(json!({
+                        "error": "bad_verification_code",
+                        "error_description": "The code passed is incorrect or expired."
+                    })),
+                }
+            }),
+        )
+        .route(
+            "/user",
+            get(|headers: HeaderMap| async move {
+                Json(if is_token(&headers, "octo") {
+                    json!({ "id": 583_231, "login": "Octocat", "name": "The Octocat" })
+                } else {
+                    json!({ "id": 9, "login": "ghost", "name": null })
+                })
+            }),
+        )
+        .route(
+            "/user/emails",
+            get(|headers: HeaderMap| async move {
+                Json(json!([{
+                    "email": "octocat@example.com",
+                    "primary": true,
+                    "verified": is_token(&headers, "octo")
+                }]))
+            }),
+        )
+}
+
+fn is_token(headers: &HeaderMap, token: &str) -> bool {
+    headers["authorization"] == format!("Bearer {token}").as_str()
+}
+
+/// Like `request`, with sign in with GitHub pointed at a stand-in.
+async fn request_with_github<F, Fut>(callback: F)
+where
+    F: FnOnce(TestServer, AppContext) -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    let base_url = stand_in::serve(github()).await;
+    let mut config = App::load_config(&Environment::Test).await.unwrap();
+    let mut settings = config.settings.take().unwrap_or_else(|| json!({}));
+    settings["oauth"] = json!({
+        "github": {
+            "client_id": "client-id",
+            "client_secret": "client-secret",
+            "authorize_url": format!("{base_url}/login/oauth/authorize"),
+            "token_url": format!("{base_url}/login/oauth/access_token"),
+            "api_url": base_url,
+        }
+    });
+    config.settings = Some(settings);
+    let boot = App::boot(StartMode::ServerOnly, &Environment::Test, config)
+        .await
+        .unwrap();
+    let server = TestServer::new(boot.router.unwrap()).unwrap();
+    callback(server, boot.app_context).await;
+}
+
+fn state_of(url: &str) -> String {
+    Url::parse(url)
+        .unwrap()
+        .query_pairs()
+        .find(|(key, _)| key == "state")
+        .unwrap()
+        .1
+        .to_string()
+}
+
+/// Go through sign in with GitHub, the status and body of the callback.
+async fn sign_in(request: &TestServer, code: &str) -> (u16, String) {
+    let response = request.get("/api/auth/github").await;
+    assert_eq!(response.status_code(), 303);
+    let state = state_of(response.header("location").to_str().unwrap());
+    let response = request
+        .get("/api/auth/github/callback")
+        .add_cookie(response.cookie("oauth_state"))
+        .add_query_params([("code", code), ("state", &state)])
+        .await;
+    (response.status_code().as_u16(), response.text())
+}
+
+#[tokio::test]
+#[serial]
+async fn can_sign_in_with_github() {
+    request_with_github(|request, ctx| async move {
+        let (status, body) = sign_in(&request, "octo").await;
+        assert_eq!(status, 200, "{body}");
+        let login: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert!(login["token"].as_str().is_some());
+        assert_eq!(login["name"], "The Octocat");
+        assert_eq!(login["is_verified"], true);
+
+        let user = users::Model::find_by_email(&ctx.db, "octocat@example.com")
+            .await
+            .unwrap();
+        assert_eq!(login["pid"], user.pid.to_string());
+        let identities = user_identities::Entity::for_user(&ctx.db, user.id)
+            .await
+            .unwrap();
+        assert_eq!(identities.len(), 1);
+        assert_eq!(identities[0].provider_user_id, "583231");
+        assert_eq!(identities[0].login, "Octocat");
+
+        // signing in again finds the same user
+        let again: serde_json::Value =
+            serde_json::from_str(&sign_in(&request, "octo").await.1).unwrap();
+        assert_eq!(again["pid"], login["pid"]);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn github_sign_in_links_existing_user_by_verified_email() {
+    request_with_github(|request, ctx| async move {
+        let user = users::Model::create_with_password(
+            &ctx.db,
+            &users::RegisterParams {
+                email: "octocat@example.com".to_string(),
+                password: "12341234".to_string(),
+                name: "octo".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+        let login: serde_json::Value =
+            serde_json::from_str(&sign_in(&request, "octo").await.1).unwrap();
+        assert_eq!(login["pid"], user.pid.to_string());
+        assert!(
+            user_identities::Entity::find_account(&ctx.db, Forge::Github, "583231")
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        // an account without a verified email can not sign up
+        assert_eq!(sign_in(&request, "ghost").await.0, 401);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn rejects_bad_github_callbacks() {
+    request_with_github(|request, _ctx| async move {
+        let response = request
+            .get("/api/auth/github/callback")
+            .add_query_params([("code", "octo"), ("state", "made.up.state.0000")])
+            .await;
+        assert_eq!(response.status_code(), 401);
+
+        assert_eq!(sign_in(&request, "expired-code").await.0, 401);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn github_callback_only_completes_in_the_browser_that_started() {
+    request_with_github(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let response = request
+            .post("/api/auth/github/link")
+            .add_header(auth_key, auth_value)
+            .await;
+        let cookie = response.cookie("oauth_state");
+        assert_eq!(cookie.http_only(), Some(true));
+        let state = state_of(
+            response.json::<serde_json::Value>()["url"]
+                .as_str()
+                .unwrap(),
+        );
+
+        // e.g. a link to the callback sent to someone else
+        let response = request
+            .get("/api/auth/github/callback")
+            .add_query_params([("code", "octo"), ("state", &state)])
+            .await;
+        assert_eq!(response.status_code(), 401);
+
+        let other = request.get("/api/auth/github").await;
+        let response = request
+            .get("/api/auth/github/callback")
+            .add_cookie(other.cookie("oauth_state"))
+            .add_query_params([("code", "octo"), ("state", &state)])
+            .await;
+        assert_eq!(response.status_code(), 401);
+        assert!(user_identities::Entity::for_user(&ctx.db, user.user.id)
+            .await
+            .unwrap()
+            .is_empty());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn github_sign_in_needs_configuring() {
+    request::<App, _, _>(|request, _ctx| async move {
+        let response = request.get("/api/auth/github").await;
+        assert_eq!(response.status_code(), 404);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_link_github_and_list_contributions() {
+    request_with_github(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+
+        let response = request
+            .post("/api/auth/github/link")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let url = response.json::<serde_json::Value>()["url"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let response = request
+            .get("/api/auth/github/callback")
+            .add_cookie(response.cookie("oauth_state"))
+            .add_query_params([("code", "octo"), ("state", &state_of(&url))])
+            .await;
+        assert_eq!(response.status_code(), 200, "{}", response.text());
+        let identity: serde_json::Value = response.json();
+        assert_eq!(identity["login"], "Octocat");
+        assert_eq!(identity["user_id"], user.user.id);
+
+        let repo = repos::ActiveModel {
+            name: Set("octocrab".to_string()),
+            owner: Set("XAMPPRocky".to_string()),
+            forge: Set("github".to_string()),
+            host: Set("github.com".to_string()),
+            stars: Set(10),
+            forks: Set(1),
+            issues: Set(2),
+            prs: Set(3),
+            contributors: Set(4),
+            commits_last_30d: Set(5),
+            watchers: Set(10),
+            releases: Set(0),
+            last_fetch: Set(chrono::Utc::now().naive_utc()),
+            ..Default::default()
+        }
+        .insert(&ctx.db)
+        .await
+        .unwrap();
+        for actor in ["octocat", "someone-else"] {
+            activity_events::ActiveModel {
+                kind: Set("push".to_string()),
+                actor: Set(Some(actor.to_string())),
+                occurred_at: Set(chrono::Utc::now().into()),
+                repo_id: Set(repo.id),
+                ..Default::default()
+            }
+            .insert(&ctx.db)
+            .await
+            .unwrap();
+        }
+
+        let contributions: serde_json::Value = request
+            .get("/api/v1/me/contributions")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await
+            .json();
+        assert_eq!(contributions["pagination"]["total_items"], 1);
+        assert_eq!(contributions["results"][0]["actor"], "octocat");
+
+        let response = request
+            .delete("/api/auth/github/link")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let contributions: serde_json::Value = request
+            .get("/api/v1/me/contributions")
+            .add_header(auth_key, auth_value)
+            .await
+            .json();
+        assert_eq!(contributions["pagination"]["total_items"], 0);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn github_account_links_to_one_user() {
+    request_with_github(|request, ctx| async move {
+        sign_in(&request, "octo").await;
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+
+        let response = request
+            .post("/api/auth/github/link")
+            .add_header(auth_key, auth_value)
+            .await;
+        let url: serde_json::Value = response.json();
+        let response = request
+            .get("/api/auth/github/callback")
+            .add_cookie(response.cookie("oauth_state"))
+            .add_query_params([
+                ("code", "octo"),
+                ("state", &state_of(url["url"].as_str().unwrap())),
+            ])
+            .await;
+        assert_eq!(response.status_code(), 400);
+    })
+    .await;
+}
@@ -1,13 +1,17 @@
 use axum::http::{HeaderName, HeaderValue};
+use axum_extra::extract::cookie::Cookie;
 use gooncityhub::{models::users, views::auth::LoginResponse};
-use loco_rs::{app::AppContext, TestServer};
+use loco_rs::{app::AppContext, prelude::IntoActiveModel, TestServer};
 
-const USER_EMAIL: &str = "test@loco.com";
-const USER_PASSWORD: &str = "1234";
+pub const USER_EMAIL: &str = "test@loco.com";
+pub const USER_PASSWORD: &str = "1234";
 
 pub struct LoggedInUser {
     pub user: users::Model,
     pub token: String,
+    pub refresh_token: String,
+    /// The session cookie a browser keeps.
+    pub cookie: Cookie<'static>,
 }
 
 pub async fn init_user_login(request: &TestServer, ctx: &AppContext) -> LoggedInUser {
@@ -40,6 +44,7 @@ pub async fn init_user_login(request: &TestServer, ctx: &AppContext) -> LoggedIn
         }))
         .await;
 
+    let cookie = response.cookie("session");
     let login_response: LoginResponse = serde_json::from_str(&response.text()).unwrap();
 
     LoggedInUser {
@@ -47,6 +52,22 @@ pub async fn init_user_login(request: &TestServer, ctx: &AppContext) -> LoggedIn
             .await
             .unwrap(),
         token: login_response.token,
+        refresh_token: login_response.refresh_token,
+        cookie,
+    }
+}
+
+/// Like [`init_user_login`], for a user with the admin role.
+pub async fn init_admin_login(request: &TestServer, ctx: &AppContext) -> LoggedInUser {
+    let logged_in = init_user_login(request, ctx).await;
+    LoggedInUser {
+        user: logged_in
+            .user
+            .into_active_model()
+            .set_admin(&ctx.db, true)
+            .await
+            .unwrap(),
+        ..logged_in
     }
 }
 
@@ -55,3 +76,11 @@ pub fn auth_header(token: &str) -> (HeaderName, HeaderValue) {
 
     (HeaderName::from_static("authorization"), auth_header_value)
 }
+
+/// The CSRF token in the hidden field of the form on `page`.
+pub fn csrf_token(page: &str) -> String {
+    let (_, rest) = page
+        .split_once(r#"name="csrf_token" value=""#)
+        .expect("page has no csrf token");
+    rest.split('"').next().unwrap().to_string()
+}
@@ -0,0 +1,218 @@
+use gooncityhub::{
+    app::App,
+    models::{_entities::projects, project_members},
+};
+use loco_rs::prelude::*;
+use serial_test::serial;
+
+use super::prepare_data;
+
+#[tokio::test]
+#[serial]
+async fn can_show_import_form() {
+    request::<App, _, _>(|request, _ctx| async move {
+        let response = request.get("/projects/import").await;
+
+        assert_eq!(response.status_code(), 200);
+        assert!(response.text().contains(r#"name="account""#));
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn import_rejects_invalid_account() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let response = request
+            .post("/projects/import")
+            .add_header(auth_key, auth_value)
+            .form(&[("account", "rust-lang/rust"), ("project_id", "")])
+            .await;
+
+        assert_eq!(response.status_code(), 200);
+        assert!(response
+            .text()
+            .contains("is not a GitHub user or organization"));
+        assert!(projects::Entity::find()
+            .all(&ctx.db)
+            .await
+            .unwrap()
+            .is_empty());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn import_rejects_missing_project() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let response = request
+            .post("/projects/import")
+            .add_header(auth_key, auth_value)
+            .form(&[("account", "rust-lang"), ("project_id", "4242")])
+            .await;
+
+        assert_eq!(response.status_code(), 200);
+        assert!(response.text().contains("project 4242 does not exist"));
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn health_can_not_be_set_from_the_form() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let response = request
+            .post("/projects")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .form(&[("name", "tool"), ("owner", "acme"), ("health", "1")])
+            .await;
+        assert!(
+            response.status_code().is_client_error(),
+            "{}",
+            response.status_code()
+        );
+        assert!(projects::Entity::find()
+            .all(&ctx.db)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let response = request
+            .post("/projects")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .form(&[("name", "tool"), ("owner", "acme"), ("description", "")])
+            .await;
+        assert_eq!(response.status_code(), 303, "{}", response.text());
+        let project = projects::Entity::find()
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!((project.health - 100.).abs() < f32::EPSILON);
+        assert_eq!(project.description, None);
+
+        let response = request
+            .post(&format!("/projects/{}", project.id))
+            .add_header(auth_key, auth_value)
+            .form(&[
+                ("name", "tool"),
+                ("owner", "acme"),
+                ("description", ""),
+                ("health", "1"),
+                ("last_fetch", "2020-01-01T00:00"),
+            ])
+            .await;
+        assert!(
+            response.status_code().is_client_error(),
+            "{}",
+            response.status_code()
+        );
+        let unchanged = projects::Entity::find_by_id(project.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!((unchanged.health - 100.).abs() < f32::EPSILON);
+        assert_eq!(unchanged.last_fetch, project.last_fetch);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_use_the_forms_from_a_browser() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let form = [("name", "tool"), ("owner", "acme"), ("description", "")];
+
+        // no header and no cookie
+        let response = request.post("/projects").form(&form).await;
+        assert_eq!(response.status_code(), 401);
+        // a form another site sends along with the cookie
+        let response = request
+            .post("/projects")
+            .add_cookie(user.cookie.clone())
+            .form(&form)
+            .await;
+        assert_eq!(response.status_code(), 403);
+        assert!(projects::Entity::find()
+            .all(&ctx.db)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let page = request
+            .get("/projects/new")
+            .add_cookie(user.cookie.clone())
+            .await
+            .text();
+        let csrf_token = prepare_data::csrf_token(&page);
+        let response = request
+            .post("/projects")
+            .add_cookie(user.cookie.clone())
+            .form(&[
+                ("name", "tool"),
+                ("owner", "acme"),
+                ("description", ""),
+                ("csrf_token", &csrf_token),
+            ])
+            .await;
+        assert_eq!(response.status_code(), 303, "{}", response.text());
+        let project = projects::Entity::find()
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            project_members::Entity::role_of(&ctx.db, project.id, user.user.id)
+                .await
+                .unwrap(),
+            Some(project_members::Role::Owner)
+        );
+
+        // the edit page deletes with a script that sends the token as a header
+        let page = request
+            .get(&format!("/projects/{}/edit", project.id))
+            .add_cookie(user.cookie.clone())
+            .await
+            .text();
+        let csrf_token = prepare_data::csrf_token(&page);
+        let response = request
+            .delete(&format!("/projects/{}", project.id))
+            .add_cookie(user.cookie.clone())
+            .add_header("x-csrf-token", &csrf_token)
+            .await;
+        assert_eq!(response.status_code(), 200);
+        assert!(projects::Entity::find()
+            .all(&ctx.db)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // signing out ends the cookie too
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        request
+            .delete("/api/auth/sessions")
+            .add_header(auth_key, auth_value)
+            .await;
+        let response = request
+            .post("/projects")
+            .add_cookie(user.cookie)
+            .form(&[
+                ("name", "tool"),
+                ("owner", "acme"),
+                ("description", ""),
+                ("csrf_token", &csrf_token),
+            ])
+            .await;
+        assert_eq!(response.status_code(), 401);
+    })
+    .await;
+}
@@ -0,0 +1,166 @@
+use gooncityhub::{
+    app::App,
+    models::{
+        _entities::projects,
+        project_members::{self, Role},
+        repos,
+    },
+};
+use loco_rs::{app::AppContext, prelude::*};
+use serial_test::serial;
+
+use super::prepare_data;
+
+async fn create_project(ctx: &AppContext, name: &str, user_id: i32) -> projects::Model {
+    let project = projects::ActiveModel {
+        name: Set(name.to_string()),
+        owner: Set("acme".to_string()),
+        health: Set(100.),
+        last_fetch: Set(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .insert(&ctx.db)
+    .await
+    .unwrap();
+    project_members::Entity::grant(&ctx.db, project.id, user_id, Role::Maintainer)
+        .await
+        .unwrap();
+    project
+}
+
+#[tokio::test]
+#[serial]
+async fn can_show_import_form() {
+    request::<App, _, _>(|request, _ctx| async move {
+        let response = request.get("/repos/new").await;
+
+        assert_eq!(response.status_code(), 200);
+        let body = response.text();
+        assert!(body.contains(r#"name="repo""#));
+        assert!(!body.contains(r#"name="stars""#));
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn import_rejects_invalid_repo() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let response = request
+            .post("/repos")
+            .add_header(auth_key, auth_value)
+            .form(&[
+                ("repo", "https://example.com/acme/tool"),
+                ("project_id", ""),
+            ])
+            .await;
+
+        assert_eq!(response.status_code(), 200);
+        let body = response.text();
+        assert!(body.contains("no forge configured for host"), "{body}");
+        assert!(body.contains(r#"value="https:&#x2F;&#x2F;example.com&#x2F;acme&#x2F;tool""#));
+        assert!(repos::Entity::find().all(&ctx.db).await.unwrap().is_empty());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn import_ignores_posted_stats() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let response = request
+            .post("/repos")
+            .add_header(auth_key, auth_value)
+            .form(&[("repo", "not a repo"), ("stars", "100000")])
+            .await;
+
+        assert_eq!(response.status_code(), 200);
+        assert!(response.text().contains("is not a repository url"));
+        assert!(repos::Entity::find().all(&ctx.db).await.unwrap().is_empty());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn edit_only_moves_the_repo() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let from = create_project(&ctx, "from", user.user.id).await;
+        let to = create_project(&ctx, "to", user.user.id).await;
+        let repo = repos::ActiveModel {
+            project_id: Set(from.id),
+            forge: Set("github".to_string()),
+            host: Set("github.com".to_string()),
+            owner: Set("acme".to_string()),
+            name: Set("tool".to_string()),
+            stars: Set(0),
+            forks: Set(0),
+            issues: Set(0),
+            prs: Set(0),
+            contributors: Set(0),
+            commits_last_30d: Set(0),
+            watchers: Set(0),
+            releases: Set(0),
+            last_fetch: Set(chrono::Utc::now().naive_utc()),
+            ..Default::default()
+        }
+        .insert(&ctx.db)
+        .await
+        .unwrap();
+        from.recalculate_health(&ctx.db).await.unwrap();
+
+        let response = request
+            .post(&format!("/repos/{}", repo.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .form(&[
+                ("project_id", to.id.to_string()),
+                ("stars", "100000".to_string()),
+            ])
+            .await;
+        assert!(
+            response.status_code().is_client_error(),
+            "{}",
+            response.status_code()
+        );
+        let unchanged = repos::Entity::find_by_id(repo.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(unchanged.stars, 0);
+        assert_eq!(unchanged.project_id, from.id);
+
+        let response = request
+            .post(&format!("/repos/{}", repo.id))
+            .add_header(auth_key, auth_value)
+            .form(&[("project_id", to.id.to_string())])
+            .await;
+        assert_eq!(response.status_code(), 303, "{}", response.text());
+        let moved = repos::Entity::find_by_id(repo.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(moved.project_id, to.id);
+        let health = |id| {
+            let db = ctx.db.clone();
+            async move {
+                projects::Entity::find_by_id(id)
+                    .one(&db)
+                    .await
+                    .unwrap()
+                    .unwrap()
+                    .health
+            }
+        };
+        assert!((health(from.id).await - 100.).abs() < f32::EPSILON);
+        assert!(health(to.id).await < 100.);
+    })
+    .await;
+}
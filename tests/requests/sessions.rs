@@ -0,0 +1,126 @@
+use gooncityhub::{app::App, models::users};
+use loco_rs::{testing::prelude::*, TestServer};
+use serde_json::json;
+use serial_test::serial;
+
+use super::prepare_data;
+
+async fn refresh(request: &TestServer, refresh_token: &str) -> (u16, serde_json::Value) {
+    let response = request
+        .post("/api/auth/refresh")
+        .json(&json!({ "refresh_token": refresh_token }))
+        .await;
+    let status = response.status_code().as_u16();
+    let body = if status == 200 {
+        response.json()
+    } else {
+        json!(null)
+    };
+    (status, body)
+}
+
+async fn sessions(request: &TestServer, token: &str) -> Vec<serde_json::Value> {
+    let (auth_key, auth_value) = prepare_data::auth_header(token);
+    let response = request
+        .get("/api/auth/sessions")
+        .add_header(auth_key, auth_value)
+        .await;
+    assert_eq!(response.status_code(), 200);
+    response.json()
+}
+
+/// The status of `/api/auth/current` with the access token `token`.
+async fn current(request: &TestServer, token: &str) -> u16 {
+    let (auth_key, auth_value) = prepare_data::auth_header(token);
+    request
+        .get("/api/auth/current")
+        .add_header(auth_key, auth_value)
+        .await
+        .status_code()
+        .as_u16()
+}
+
+#[tokio::test]
+#[serial]
+async fn refresh_tokens_rotate() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+
+        let (status, refreshed) = refresh(&request, &user.refresh_token).await;
+        assert_eq!(status, 200);
+        let token = refreshed["token"].as_str().unwrap();
+        let new_refresh = refreshed["refresh_token"].as_str().unwrap();
+        assert_ne!(new_refresh, user.refresh_token);
+        assert_eq!(current(&request, token).await, 200);
+
+        // the old token was traded in, using it again ends the session
+        assert_eq!(refresh(&request, &user.refresh_token).await.0, 401);
+        assert_eq!(refresh(&request, new_refresh).await.0, 401);
+        assert_eq!(current(&request, token).await, 401);
+
+        assert_eq!(refresh(&request, "gcr_made-up").await.0, 401);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_list_and_revoke_sessions() {
+    request::<App, _, _>(|request, ctx| async move {
+        let first = prepare_data::init_user_login(&request, &ctx).await;
+        let second = prepare_data::init_user_login(&request, &ctx).await;
+
+        let listed = sessions(&request, &second.token).await;
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0]["current"], true);
+        assert_eq!(listed[1]["current"], false);
+
+        let (auth_key, auth_value) = prepare_data::auth_header(&second.token);
+        let response = request
+            .delete(&format!("/api/auth/sessions/{}", listed[1]["id"]))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(sessions(&request, &second.token).await.len(), 1);
+        assert_eq!(refresh(&request, &first.refresh_token).await.0, 401);
+        // the access token of a revoked session stops working right away
+        assert_eq!(current(&request, &first.token).await, 401);
+        assert_eq!(current(&request, &second.token).await, 200);
+
+        let response = request
+            .delete("/api/auth/sessions")
+            .add_header(auth_key, auth_value)
+            .await;
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(current(&request, &second.token).await, 401);
+        assert_eq!(refresh(&request, &second.refresh_token).await.0, 401);
+        let again = prepare_data::init_user_login(&request, &ctx).await;
+        assert_eq!(sessions(&request, &again.token).await.len(), 1);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn password_reset_ends_sessions() {
+    request::<App, _, _>(|request, ctx| async move {
+        let logged_in = prepare_data::init_user_login(&request, &ctx).await;
+
+        request
+            .post("/api/auth/forgot")
+            .json(&json!({ "email": logged_in.user.email }))
+            .await;
+        let user = users::Model::find_by_email(&ctx.db, &logged_in.user.email)
+            .await
+            .unwrap();
+        let response = request
+            .post("/api/auth/reset")
+            .json(&json!({ "token": user.reset_token, "password": "new-password" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        assert_eq!(refresh(&request, &logged_in.refresh_token).await.0, 401);
+        assert_eq!(current(&request, &logged_in.token).await, 401);
+    })
+    .await;
+}
@@ -0,0 +1,104 @@
+use gooncityhub::{app::App, models::audit_events};
+use loco_rs::{prelude::*, TestServer};
+use serde_json::json;
+use serial_test::serial;
+
+use super::prepare_data;
+
+async fn login(request: &TestServer, password: &str, ip: &str) -> (u16, Option<String>) {
+    let response = request
+        .post("/api/auth/login")
+        .add_header("x-forwarded-for", ip)
+        .json(&json!({
+            "email": prepare_data::USER_EMAIL,
+            "password": password
+        }))
+        .await;
+    let retry_after = response
+        .maybe_header("retry-after")
+        .map(|value| value.to_str().unwrap().to_string());
+    (response.status_code().as_u16(), retry_after)
+}
+
+#[tokio::test]
+#[serial]
+async fn locks_out_accounts_after_failed_logins() {
+    request::<App, _, _>(|request, ctx| async move {
+        prepare_data::init_user_login(&request, &ctx).await;
+
+        for _ in 0..5 {
+            assert_eq!(login(&request, "wrong", "203.0.113.1").await.0, 401);
+        }
+        // locked out, even with the right password and from elsewhere
+        let (status, retry_after) =
+            login(&request, prepare_data::USER_PASSWORD, "203.0.113.2").await;
+        assert_eq!(status, 429);
+        let retry_after: i64 = retry_after.unwrap().parse().unwrap();
+        assert!(retry_after > 0 && retry_after <= 60);
+
+        let lockouts = audit_events::Entity::find()
+            .filter(audit_events::Column::Action.eq(audit_events::AUTH_LOCKOUT))
+            .all(&ctx.db)
+            .await
+            .unwrap();
+        assert_eq!(lockouts.len(), 1);
+        assert_eq!(
+            lockouts[0].target.as_deref(),
+            Some("login:account:test@loco.com")
+        );
+        assert_eq!(lockouts[0].ip.as_deref(), Some("203.0.113.1"));
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn successful_logins_reset_the_count() {
+    request::<App, _, _>(|request, ctx| async move {
+        prepare_data::init_user_login(&request, &ctx).await;
+
+        for _ in 0..2 {
+            for _ in 0..4 {
+                assert_eq!(login(&request, "wrong", "203.0.113.1").await.0, 401);
+            }
+            assert_eq!(
+                login(&request, prepare_data::USER_PASSWORD, "203.0.113.1")
+                    .await
+                    .0,
+                200
+            );
+        }
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn locks_out_ips_trying_many_accounts() {
+    request::<App, _, _>(|request, ctx| async move {
+        prepare_data::init_user_login(&request, &ctx).await;
+
+        for n in 0..20 {
+            let response = request
+                .post("/api/auth/forgot")
+                .add_header("x-forwarded-for", "203.0.113.9")
+                .json(&json!({ "email": format!("user{n}@example.com") }))
+                .await;
+            assert_eq!(response.status_code(), 200);
+        }
+        let response = request
+            .post("/api/auth/forgot")
+            .add_header("x-forwarded-for", "203.0.113.9")
+            .json(&json!({ "email": prepare_data::USER_EMAIL }))
+            .await;
+        assert_eq!(response.status_code(), 429);
+        // other addresses are not affected
+        let response = request
+            .post("/api/auth/forgot")
+            .add_header("x-forwarded-for", "203.0.113.10")
+            .json(&json!({ "email": prepare_data::USER_EMAIL }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+    })
+    .await;
+}
@@ -0,0 +1,217 @@
+use chrono::Utc;
+use gooncityhub::{app::App, totp};
+use loco_rs::{testing::prelude::*, TestServer};
+use serde_json::json;
+use serial_test::serial;
+
+use super::prepare_data;
+
+/// The time step a test starts in. Codes are taken relative to it, so that a
+/// test uses the same codes when it runs into the next step, and more than
+/// one code without waiting.
+fn start_step() -> u64 {
+    totp::step_at(u64::try_from(Utc::now().timestamp()).unwrap())
+}
+
+/// The code of `secret` for `step`.
+fn code(secret: &str, step: u64) -> String {
+    totp::code_at(&totp::base32_decode(secret).unwrap(), step)
+}
+
+async fn post(
+    request: &TestServer,
+    token: &str,
+    path: &str,
+    body: serde_json::Value,
+) -> (u16, serde_json::Value) {
+    let (auth_key, auth_value) = prepare_data::auth_header(token);
+    let response = request
+        .post(&format!("/api/auth/2fa/{path}"))
+        .add_header(auth_key, auth_value)
+        .json(&body)
+        .await;
+    let status = response.status_code().as_u16();
+    let body = if status == 200 {
+        response.json()
+    } else {
+        json!(null)
+    };
+    (status, body)
+}
+
+async fn login(request: &TestServer) -> serde_json::Value {
+    let response = request
+        .post("/api/auth/login")
+        .json(&json!({
+            "email": prepare_data::USER_EMAIL,
+            "password": prepare_data::USER_PASSWORD
+        }))
+        .await;
+    assert_eq!(response.status_code(), 200);
+    response.json()
+}
+
+async fn verify(request: &TestServer, challenge: &str, code: &str) -> (u16, serde_json::Value) {
+    let response = request
+        .post("/api/auth/2fa/verify")
+        .json(&json!({ "challenge": challenge, "code": code }))
+        .await;
+    let status = response.status_code().as_u16();
+    let body = if status == 200 {
+        response.json()
+    } else {
+        json!(null)
+    };
+    (status, body)
+}
+
+/// Turn on two-factor authentication for the logged in user with the code
+/// for `step`, returning the secret and recovery codes.
+async fn enable(request: &TestServer, token: &str, step: u64) -> (String, Vec<String>) {
+    let (status, enrolled) = post(request, token, "enroll", json!({})).await;
+    assert_eq!(status, 200);
+    let secret = enrolled["secret"].as_str().unwrap().to_string();
+    let (status, confirmed) = post(
+        request,
+        token,
+        "confirm",
+        json!({ "code": code(&secret, step) }),
+    )
+    .await;
+    assert_eq!(status, 200);
+    let recovery_codes = serde_json::from_value(confirmed["recovery_codes"].clone()).unwrap();
+    (secret, recovery_codes)
+}
+
+#[tokio::test]
+#[serial]
+async fn can_enroll_and_sign_in_with_a_code() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let step = start_step();
+
+        let (status, enrolled) = post(&request, &user.token, "enroll", json!({})).await;
+        assert_eq!(status, 200);
+        let secret = enrolled["secret"].as_str().unwrap();
+        assert!(enrolled["uri"]
+            .as_str()
+            .unwrap()
+            .starts_with("otpauth://totp/gooncityhub:test@loco.com?secret="));
+        // not on before it is confirmed
+        assert!(login(&request).await["token"].is_string());
+
+        let (status, _) = post(
+            &request,
+            &user.token,
+            "confirm",
+            json!({ "code": "000000" }),
+        )
+        .await;
+        assert_eq!(status, 400);
+        let (status, confirmed) = post(
+            &request,
+            &user.token,
+            "confirm",
+            json!({ "code": code(secret, step) }),
+        )
+        .await;
+        assert_eq!(status, 200);
+        assert_eq!(confirmed["recovery_codes"].as_array().unwrap().len(), 10);
+        let (status, _) = post(&request, &user.token, "enroll", json!({})).await;
+        assert_eq!(status, 400);
+
+        let first_step = login(&request).await;
+        assert_eq!(first_step["two_factor_required"], true);
+        assert!(first_step.get("token").is_none());
+        let challenge = first_step["challenge"].as_str().unwrap();
+
+        // the code used to confirm does not work again
+        assert_eq!(
+            verify(&request, challenge, &code(secret, step)).await.0,
+            401
+        );
+        assert_eq!(
+            verify(&request, "tampered", &code(secret, step + 1))
+                .await
+                .0,
+            401
+        );
+        let (status, signed_in) = verify(&request, challenge, &code(secret, step + 1)).await;
+        assert_eq!(status, 200);
+        let (auth_key, auth_value) =
+            prepare_data::auth_header(signed_in["token"].as_str().unwrap());
+        let response = request
+            .get("/api/auth/current")
+            .add_header(auth_key, auth_value)
+            .await;
+        assert_eq!(response.status_code(), 200);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn recovery_codes_work_once() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let (_, recovery_codes) = enable(&request, &user.token, start_step()).await;
+
+        let challenge = login(&request).await["challenge"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            verify(&request, &challenge, &recovery_codes[0]).await.0,
+            200
+        );
+        assert_eq!(
+            verify(&request, &challenge, &recovery_codes[0]).await.0,
+            401
+        );
+
+        let (status, regenerated) = post(
+            &request,
+            &user.token,
+            "recovery_codes",
+            json!({ "code": recovery_codes[1] }),
+        )
+        .await;
+        assert_eq!(status, 200);
+        assert_eq!(
+            verify(&request, &challenge, &recovery_codes[2]).await.0,
+            401
+        );
+        let fresh = regenerated["recovery_codes"][0].as_str().unwrap();
+        assert_eq!(verify(&request, &challenge, fresh).await.0, 200);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_disable() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let step = start_step();
+        let (secret, _) = enable(&request, &user.token, step).await;
+
+        let (status, _) = post(
+            &request,
+            &user.token,
+            "disable",
+            json!({ "code": "000000" }),
+        )
+        .await;
+        assert_eq!(status, 400);
+        let (status, _) = post(
+            &request,
+            &user.token,
+            "disable",
+            json!({ "code": code(&secret, step + 1) }),
+        )
+        .await;
+        assert_eq!(status, 200);
+        assert!(login(&request).await["token"].is_string());
+    })
+    .await;
+}
@@ -0,0 +1,327 @@
+use axum::http::{HeaderName, HeaderValue};
+use gooncityhub::{
+    app::App,
+    models::_entities::{activity_events, repos},
+};
+use hmac::{Hmac, Mac};
+use loco_rs::{app::AppContext, testing::prelude::*, TestServer};
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, EntityTrait, IntoActiveModel};
+use serde_json::json;
+use serial_test::serial;
+use sha2::Sha256;
+
+const SECRET: &str = "test-webhook-secret";
+
+async fn create_repo(ctx: &AppContext) -> repos::Model {
+    repos::ActiveModel {
+        name: Set("octocrab".to_string()),
+        owner: Set("XAMPPRocky".to_string()),
+        forge: Set("github".to_string()),
+        host: Set("github.com".to_string()),
+        stars: Set(10),
+        forks: Set(1),
+        issues: Set(2),
+        prs: Set(3),
+        contributors: Set(4),
+        commits_last_30d: Set(5),
+        watchers: Set(10),
+        releases: Set(0),
+        last_fetch: Set(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .insert(&ctx.db)
+    .await
+    .unwrap()
+}
+
+fn sign(body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(SECRET.as_bytes()).unwrap();
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+fn header(name: &'static str, value: &str) -> (HeaderName, HeaderValue) {
+    (
+        HeaderName::from_static(name),
+        HeaderValue::from_str(value).unwrap(),
+    )
+}
+
+async fn deliver(
+    request: &TestServer,
+    event: &str,
+    delivery_id: &str,
+    payload: &serde_json::Value,
+) -> serde_json::Value {
+    let body = serde_json::to_vec(payload).unwrap();
+    let (sig_name, sig_value) = header("x-hub-signature-256", &sign(&body));
+    let (event_name, event_value) = header("x-github-event", event);
+    let (delivery_name, delivery_value) = header("x-github-delivery", delivery_id);
+
+    let response = request
+        .post("/api/webhooks/github")
+        .add_header(sig_name, sig_value)
+        .add_header(event_name, event_value)
+        .add_header(delivery_name, delivery_value)
+        .bytes(body.into())
+        .await;
+    assert_eq!(response.status_code(), 200, "{}", response.text());
+    response.json()
+}
+
+fn repository(stars: i32) -> serde_json::Value {
+    json!({
+        "name": "octocrab",
+        "owner": { "login": "XAMPPRocky" },
+        "default_branch": "main",
+        "stargazers_count": stars,
+        "forks_count": 1,
+        "open_issues_count": 2,
+        "watchers_count": stars
+    })
+}
+
+#[tokio::test]
+#[serial]
+async fn rejects_invalid_signature() {
+    request::<App, _, _>(|request, ctx| async move {
+        create_repo(&ctx).await;
+        let (sig_name, sig_value) = header("x-hub-signature-256", "sha256=00");
+        let (event_name, event_value) = header("x-github-event", "star");
+        let (delivery_name, delivery_value) = header("x-github-delivery", "1");
+
+        let response = request
+            .post("/api/webhooks/github")
+            .add_header(sig_name, sig_value)
+            .add_header(event_name, event_value)
+            .add_header(delivery_name, delivery_value)
+            .json(&json!({ "action": "created", "repository": repository(11) }))
+            .await;
+
+        assert_eq!(response.status_code(), 401);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_apply_push_once() {
+    request::<App, _, _>(|request, ctx| async move {
+        let repo = create_repo(&ctx).await;
+        let payload = json!({
+            "ref": "refs/heads/main",
+            "commits": [{ "id": "a" }, { "id": "b" }],
+            "compare": "https://github.com/XAMPPRocky/octocrab/compare/a...b",
+            "repository": repository(10),
+            "sender": { "login": "octocat" }
+        });
+
+        let res = deliver(&request, "push", "delivery-1", &payload).await;
+        assert_eq!(res["status"], "applied");
+        let res = deliver(&request, "push", "delivery-1", &payload).await;
+        assert_eq!(res["status"], "duplicate");
+
+        let repo = repos::Entity::find_by_id(repo.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(repo.commits_last_30d, 7);
+
+        let events = activity_events::Entity::find().all(&ctx.db).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].repo_id, repo.id);
+        assert_eq!(events[0].kind, "push");
+        assert_eq!(events[0].actor.as_deref(), Some("octocat"));
+        assert_eq!(events[0].title.as_deref(), Some("2 commit(s) to main"));
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_apply_repo_events() {
+    request::<App, _, _>(|request, ctx| async move {
+        let repo = create_repo(&ctx).await;
+
+        let pr = json!({
+            "action": "opened",
+            "pull_request": { "title": "Add feature", "html_url": "https://github.com/pr/1" },
+            "repository": repository(10),
+        });
+        deliver(&request, "pull_request", "pr-1", &pr).await;
+        let star = json!({ "action": "created", "repository": repository(11) });
+        deliver(&request, "star", "star-1", &star).await;
+        let release = json!({
+            "action": "published",
+            "release": { "name": null, "tag_name": "v1.0.0" },
+            "repository": repository(11),
+        });
+        deliver(&request, "release", "release-1", &release).await;
+        let push = json!({
+            "ref": "refs/heads/feature",
+            "commits": [{ "id": "a" }],
+            "repository": repository(11),
+        });
+        deliver(&request, "push", "push-1", &push).await;
+
+        let repo = repos::Entity::find_by_id(repo.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(repo.prs, 4);
+        assert_eq!(repo.stars, 11);
+        assert_eq!(repo.releases, 1);
+        // not the default branch
+        assert_eq!(repo.commits_last_30d, 5);
+
+        let events = activity_events::Entity::find().all(&ctx.db).await.unwrap();
+        assert_eq!(events.len(), 4);
+        assert!(events
+            .iter()
+            .any(|e| e.kind == "release" && e.title.as_deref() == Some("v1.0.0")));
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn ignores_untracked_repos_and_events() {
+    request::<App, _, _>(|request, ctx| async move {
+        let mut other = repository(1);
+        other["name"] = json!("unknown");
+
+        let res = deliver(
+            &request,
+            "star",
+            "star-1",
+            &json!({ "action": "created", "repository": other }),
+        )
+        .await;
+        assert_eq!(res["status"], "ignored");
+        let res = deliver(
+            &request,
+            "ping",
+            "ping-1",
+            &json!({ "zen": "Keep it simple." }),
+        )
+        .await;
+        assert_eq!(res["status"], "ignored");
+
+        let events = activity_events::Entity::find().all(&ctx.db).await.unwrap();
+        assert!(events.is_empty());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn follows_renames_and_deletions() {
+    request::<App, _, _>(|request, ctx| async move {
+        let repo = create_repo(&ctx).await;
+
+        let mut transferred = repository(10);
+        transferred["id"] = json!(4242);
+        transferred["owner"] = json!({ "login": "octo-org" });
+        transferred["archived"] = json!(true);
+        let res = deliver(
+            &request,
+            "repository",
+            "r-1",
+            &json!({
+                "action": "transferred",
+                "changes": { "owner": { "from": { "user": { "login": "XAMPPRocky" } } } },
+                "repository": transferred,
+            }),
+        )
+        .await;
+        assert_eq!(res["status"], "applied");
+        let stored = repos::Entity::find_by_id(repo.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.owner, "octo-org");
+        assert_eq!(stored.name, "octocrab");
+        assert_eq!(stored.forge_id, Some(4242));
+        assert!(stored.archived);
+
+        // found by id from now on, whatever the name says
+        let mut deleted = repository(10);
+        deleted["id"] = json!(4242);
+        deleted["name"] = json!("something-else");
+        let res = deliver(
+            &request,
+            "repository",
+            "r-2",
+            &json!({ "action": "deleted", "repository": deleted }),
+        )
+        .await;
+        assert_eq!(res["status"], "applied");
+        let stored = repos::Entity::find_by_id(repo.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.sync_status, "gone");
+
+        let events = activity_events::Entity::find().all(&ctx.db).await.unwrap();
+        assert_eq!(
+            events[0].title.as_deref(),
+            Some("moved from XAMPPRocky/octocrab")
+        );
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn only_applies_to_repos_on_github_com() {
+    request::<App, _, _>(|request, ctx| async move {
+        let mut enterprise = create_repo(&ctx).await.into_active_model();
+        enterprise.host = Set("github.example.com".to_string());
+        enterprise.forge_id = Set(Some(4242));
+        let enterprise = enterprise.update(&ctx.db).await.unwrap();
+
+        let mut starred = repository(11);
+        starred["id"] = json!(4242);
+        let res = deliver(
+            &request,
+            "star",
+            "star-1",
+            &json!({ "action": "created", "repository": starred }),
+        )
+        .await;
+        assert_eq!(res["status"], "ignored");
+
+        // tracked on github.com before its id was known, in other case
+        let mut tracked = create_repo(&ctx).await.into_active_model();
+        tracked.owner = Set("xampprocky".to_string());
+        tracked.name = Set("Octocrab".to_string());
+        let tracked = tracked.update(&ctx.db).await.unwrap();
+        let res = deliver(
+            &request,
+            "star",
+            "star-2",
+            &json!({ "action": "created", "repository": starred }),
+        )
+        .await;
+        assert_eq!(res["status"], "applied");
+
+        let stored = repos::Entity::find_by_id(tracked.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.stars, 11);
+        assert_eq!(stored.forge_id, Some(4242));
+        let untouched = repos::Entity::find_by_id(enterprise.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(untouched.stars, 10);
+    })
+    .await;
+}
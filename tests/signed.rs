@@ -0,0 +1,30 @@
+use chrono::{Duration, Utc};
+use gooncityhub::signed;
+
+const SECRET: &str = "secret";
+
+#[test]
+fn round_trips_fields() {
+    let now = Utc::now();
+    let token = signed::sign(SECRET, "test", &["a", "", "c"], now, 60);
+    assert_eq!(
+        signed::verify(SECRET, "test", &token, now),
+        Some(vec!["a".to_string(), String::new(), "c".to_string()])
+    );
+    let token = signed::sign(SECRET, "test", &[], now, 60);
+    assert_eq!(signed::verify(SECRET, "test", &token, now), Some(vec![]));
+}
+
+#[test]
+fn rejects_tampered_foreign_and_expired_tokens() {
+    let now = Utc::now();
+    let token = signed::sign(SECRET, "test", &["a"], now, 60);
+
+    assert!(signed::verify(SECRET, "test", &token.replacen('a', "b", 1), now).is_none());
+    assert!(signed::verify(SECRET, "other", &token, now).is_none());
+    assert!(signed::verify("other secret", "test", &token, now).is_none());
+    assert!(signed::verify(SECRET, "test", "made.up.0000", now).is_none());
+
+    assert!(signed::verify(SECRET, "test", &token, now + Duration::seconds(60)).is_some());
+    assert!(signed::verify(SECRET, "test", &token, now + Duration::seconds(61)).is_none());
+}
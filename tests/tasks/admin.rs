@@ -0,0 +1,55 @@
+use gooncityhub::{
+    app::App,
+    models::{audit_events, users},
+};
+use loco_rs::{app::AppContext, boot::run_task, prelude::*, task};
+use serial_test::serial;
+
+async fn admin(ctx: &AppContext, args: &[(&str, &str)]) -> Result<()> {
+    let vars = task::Vars::from_cli_args(
+        args.iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect(),
+    );
+    run_task::<App>(ctx, Some(&"admin".to_string()), &vars).await
+}
+
+#[tokio::test]
+#[serial]
+async fn can_grant_and_revoke_admin() {
+    let boot = boot_test::<App>().await.unwrap();
+    let ctx = &boot.app_context;
+    let user = users::Model::create_with_password(
+        &ctx.db,
+        &users::RegisterParams {
+            email: "admin@example.com".to_string(),
+            password: "12341234".to_string(),
+            name: "admin".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+    assert!(!user.is_admin);
+
+    admin(ctx, &[("email", "admin@example.com")]).await.unwrap();
+    let user = users::Model::find_by_email(&ctx.db, "admin@example.com")
+        .await
+        .unwrap();
+    assert!(user.is_admin);
+    let events = audit_events::Entity::find().all(&ctx.db).await.unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].action, audit_events::ADMIN_GRANTED);
+    assert_eq!(events[0].actor_id, None);
+
+    admin(ctx, &[("email", "admin@example.com"), ("revoke", "true")])
+        .await
+        .unwrap();
+    let user = users::Model::find_by_email(&ctx.db, "admin@example.com")
+        .await
+        .unwrap();
+    assert!(!user.is_admin);
+
+    assert!(admin(ctx, &[("email", "nobody@example.com")])
+        .await
+        .is_err());
+}
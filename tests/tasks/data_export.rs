@@ -0,0 +1,50 @@
+use gooncityhub::{
+    app::App,
+    models::{data_exports, users},
+};
+use loco_rs::{app::AppContext, boot::run_task, prelude::*, task};
+use serial_test::serial;
+
+async fn data_export(ctx: &AppContext, args: &[(&str, &str)]) -> Result<()> {
+    let vars = task::Vars::from_cli_args(
+        args.iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect(),
+    );
+    run_task::<App>(ctx, Some(&"data_export".to_string()), &vars).await
+}
+
+#[tokio::test]
+#[serial]
+async fn can_export_a_users_data() {
+    let boot = boot_test::<App>().await.unwrap();
+    let ctx = &boot.app_context;
+    let user = users::Model::create_with_password(
+        &ctx.db,
+        &users::RegisterParams {
+            email: "export@example.com".to_string(),
+            password: "12341234".to_string(),
+            name: "export".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+
+    assert!(data_export(ctx, &[]).await.is_err());
+    data_export(ctx, &[("email", "export@example.com")])
+        .await
+        .unwrap();
+    data_export(ctx, &[("pid", &user.pid.to_string())])
+        .await
+        .unwrap();
+
+    let exports = data_exports::Entity::find().all(&ctx.db).await.unwrap();
+    assert_eq!(exports.len(), 2);
+    for export in exports {
+        assert!(export.is_complete());
+        assert_eq!(
+            export.archive.unwrap()["profile"]["pid"],
+            user.pid.to_string()
+        );
+    }
+}
@@ -0,0 +1,44 @@
+use gooncityhub::{app::App, models::email_domain_rules};
+use loco_rs::{app::AppContext, boot::run_task, task, testing::prelude::*};
+use serial_test::serial;
+
+async fn email_domains(ctx: &AppContext, args: &[(&str, &str)]) {
+    let vars = task::Vars::from_cli_args(
+        args.iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect(),
+    );
+    run_task::<App>(ctx, Some(&"email_domains".to_string()), &vars)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[serial]
+async fn can_override_and_reset_email_domains() {
+    let boot = boot_test::<App>().await.unwrap();
+    let ctx = &boot.app_context;
+
+    email_domains(
+        ctx,
+        &[
+            ("allow", "corp.example, *.corp.example"),
+            ("deny", "gmail.com"),
+        ],
+    )
+    .await;
+    let rules = email_domain_rules::Entity::overrides(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(rules.allow, ["corp.example", "*.corp.example"]);
+    assert_eq!(rules.deny, ["gmail.com"]);
+
+    email_domains(ctx, &[("reset", "true")]).await;
+    assert_eq!(
+        email_domain_rules::Entity::overrides(&ctx.db)
+            .await
+            .unwrap(),
+        None
+    );
+}
@@ -0,0 +1,29 @@
+use gooncityhub::{app::App, models::repos::Entity};
+use loco_rs::{boot::run_task, task, testing::prelude::*};
+use sea_orm::EntityTrait;
+use serial_test::serial;
+
+use crate::forges::fixture_repo::FixtureRepo;
+
+#[tokio::test]
+#[serial]
+async fn can_fetch_local_repo_by_path() {
+    let boot = boot_test::<App>().await.unwrap();
+    let fixture = FixtureRepo::create();
+
+    let vars = task::Vars::from_cli_args(vec![(
+        "path".to_string(),
+        fixture.path.display().to_string(),
+    )]);
+    run_task::<App>(&boot.app_context, Some(&"fetch_repo".to_string()), &vars)
+        .await
+        .unwrap();
+
+    let repos = Entity::find().all(&boot.app_context.db).await.unwrap();
+    assert_eq!(repos.len(), 1);
+    assert_eq!(repos[0].forge, "local");
+    assert_eq!(repos[0].owner, "acme");
+    assert_eq!(repos[0].name, "tool");
+    assert_eq!(repos[0].contributors, 3);
+    assert_eq!(repos[0].releases, 2);
+}
@@ -1 +1,5 @@
-
+mod admin;
+mod data_export;
+mod email_domains;
+mod fetch_repo;
+mod recalculate_health;
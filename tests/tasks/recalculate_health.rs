@@ -0,0 +1,99 @@
+use gooncityhub::{
+    app::App,
+    models::{_entities::repos, projects},
+};
+use loco_rs::{app::AppContext, boot::run_task, prelude::*, task};
+use serial_test::serial;
+
+async fn recalculate_health(ctx: &AppContext, args: &[(&str, &str)]) -> Result<()> {
+    let vars = task::Vars::from_cli_args(
+        args.iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect(),
+    );
+    run_task::<App>(ctx, Some(&"recalculate_health".to_string()), &vars).await
+}
+
+async fn create_project(ctx: &AppContext, name: &str, stars: Option<i32>) -> projects::Model {
+    let project = projects::ActiveModel {
+        name: Set(name.to_string()),
+        owner: Set("acme".to_string()),
+        health: Set(-1.0),
+        last_fetch: Set(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .insert(&ctx.db)
+    .await
+    .unwrap();
+    if let Some(stars) = stars {
+        repos::ActiveModel {
+            project_id: Set(project.id),
+            forge: Set("github".to_string()),
+            host: Set("github.com".to_string()),
+            owner: Set("acme".to_string()),
+            name: Set(name.to_string()),
+            stars: Set(stars),
+            forks: Set(0),
+            issues: Set(0),
+            prs: Set(0),
+            contributors: Set(0),
+            commits_last_30d: Set(0),
+            watchers: Set(0),
+            releases: Set(0),
+            last_fetch: Set(chrono::Utc::now().naive_utc()),
+            ..Default::default()
+        }
+        .insert(&ctx.db)
+        .await
+        .unwrap();
+    }
+    project
+}
+
+#[tokio::test]
+#[serial]
+async fn recalculates_every_project() {
+    let boot = boot_test::<App>().await.unwrap();
+    let ctx = &boot.app_context;
+    let mut created = Vec::new();
+    for (name, stars) in [
+        ("empty", None),
+        ("none", Some(0)),
+        ("some", Some(50)),
+        ("many", Some(100)),
+        ("more", Some(1000)),
+    ] {
+        created.push(create_project(ctx, name, stars).await);
+    }
+
+    // saving repos leaves health alone, it is recalculated by whoever
+    // changes them
+    let stale = projects::Entity::find().all(&ctx.db).await.unwrap();
+    assert!(stale.iter().all(|p| (p.health + 1.0).abs() < f32::EPSILON));
+
+    recalculate_health(ctx, &[("concurrency", "2")])
+        .await
+        .unwrap();
+
+    let mut health = Vec::new();
+    for project in &created {
+        let project = projects::Entity::find_by_id(project.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(project.health_strategy_used.as_deref(), Some("average"));
+        health.push(project.health);
+    }
+    assert_eq!(health, vec![100.0, 0.0, 7.5, 15.0, 15.0]);
+}
+
+#[tokio::test]
+#[serial]
+async fn rejects_bad_concurrency() {
+    let boot = boot_test::<App>().await.unwrap();
+    let err = recalculate_health(&boot.app_context, &[("concurrency", "0")])
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("above zero"), "{err}");
+}
@@ -0,0 +1,265 @@
+use axum::{http::StatusCode, response::IntoResponse, routing::get, Json, Router};
+use chrono::Utc;
+use gooncityhub::{
+    app::App,
+    forges::Forge,
+    models::{
+        _entities::projects,
+        project_members::{self, Role},
+        repos, users,
+    },
+    workers::fetch_repo::{FetchRepoWorker, FetchRepoWorkerArgs},
+};
+use loco_rs::{bgworker::BackgroundWorker, prelude::*};
+use serde_json::json;
+use serial_test::serial;
+
+use crate::forges::stand_in;
+
+async fn repo() -> impl IntoResponse {
+    Json(json!({
+        "id": 7,
+        "name": "forgejo",
+        "owner": { "login": "forgejo" },
+        "stars_count": 300,
+        "forks_count": 40,
+        "watchers_count": 25,
+        "open_issues_count": 9,
+        "open_pr_counter": 6,
+        "release_counter": 11,
+        "licenses": []
+    }))
+}
+
+async fn commits() -> impl IntoResponse {
+    (
+        StatusCode::OK,
+        [("x-total-count", "2")],
+        Json(json!([{ "commit": { "author": { "email": "a@example.com" } } }])),
+    )
+}
+
+/// A context whose only gitea instance is a local stand-in.
+async fn ctx_with_stand_in(ctx: &AppContext) -> AppContext {
+    let base_url = stand_in::serve(
+        Router::new()
+            .route("/api/v1/repos/{owner}/{name}", get(repo))
+            .route("/api/v1/repos/{owner}/{name}/commits", get(commits)),
+    )
+    .await;
+    let mut ctx = ctx.clone();
+    ctx.config.settings = Some(json!({
+        "forges": { "gitea": [{ "base_url": base_url }] }
+    }));
+    ctx
+}
+
+fn args(project_id: Option<i32>) -> FetchRepoWorkerArgs {
+    FetchRepoWorkerArgs {
+        forge: Forge::Gitea,
+        host: "127.0.0.1".to_string(),
+        owner: "forgejo".to_string(),
+        name: "forgejo".to_string(),
+        project_id,
+        user_id: None,
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn can_fetch_repo_into_new_project() {
+    let boot = boot_test::<App>().await.unwrap();
+    let ctx = ctx_with_stand_in(&boot.app_context).await;
+
+    FetchRepoWorker::build(&ctx)
+        .perform(args(None))
+        .await
+        .unwrap();
+
+    let repos = repos::Entity::find().all(&ctx.db).await.unwrap();
+    assert_eq!(repos.len(), 1);
+    assert_eq!(repos[0].forge, "gitea");
+    assert_eq!(repos[0].stars, 300);
+    assert_eq!(repos[0].commits_last_30d, 2);
+    let project = projects::Entity::find_by_id(repos[0].project_id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(project.name, "forgejo");
+}
+
+#[tokio::test]
+#[serial]
+async fn can_fetch_repo_into_existing_project() {
+    let boot = boot_test::<App>().await.unwrap();
+    let ctx = ctx_with_stand_in(&boot.app_context).await;
+    let project = projects::ActiveModel {
+        name: Set("Forges".to_string()),
+        owner: Set("forgejo".to_string()),
+        health: Set(0.0),
+        last_fetch: Set(Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .insert(&ctx.db)
+    .await
+    .unwrap();
+
+    FetchRepoWorker::build(&ctx)
+        .perform(args(Some(project.id)))
+        .await
+        .unwrap();
+
+    let repos = repos::Entity::find().all(&ctx.db).await.unwrap();
+    assert_eq!(repos.len(), 1);
+    assert_eq!(repos[0].project_id, project.id);
+    assert_eq!(
+        projects::Entity::find().all(&ctx.db).await.unwrap().len(),
+        1
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn fetching_rescores_the_project_with_the_saved_repo() {
+    let boot = boot_test::<App>().await.unwrap();
+    let ctx = ctx_with_stand_in(&boot.app_context).await;
+    let project = projects::ActiveModel {
+        name: Set("Forges".to_string()),
+        owner: Set("forgejo".to_string()),
+        health: Set(0.0),
+        last_fetch: Set(Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .insert(&ctx.db)
+    .await
+    .unwrap();
+
+    FetchRepoWorker::build(&ctx)
+        .perform(args(Some(project.id)))
+        .await
+        .unwrap();
+
+    let repo = repos::Entity::find().one(&ctx.db).await.unwrap().unwrap();
+    let project = projects::Entity::find_by_id(project.id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(repo.health() < 100.0);
+    assert!((project.health - repo.health()).abs() < f32::EPSILON);
+}
+
+#[tokio::test]
+#[serial]
+async fn importing_user_owns_new_project() {
+    let boot = boot_test::<App>().await.unwrap();
+    let ctx = ctx_with_stand_in(&boot.app_context).await;
+    let user = users::Model::create_with_password(
+        &ctx.db,
+        &users::RegisterParams {
+            email: "owner@example.com".to_string(),
+            password: "12341234".to_string(),
+            name: "owner".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+
+    FetchRepoWorker::build(&ctx)
+        .perform(FetchRepoWorkerArgs {
+            user_id: Some(user.id),
+            ..args(None)
+        })
+        .await
+        .unwrap();
+
+    let repos = repos::Entity::find().all(&ctx.db).await.unwrap();
+    let role = project_members::Entity::role_of(&ctx.db, repos[0].project_id, user.id)
+        .await
+        .unwrap();
+    assert_eq!(role, Some(Role::Owner));
+}
+
+#[tokio::test]
+#[serial]
+async fn importing_a_tracked_repo_does_not_grant_its_project() {
+    let boot = boot_test::<App>().await.unwrap();
+    let ctx = ctx_with_stand_in(&boot.app_context).await;
+    let user = users::Model::create_with_password(
+        &ctx.db,
+        &users::RegisterParams {
+            email: "owner@example.com".to_string(),
+            password: "12341234".to_string(),
+            name: "owner".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+    // tracked before, e.g. from the CLI, so its project has no members
+    FetchRepoWorker::build(&ctx)
+        .perform(args(None))
+        .await
+        .unwrap();
+
+    FetchRepoWorker::build(&ctx)
+        .perform(FetchRepoWorkerArgs {
+            user_id: Some(user.id),
+            ..args(None)
+        })
+        .await
+        .unwrap();
+
+    let repos = repos::Entity::find().all(&ctx.db).await.unwrap();
+    assert_eq!(repos.len(), 1);
+    let role = project_members::Entity::role_of(&ctx.db, repos[0].project_id, user.id)
+        .await
+        .unwrap();
+    assert_eq!(role, None);
+}
+
+#[tokio::test]
+#[serial]
+async fn importing_does_not_move_a_repo_of_another_project() {
+    let boot = boot_test::<App>().await.unwrap();
+    let ctx = ctx_with_stand_in(&boot.app_context).await;
+    let user = users::Model::create_with_password(
+        &ctx.db,
+        &users::RegisterParams {
+            email: "owner@example.com".to_string(),
+            password: "12341234".to_string(),
+            name: "owner".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+    let mine = projects::ActiveModel {
+        name: Set("Mine".to_string()),
+        owner: Set("me".to_string()),
+        health: Set(0.0),
+        last_fetch: Set(Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .insert(&ctx.db)
+    .await
+    .unwrap();
+    project_members::Entity::grant(&ctx.db, mine.id, user.id, Role::Owner)
+        .await
+        .unwrap();
+    FetchRepoWorker::build(&ctx)
+        .perform(args(None))
+        .await
+        .unwrap();
+
+    let moved = FetchRepoWorker::build(&ctx)
+        .perform(FetchRepoWorkerArgs {
+            user_id: Some(user.id),
+            ..args(Some(mine.id))
+        })
+        .await;
+
+    assert!(moved.is_err());
+    let repo = repos::Entity::find().one(&ctx.db).await.unwrap().unwrap();
+    assert_ne!(repo.project_id, mine.id);
+    assert_eq!(repo.failed_syncs, 0);
+}
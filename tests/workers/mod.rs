@@ -1 +1,2 @@
-
+mod fetch_repo;
+mod sync_repo;
@@ -0,0 +1,130 @@
+use axum::{http::StatusCode, response::IntoResponse, routing::get, Json, Router};
+use chrono::Utc;
+use gooncityhub::{
+    app::App,
+    forges::LocalGit,
+    models::{_entities::projects, repos, sync_runs},
+    workers::sync_repo::{SyncRepoWorker, SyncRepoWorkerArgs},
+};
+use loco_rs::{bgworker::BackgroundWorker, prelude::*};
+use serde_json::json;
+use serial_test::serial;
+
+use crate::forges::{fixture_repo::FixtureRepo, stand_in};
+
+async fn repo() -> impl IntoResponse {
+    Json(json!({
+        "id": 7,
+        "name": "forgejo",
+        "owner": { "login": "forgejo" },
+        "stars_count": 300,
+        "forks_count": 40,
+        "watchers_count": 25,
+        "open_issues_count": 9,
+        "open_pr_counter": 6,
+        "release_counter": 11,
+        "licenses": []
+    }))
+}
+
+async fn commits() -> impl IntoResponse {
+    (
+        StatusCode::OK,
+        [("x-total-count", "2")],
+        Json(json!([{ "commit": { "author": { "email": "a@example.com" } } }])),
+    )
+}
+
+#[tokio::test]
+#[serial]
+async fn can_sync_tracked_repo() {
+    let boot = boot_test::<App>().await.unwrap();
+    let base_url = stand_in::serve(
+        Router::new()
+            .route("/api/v1/repos/{owner}/{name}", get(repo))
+            .route("/api/v1/repos/{owner}/{name}/commits", get(commits)),
+    )
+    .await;
+    let mut ctx = boot.app_context.clone();
+    ctx.config.settings = Some(json!({
+        "forges": { "gitea": [{ "base_url": base_url }] }
+    }));
+    let project = projects::ActiveModel {
+        name: Set("Forges".to_string()),
+        owner: Set("forgejo".to_string()),
+        health: Set(0.0),
+        last_fetch: Set(Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .insert(&ctx.db)
+    .await
+    .unwrap();
+    let tracked = repos::ActiveModel {
+        project_id: Set(project.id),
+        forge: Set("gitea".to_string()),
+        host: Set("127.0.0.1".to_string()),
+        owner: Set("forgejo".to_string()),
+        name: Set("forgejo".to_string()),
+        stars: Set(0),
+        forks: Set(0),
+        issues: Set(0),
+        prs: Set(0),
+        contributors: Set(0),
+        commits_last_30d: Set(0),
+        watchers: Set(0),
+        releases: Set(0),
+        last_fetch: Set(Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .insert(&ctx.db)
+    .await
+    .unwrap();
+
+    SyncRepoWorker::build(&ctx)
+        .perform(SyncRepoWorkerArgs {
+            repo_id: tracked.id,
+        })
+        .await
+        .unwrap();
+
+    let synced = repos::Entity::find_by_id(tracked.id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(synced.stars, 300);
+    assert_eq!(synced.commits_last_30d, 2);
+    let runs = sync_runs::Entity::find().all(&ctx.db).await.unwrap();
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0].repo_id, Some(tracked.id));
+}
+
+#[tokio::test]
+#[serial]
+async fn can_sync_local_repo_from_its_path() {
+    let boot = boot_test::<App>().await.unwrap();
+    let ctx = boot.app_context.clone();
+    let fixture = FixtureRepo::create();
+    let tracked = repos::Entity::fetch(&LocalGit::new(&fixture.path), "acme", "tool", &ctx.db)
+        .await
+        .unwrap();
+    assert!(tracked.local_path.is_some());
+
+    SyncRepoWorker::build(&ctx)
+        .perform(SyncRepoWorkerArgs {
+            repo_id: tracked.id,
+        })
+        .await
+        .unwrap();
+
+    let synced = repos::Entity::find_by_id(tracked.id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(synced.commits_last_30d, 3);
+    assert_eq!(synced.local_path, tracked.local_path);
+    let runs = sync_runs::Entity::find().all(&ctx.db).await.unwrap();
+    assert_eq!(runs.len(), 2);
+    assert!(runs.iter().all(|run| run.outcome == "ok"));
+}